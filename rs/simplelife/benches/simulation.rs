@@ -0,0 +1,150 @@
+//! Timings for the hot paths. Run with `cargo bench`, optionally with
+//! `--features parallel` or a filter like `cargo bench -- potential/fft`.
+
+use std::hint::black_box;
+use std::sync::Arc;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use simplelife::{Boundary, ConvMethod, GaussianGrowth, GrowthRule, Palette, QuantizedLife, Real, SimpleLife};
+
+const SIZES: [usize; 3] = [256, 512, 1024];
+const RADII: [usize; 3] = [5, 13, 25];
+const SEED: u64 = 42;
+
+fn seeded(size: usize, radius: usize, method: ConvMethod) -> SimpleLife {
+    let mut sim = SimpleLife::builder()
+        .size(size, size)
+        .kernel_radius(radius)
+        .conv_method(method)
+        .seed(SEED)
+        .build()
+        .unwrap();
+    sim.random_init_seeded(0.3, 0.3, SEED);
+    sim
+}
+
+fn potential(c: &mut Criterion) {
+    let mut group = c.benchmark_group("potential");
+    // The direct convolution at 1024x1024, radius 25 takes a good fraction of a second
+    group.sample_size(10);
+
+    for (name, method) in [("direct", ConvMethod::Direct), ("fft", ConvMethod::Fft)] {
+        for size in SIZES {
+            for radius in RADII {
+                let sim = seeded(size, radius, method);
+                let id = BenchmarkId::new(name, format!("{}x{}/r{}", size, size, radius));
+                group.bench_function(id, |b| b.iter(|| black_box(sim.compute_potential())));
+            }
+        }
+    }
+    group.finish();
+}
+
+// The direct path at each boundary; only the halo copied around each block
+// differs between them
+fn boundary(c: &mut Criterion) {
+    let mut group = c.benchmark_group("boundary");
+    group.sample_size(10);
+
+    for boundary in [Boundary::Toroidal, Boundary::Fixed, Boundary::Reflective] {
+        let mut sim = seeded(256, 13, ConvMethod::Direct);
+        sim.set_boundary(boundary);
+        let id = BenchmarkId::from_parameter(format!("{:?}", boundary).to_lowercase());
+        group.bench_function(id, |b| b.iter(|| black_box(sim.compute_potential())));
+    }
+    group.finish();
+}
+
+fn update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update");
+    group.sample_size(10);
+
+    for size in SIZES {
+        for radius in RADII {
+            let mut sim = seeded(size, radius, ConvMethod::Fft);
+            let id = BenchmarkId::from_parameter(format!("{}x{}/r{}", size, size, radius));
+            group.bench_function(id, |b| b.iter(|| black_box(sim.update())));
+        }
+    }
+    group.finish();
+}
+
+// A fixed-point step against the floating-point direct one it approximates
+fn quantized(c: &mut Criterion) {
+    let mut group = c.benchmark_group("quantized");
+    group.sample_size(10);
+
+    for size in SIZES {
+        let sim = seeded(size, 13, ConvMethod::Direct);
+        let id = format!("{}x{}/r13", size, size);
+        // Both step the whole grid
+        let mut float = sim.clone();
+        float.set_activity_tracking(false);
+        group.bench_function(BenchmarkId::new("real", &id), |b| b.iter(|| black_box(float.update())));
+        let mut fixed = QuantizedLife::new(sim);
+        group.bench_function(BenchmarkId::new("u16", &id), |b| b.iter(|| fixed.update().unwrap()));
+    }
+    group.finish();
+}
+
+// Rendering into a fresh buffer, into a reused one, and a quarter of the grid zoomed 2x
+fn create_buffer(c: &mut Criterion) {
+    let mut group = c.benchmark_group("create_buffer");
+
+    for size in SIZES {
+        let sim = seeded(size, 13, ConvMethod::Fft);
+        let mut buffer = vec![0; size * size];
+        group.bench_function(BenchmarkId::new("new", format!("{}x{}", size, size)), |b| {
+            b.iter(|| black_box(sim.create_buffer()))
+        });
+        group.bench_function(BenchmarkId::new("into", format!("{}x{}", size, size)), |b| {
+            b.iter(|| sim.create_buffer_into(Palette::Blue, black_box(&mut buffer)))
+        });
+        group.bench_function(BenchmarkId::new("region", format!("{}x{}", size, size)), |b| {
+            b.iter(|| black_box(sim.create_buffer_region(size / 4, size / 4, size / 2, size / 2, 2)))
+        });
+    }
+    group.finish();
+}
+
+// One small blob on a large grid, with and without skipping the dead tiles
+fn activity(c: &mut Criterion) {
+    let mut group = c.benchmark_group("activity");
+    group.sample_size(10);
+
+    for (name, tracking) in [("tracked", true), ("full", false)] {
+        let mut sim = SimpleLife::builder()
+            .size(800, 800)
+            .kernel_radius(13)
+            .conv_method(ConvMethod::Direct)
+            .seed(SEED)
+            .build()
+            .unwrap();
+        sim.set_activity_tracking(tracking);
+        sim.random_init_seeded(0.05, 0.5, SEED);
+        group.bench_function(name, |b| b.iter(|| black_box(sim.update())));
+    }
+    group.finish();
+}
+
+// The growth pass alone: a sampled table against calling the bell directly
+fn growth(c: &mut Criterion) {
+    let mut group = c.benchmark_group("growth");
+    let bell = GaussianGrowth::new(0.15, 0.015).unwrap();
+    let rules = [
+        ("gaussian", GrowthRule::Function(Arc::new(move |u| bell.value(u)))),
+        ("table", GrowthRule::from_samples(|u| bell.value(u), 1024)),
+    ];
+
+    let sim = seeded(512, 13, ConvMethod::Fft);
+    let potential = sim.compute_potential();
+    for (name, rule) in rules {
+        group.bench_function(name, |b| {
+            b.iter(|| potential.iter().map(|&u| rule.value(u, 0.0)).sum::<Real>())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, potential, boundary, update, quantized, create_buffer, activity, growth);
+criterion_main!(benches);
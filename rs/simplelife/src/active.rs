@@ -0,0 +1,105 @@
+//! Which parts of the grid the potential can be non-zero in, so the direct
+//! convolution can skip the dead rest.
+
+use std::ops::Range;
+
+use crate::{Boundary, Grid};
+
+/// Side of the square tiles activity is tracked in.
+pub(crate) const ACTIVE_TILE: usize = 32;
+
+/// Tiles holding a non-zero cell, grown by the kernel extent and kept as
+/// column spans per row of tiles. Outside the spans every kernel window only
+/// covers zeros, so the potential there is exactly 0.
+#[derive(Clone, Default)]
+pub(crate) struct ActiveTiles {
+    live: Vec<bool>,
+    needed: Vec<bool>,
+    spans: Vec<Vec<Range<usize>>>,
+}
+
+impl ActiveTiles {
+    /// Bytes held by the tile flags and spans.
+    pub(crate) fn memory_bytes(&self) -> usize {
+        (self.live.capacity() + self.needed.capacity()) * std::mem::size_of::<bool>()
+            + self.spans.capacity() * std::mem::size_of::<Vec<Range<usize>>>()
+            + self.spans.iter().map(|spans| spans.capacity() * std::mem::size_of::<Range<usize>>()).sum::<usize>()
+    }
+
+    /// Recompute the spans for `grid` and a kernel reaching `(rx, ry)` cells from its center.
+    pub(crate) fn update(&mut self, grid: &Grid, (rx, ry): (usize, usize), boundary: Boundary) {
+        let (width, height) = (grid.width(), grid.height());
+        let (tiles_x, tiles_y) = (width.div_ceil(ACTIVE_TILE), height.div_ceil(ACTIVE_TILE));
+
+        self.live.clear();
+        self.live.resize(tiles_x * tiles_y, false);
+        for y in 0..height {
+            let live = &mut self.live[y / ACTIVE_TILE * tiles_x..(y / ACTIVE_TILE + 1) * tiles_x];
+            for (tile, cells) in live.iter_mut().zip(grid.row(y).chunks(ACTIVE_TILE)) {
+                if !*tile && cells.iter().any(|&v| v != 0.0) {
+                    *tile = true;
+                }
+            }
+        }
+
+        // A live cell at x feeds the potential at x - rx..=x + rx, seen through the boundary
+        self.needed.clear();
+        self.needed.resize(tiles_x * tiles_y, false);
+        for (i, _) in self.live.iter().enumerate().filter(|&(_, &live)| live) {
+            let columns = reach(i % tiles_x, rx, width, boundary);
+            for ty in reach(i / tiles_x, ry, height, boundary).into_iter().flatten() {
+                for tx in columns.iter().cloned().flatten() {
+                    self.needed[ty * tiles_x + tx] = true;
+                }
+            }
+        }
+
+        self.spans.resize_with(tiles_y, Vec::new);
+        self.spans.truncate(tiles_y);
+        for (spans, needed) in self.spans.iter_mut().zip(self.needed.chunks(tiles_x)) {
+            spans.clear();
+            let mut tx = 0;
+            while tx < tiles_x {
+                if !needed[tx] {
+                    tx += 1;
+                    continue;
+                }
+                let start = tx;
+                while tx < tiles_x && needed[tx] {
+                    tx += 1;
+                }
+                spans.push(start * ACTIVE_TILE..(tx * ACTIVE_TILE).min(width));
+            }
+        }
+    }
+
+    /// Column spans of row `y` where the potential has to be computed.
+    pub(crate) fn spans(&self, y: usize) -> &[Range<usize>] {
+        &self.spans[y / ACTIVE_TILE]
+    }
+}
+
+// Tiles along one axis of `size` cells whose cells lie within `r` of tile `t`,
+// as one range or two when it wraps around the torus
+fn reach(t: usize, r: usize, size: usize, boundary: Boundary) -> [Range<usize>; 2] {
+    let tiles = size.div_ceil(ACTIVE_TILE);
+    let start = (t * ACTIVE_TILE) as isize - r as isize;
+    let end = ((t + 1) * ACTIVE_TILE).min(size) as isize + r as isize;
+
+    // Fixed edges read zeros and reflective ones mirror cells that are already
+    // within reach, so both only need clipping
+    if boundary != Boundary::Toroidal || (start >= 0 && end <= size as isize) {
+        let (first, last) = (start.max(0) as usize, end.min(size as isize) as usize - 1);
+        return [first / ACTIVE_TILE..last / ACTIVE_TILE + 1, 0..0];
+    }
+    if end - start >= size as isize {
+        return [0..tiles, 0..0];
+    }
+
+    let (first, last) = (start.rem_euclid(size as isize) as usize, (end - 1).rem_euclid(size as isize) as usize);
+    if first <= last {
+        [first / ACTIVE_TILE..last / ACTIVE_TILE + 1, 0..0]
+    } else {
+        [first / ACTIVE_TILE..tiles, 0..last / ACTIVE_TILE + 1]
+    }
+}
@@ -0,0 +1,330 @@
+//! Every convolution backend against a brute-force reference.
+//!
+//! The backends are separate code paths for the same potential, so a bug in
+//! one of them shows up as a simulation that quietly drifts away from the
+//! others. [`BackendMatrix::run`] steps each of them once on seeded grids of
+//! several sizes, densities, kernel radii and boundaries, and compares the
+//! cells with the same step done by a plain loop over the kernel table.
+
+use std::fmt;
+
+use crate::{Boundary, ConvMethod, Kernel, KernelShape, QuantizedLife, Real, SimpleLife};
+
+/// A code path that computes the potential.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// The direct convolution over the non-zero kernel entries, on every cell.
+    Blocked,
+    /// The direct convolution limited to the tiles near live cells.
+    ActiveTiles,
+    /// A row pass and a column pass, for a kernel that is an outer product.
+    Separable,
+    /// The FFT, which only knows toroidal wrapping.
+    Fft,
+    /// The compute shader behind `GpuLife`, with the `gpu` feature.
+    Gpu,
+    /// The fixed-point stepper of [`QuantizedLife`].
+    Quantized,
+}
+
+impl Backend {
+    /// Every backend, in the order the matrix reports them.
+    pub const ALL: [Backend; 6] = [
+        Backend::Blocked,
+        Backend::ActiveTiles,
+        Backend::Separable,
+        Backend::Fft,
+        Backend::Gpu,
+        Backend::Quantized,
+    ];
+
+    /// Largest difference from the reference any cell may show after a step.
+    ///
+    /// The floating-point paths only add up the same products in a different
+    /// order, and the GPU also looks the growth up in a sampled table; they
+    /// stay within about 1e-7 of the reference. The fixed-point path rounds
+    /// cells, weights and changes to whole levels, which comes to about 4e-5.
+    pub fn tolerance(self) -> Real {
+        match self {
+            Backend::Quantized => 1e-4,
+            _ => 1e-6,
+        }
+    }
+
+    // Step a copy of `sim` once on this backend and return the cells, or why
+    // the backend can't run here
+    fn step(self, sim: &SimpleLife) -> Result<Vec<Real>, Skip> {
+        let mut sim = sim.clone();
+        match self {
+            Backend::Blocked | Backend::ActiveTiles | Backend::Separable | Backend::Fft => {
+                sim.set_conv_method(if self == Backend::Fft { ConvMethod::Fft } else { ConvMethod::Direct });
+                sim.set_activity_tracking(self == Backend::ActiveTiles);
+                if self == Backend::Fft && !sim.uses_fft() {
+                    return Err(Skip::Unavailable("the FFT only wraps toroidally".to_string()));
+                }
+                if sim.is_separable() != (self == Backend::Separable) {
+                    return Err(Skip::Failed(format!("the kernel {} factor", if sim.is_separable() { "did" } else { "did not" })));
+                }
+                if self == Backend::ActiveTiles && !sim.skips_dead_cells() {
+                    return Err(Skip::Failed("the step did not use the active tiles".to_string()));
+                }
+                sim.update();
+                Ok(sim.grid().iter().copied().collect())
+            }
+            Backend::Gpu => gpu_step(sim),
+            Backend::Quantized => {
+                let mut quantized = QuantizedLife::new(sim);
+                quantized.update().map_err(|e| Skip::Failed(e.to_string()))?;
+                Ok(quantized.into_sim().grid().iter().copied().collect())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "gpu")]
+fn gpu_step(sim: SimpleLife) -> Result<Vec<Real>, Skip> {
+    // No adapter is a machine without a GPU, anything after that is a failure
+    let mut gpu = crate::GpuLife::new(sim).map_err(|e| Skip::Unavailable(e.to_string()))?;
+    gpu.update().map_err(|e| Skip::Failed(e.to_string()))?;
+    let sim = gpu.into_sim().map_err(|e| Skip::Failed(e.to_string()))?;
+    Ok(sim.grid().iter().copied().collect())
+}
+
+#[cfg(not(feature = "gpu"))]
+fn gpu_step(_sim: SimpleLife) -> Result<Vec<Real>, Skip> {
+    Err(Skip::Unavailable("built without the gpu feature".to_string()))
+}
+
+enum Skip {
+    Unavailable(String),
+    Failed(String),
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // `pad` so the table can line the names up
+        f.pad(match self {
+            Backend::Blocked => "blocked",
+            Backend::ActiveTiles => "active-tiles",
+            Backend::Separable => "separable",
+            Backend::Fft => "fft",
+            Backend::Gpu => "gpu",
+            Backend::Quantized => "quantized",
+        })
+    }
+}
+
+/// One seeded grid the backends are stepped on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatrixCase {
+    pub width: usize,
+    pub height: usize,
+    pub radius: usize,
+    /// Cells all over the grid rather than a blob in the middle of an empty one.
+    pub dense: bool,
+    pub boundary: Boundary,
+    pub seed: u64,
+}
+
+impl MatrixCase {
+    /// Small and large grids, sparse and dense, kernel radius 1, 5, 13 and 40
+    /// wherever it fits, each with every boundary.
+    pub fn suite() -> Vec<MatrixCase> {
+        let mut cases = Vec::new();
+        for (width, height) in [(48, 40), (112, 84)] {
+            for dense in [false, true] {
+                for radius in [1, 5, 13, 40] {
+                    if 2 * radius + 1 > width.min(height) {
+                        continue;
+                    }
+                    for boundary in [Boundary::Toroidal, Boundary::Fixed, Boundary::Reflective] {
+                        let seed = cases.len() as u64;
+                        cases.push(MatrixCase { width, height, radius, dense, boundary, seed });
+                    }
+                }
+            }
+        }
+        cases
+    }
+
+    // The seeded simulation, with the square tent the separable path needs
+    // or Lenia's ring for everything else; unlike the default cone the ring
+    // doesn't shrink to a single, separable cell at radius 1
+    fn build(&self, separable: bool) -> SimpleLife {
+        let builder = SimpleLife::builder()
+            .size(self.width, self.height)
+            .kernel_radius(self.radius)
+            .boundary(self.boundary)
+            .dt(0.1);
+        let builder = if separable {
+            builder.kernel(SquareTent)
+        } else {
+            builder.kernel_shape(KernelShape::Gaussian { mu: 0.5, sigma: 0.15 })
+        };
+        let mut sim = builder.build().expect("matrix cases fit their grids");
+        let (radius, density) = if self.dense { (1.0, 0.5) } else { (0.2, 0.3) };
+        sim.random_init_seeded(radius, density, self.seed);
+        sim
+    }
+}
+
+impl fmt::Display for MatrixCase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{} r{} {} {:?}", self.width, self.height, self.radius,
+               if self.dense { "dense" } else { "sparse" }, self.boundary)
+    }
+}
+
+// A tent over the whole square table, `(r + 1 - |dx|) (r + 1 - |dy|)`; the
+// weights are whole numbers, so it is exactly a row times a column in either
+// precision
+struct SquareTent;
+
+impl Kernel for SquareTent {
+    fn weight(&self, dx: f32, dy: f32, radius: f32) -> f32 {
+        (radius + 1.0 - dx.abs()) * (radius + 1.0 - dy.abs())
+    }
+}
+
+/// How a backend compared to the reference on one case.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Comparison {
+    /// Largest and root-mean-square difference of the cells after one step.
+    Compared { max: Real, rms: Real },
+    /// The backend doesn't exist on this machine or for this case.
+    Skipped(String),
+    /// The backend should have run but didn't.
+    Failed(String),
+}
+
+/// One backend on one case.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatrixEntry {
+    pub backend: Backend,
+    pub case: MatrixCase,
+    pub comparison: Comparison,
+}
+
+impl MatrixEntry {
+    /// Whether the backend ran and stayed within its tolerance, or was skipped.
+    pub fn passed(&self) -> bool {
+        match self.comparison {
+            Comparison::Compared { max, .. } => max <= self.backend.tolerance(),
+            Comparison::Skipped(_) => true,
+            Comparison::Failed(_) => false,
+        }
+    }
+}
+
+/// Every backend on every case, printed as a table by its `Display`.
+pub struct BackendMatrix {
+    pub entries: Vec<MatrixEntry>,
+}
+
+impl BackendMatrix {
+    /// Step every backend once on each case and compare it to the reference.
+    pub fn run(cases: &[MatrixCase]) -> Self {
+        let mut entries = Vec::new();
+        for case in cases {
+            // The reference is by far the slowest part, so each kernel gets it once
+            let [ring, square] = [false, true].map(|separable| {
+                let sim = case.build(separable);
+                let reference = reference_step(&sim);
+                (sim, reference)
+            });
+            for backend in Backend::ALL {
+                let (sim, reference) = if backend == Backend::Separable { &square } else { &ring };
+                let comparison = match backend.step(sim) {
+                    Ok(cells) => compare(&cells, reference),
+                    Err(Skip::Unavailable(reason)) => Comparison::Skipped(reason),
+                    Err(Skip::Failed(reason)) => Comparison::Failed(reason),
+                };
+                entries.push(MatrixEntry { backend, case: *case, comparison });
+            }
+        }
+        BackendMatrix { entries }
+    }
+
+    /// Whether no entry failed.
+    pub fn passed(&self) -> bool {
+        self.entries.iter().all(MatrixEntry::passed)
+    }
+
+    /// The entries that failed.
+    pub fn failures(&self) -> impl Iterator<Item = &MatrixEntry> {
+        self.entries.iter().filter(|entry| !entry.passed())
+    }
+}
+
+impl fmt::Display for BackendMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<13} {:<28} {:>10} {:>10} {:>10}", "backend", "case", "max", "rms", "tolerance")?;
+        for entry in &self.entries {
+            let (backend, case, tolerance) = (entry.backend, entry.case.to_string(), entry.backend.tolerance());
+            match &entry.comparison {
+                Comparison::Compared { max, rms } => writeln!(f, "{:<13} {:<28} {:>10.2e} {:>10.2e} {:>10.0e}{}",
+                    backend, case, max, rms, tolerance, if entry.passed() { "" } else { "  FAIL" })?,
+                Comparison::Skipped(reason) => writeln!(f, "{:<13} {:<28} skipped: {}", backend, case, reason)?,
+                Comparison::Failed(reason) => writeln!(f, "{:<13} {:<28} FAIL: {}", backend, case, reason)?,
+            }
+        }
+
+        let count = |pick: fn(&Comparison) -> bool| self.entries.iter().filter(|entry| pick(&entry.comparison)).count();
+        write!(f, "{} compared, {} skipped, {} failed",
+               count(|c| matches!(c, Comparison::Compared { .. })),
+               count(|c| matches!(c, Comparison::Skipped(_))),
+               self.failures().count())
+    }
+}
+
+// One step by the book: the grid is padded once by the kernel extent through
+// the boundary, then every cell sums every non-zero kernel entry times the
+// padded cell under it in f64 and grows with a hard clamp. The casts to f64
+// are no-ops with the f64 feature
+#[allow(clippy::unnecessary_cast)]
+fn reference_step(sim: &SimpleLife) -> Vec<Real> {
+    let (width, height) = (sim.width(), sim.height());
+    let (rx, ry) = sim.kernel_extent;
+    let (padded_width, padded_height) = (width + 2 * rx, height + 2 * ry);
+    let mut padded = vec![0.0_f64; padded_width * padded_height];
+    for py in 0..padded_height {
+        for px in 0..padded_width {
+            let gy = sim.boundary.resolve(py as isize - ry as isize, height as isize);
+            let gx = sim.boundary.resolve(px as isize - rx as isize, width as isize);
+            if let (Some(gx), Some(gy)) = (gx, gy) {
+                padded[py * padded_width + px] = sim.grid[(gx, gy)] as f64;
+            }
+        }
+    }
+    let entries: Vec<(usize, f64)> = sim.kernel().iter().enumerate()
+        .filter(|&(_, &k)| k != 0.0)
+        .map(|(i, &k)| ((i / (2 * rx + 1)) * padded_width + i % (2 * rx + 1), k as f64))
+        .collect();
+
+    let mut cells = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let corner = y * padded_width + x;
+            let sum: f64 = entries.iter().map(|&(offset, k)| k * padded[corner + offset]).sum();
+            let cell = sim.grid[(x, y)] + sim.dt() * sim.growth_function(sum as Real);
+            cells.push(cell.clamp(0.0, 1.0));
+        }
+    }
+    cells
+}
+
+// Largest and root-mean-square difference; the cast is a no-op with the f64 feature
+#[allow(clippy::unnecessary_cast)]
+fn compare(cells: &[Real], reference: &[Real]) -> Comparison {
+    let (mut max, mut squares) = (0.0 as Real, 0.0_f64);
+    for (&a, &b) in cells.iter().zip(reference) {
+        let d = (a - b).abs();
+        // Once NaN, `max` stays NaN and fails the tolerance
+        if d.is_nan() || d > max {
+            max = d;
+        }
+        squares += d as f64 * d as f64;
+    }
+    let rms = (squares / reference.len() as f64).sqrt() as Real;
+    Comparison::Compared { max, rms }
+}
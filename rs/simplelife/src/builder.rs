@@ -0,0 +1,357 @@
+//! Validated construction of a [`SimpleLife`].
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use crate::active::ActiveTiles;
+use crate::conv::Scratch;
+use crate::{Anisotropic, Boundary, Clamp, ConvMethod, GaussianGrowth, Grid, GrowthPreset, GrowthRule, Kernel, KernelFn, KernelShape, Normalization, Real, SimpleLife, WithRadius};
+
+/// Why a [`SimpleLifeBuilder`] refused to build a simulation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildError {
+    /// The grid has no cells.
+    ZeroSize { width: usize, height: usize },
+    /// A kernel radius of 0 has no neighborhood to convolve.
+    ZeroRadius,
+    /// The kernel must be smaller than half the smallest grid dimension.
+    RadiusTooLarge { radius: usize, max: usize },
+    /// A stretched kernel's table must still fit in half the grid along each axis.
+    ExtentTooLarge { extent: (usize, usize), max: (usize, usize) },
+    /// The kernel shape parameters are invalid.
+    InvalidKernel(String),
+    /// The growth preset parameters are invalid.
+    InvalidGrowth(String),
+    /// dt must be a finite number.
+    NonFiniteDt(Real),
+    /// Negative dt was given without opting in through `allow_negative_dt`.
+    NegativeDt(Real),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::ZeroSize { width, height } =>
+                write!(f, "grid size {}x{} has no cells", width, height),
+            BuildError::ZeroRadius =>
+                write!(f, "kernel radius must be at least 1"),
+            BuildError::RadiusTooLarge { radius, max } =>
+                write!(f, "kernel radius {} is too large for the grid, the maximum is {}", radius, max),
+            BuildError::ExtentTooLarge { extent, max } =>
+                write!(f, "kernel reaches {}x{} cells from its center, the grid allows at most {}x{}",
+                       extent.0, extent.1, max.0, max.1),
+            BuildError::InvalidKernel(msg) =>
+                write!(f, "invalid kernel: {}", msg),
+            BuildError::InvalidGrowth(msg) =>
+                write!(f, "invalid growth curve: {}", msg),
+            BuildError::NonFiniteDt(dt) =>
+                write!(f, "dt must be a finite number, got {}", dt),
+            BuildError::NegativeDt(dt) =>
+                write!(f, "dt {} is negative; negative dt has to be allowed explicitly", dt),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Builder for [`SimpleLife`]; unset fields fall back to the defaults of the
+/// interactive binary (400x400, radius 13, dt 0.05, unseeded).
+#[derive(Clone)]
+pub struct SimpleLifeBuilder {
+    width: usize,
+    height: usize,
+    kernel_radius: usize,
+    kernel_fn: KernelFn,
+    extra_kernels: Vec<(KernelFn, Real)>,
+    normalize_kernel_weights: bool,
+    anisotropy: Option<(f32, f32)>,
+    normalization: Normalization,
+    kernel_supersampling: usize,
+    dt: Real,
+    conv_method: ConvMethod,
+    boundary: Boundary,
+    clamp: Clamp,
+    allow_negative_dt: bool,
+    seed: Option<u64>,
+    growth: GrowthRule,
+    // Kept to validate at build time; None for custom closures
+    growth_preset: Option<GrowthPreset>,
+}
+
+impl Default for SimpleLifeBuilder {
+    fn default() -> Self {
+        SimpleLifeBuilder {
+            width: 400,
+            height: 400,
+            kernel_radius: 13,
+            kernel_fn: Arc::new(KernelShape::Linear),
+            extra_kernels: Vec::new(),
+            normalize_kernel_weights: false,
+            anisotropy: None,
+            normalization: Normalization::SumToOne,
+            kernel_supersampling: 1,
+            dt: 0.05,
+            conv_method: ConvMethod::Auto,
+            boundary: Boundary::Toroidal,
+            clamp: Clamp::Hard,
+            allow_negative_dt: false,
+            seed: None,
+            growth: GrowthRule::Quadratic { a: 1.8, b: 0.2 },
+            growth_preset: None,
+        }
+    }
+}
+
+impl SimpleLifeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Grid dimensions in cells.
+    pub fn size(mut self, width: usize, height: usize) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn kernel_radius(mut self, radius: usize) -> Self {
+        self.kernel_radius = radius;
+        self
+    }
+
+    /// Radial profile of the kernel; defaults to the linear cone.
+    pub fn kernel_shape(mut self, shape: KernelShape) -> Self {
+        self.kernel_fn = Arc::new(shape);
+        self
+    }
+
+    /// Use any [`Kernel`] implementation for the convolution table.
+    pub fn kernel(mut self, kernel: impl Kernel + 'static) -> Self {
+        self.kernel_fn = Arc::new(kernel);
+        self
+    }
+
+    /// Add another kernel whose potential is added in with the given weight,
+    /// e.g. an outer ring next to an inner disc. The main kernel has weight 1;
+    /// see [`SimpleLife::compute_potentials`] for the individual fields.
+    pub fn add_kernel(mut self, kernel: impl Kernel + 'static, weight: Real) -> Self {
+        self.extra_kernels.push((Arc::new(kernel), weight));
+        self
+    }
+
+    /// Like [`add_kernel`](Self::add_kernel), but the kernel keeps its own
+    /// `radius` instead of following the simulation's, see [`WithRadius`].
+    pub fn add_kernel_with_radius(self, kernel: impl Kernel + 'static, radius: usize, weight: Real) -> Self {
+        self.add_kernel(WithRadius::new(Arc::new(kernel), radius), weight)
+    }
+
+    /// Scale the kernel weights, the main kernel's 1 included, so they sum
+    /// to 1. Off by default, which adds the extra kernels on top.
+    pub fn normalize_kernel_weights(mut self, normalize: bool) -> Self {
+        self.normalize_kernel_weights = normalize;
+        self
+    }
+
+    /// Stretch the kernel by `aspect_ratio` along an axis rotated by
+    /// `rotation_degrees`, see [`Anisotropic`]. Applies to whichever kernel is set.
+    pub fn anisotropy(mut self, aspect_ratio: f32, rotation_degrees: f32) -> Self {
+        self.anisotropy = Some((aspect_ratio, rotation_degrees));
+        self
+    }
+
+    /// How the kernel table is scaled; weights sum to 1 unless set.
+    pub fn normalization(mut self, normalization: Normalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Average `n` x `n` samples per kernel cell to smooth the kernel's edge;
+    /// 1 (the default) samples each cell at its center.
+    pub fn kernel_supersampling(mut self, n: usize) -> Self {
+        self.kernel_supersampling = n;
+        self
+    }
+
+    /// Time step; 0 freezes the dynamics, negative values need `allow_negative_dt`.
+    pub fn dt(mut self, dt: Real) -> Self {
+        self.dt = dt;
+        self
+    }
+
+    /// Algorithm for the convolution; FFT pays off for large kernel radii.
+    pub fn conv_method(mut self, method: ConvMethod) -> Self {
+        self.conv_method = method;
+        self
+    }
+
+    /// Edge handling of the convolution; toroidal unless set.
+    pub fn boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    /// How cells are kept within `0..=1`; a hard clamp unless set.
+    pub fn clamp(mut self, clamp: Clamp) -> Self {
+        self.clamp = clamp;
+        self
+    }
+
+    pub fn allow_negative_dt(mut self, allow: bool) -> Self {
+        self.allow_negative_dt = allow;
+        self
+    }
+
+    /// Seed for the random number generator used by `random_init`.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Growth curve applied to the potential; defaults to `GrowthRule::Quadratic { a: 1.8, b: 0.2 }`.
+    pub fn growth(self, growth: impl Fn(Real) -> Real + Send + Sync + 'static) -> Self {
+        self.growth_rule(GrowthRule::Function(Arc::new(growth)))
+    }
+
+    /// Growth as a [`GrowthRule`], e.g. a sampled table; checked by `build`.
+    pub fn growth_rule(mut self, growth: GrowthRule) -> Self {
+        self.growth = growth;
+        self.growth_preset = None;
+        self
+    }
+
+    /// Use one of the named [`GrowthPreset`]s; its parameters are checked by `build`.
+    pub fn growth_preset(mut self, preset: GrowthPreset) -> Self {
+        self = self.growth(move |u| preset.value(u));
+        self.growth_preset = Some(preset);
+        self
+    }
+
+    /// Use a [`GaussianGrowth`] bell as the growth curve.
+    pub fn gaussian_growth(self, growth: GaussianGrowth) -> Self {
+        self.growth(move |u| growth.value(u))
+    }
+
+    /// Check the combination of parameters and build the simulation.
+    pub fn build(mut self) -> Result<SimpleLife, BuildError> {
+        self.apply_anisotropy();
+
+        if self.width == 0 || self.height == 0 {
+            return Err(BuildError::ZeroSize { width: self.width, height: self.height });
+        }
+        if self.kernel_radius == 0 {
+            return Err(BuildError::ZeroRadius);
+        }
+
+        // The kernel has to stay smaller than half the grid or it wraps onto itself
+        let max = (self.width.min(self.height) - 1) / 2;
+        if self.kernel_radius > max {
+            return Err(BuildError::RadiusTooLarge { radius: self.kernel_radius, max });
+        }
+
+        self.kernel_fn.validate().map_err(BuildError::InvalidKernel)?;
+        if self.kernel_supersampling == 0 {
+            return Err(BuildError::InvalidKernel("supersampling needs at least 1 sample per axis".to_string()));
+        }
+        self.kernel_fn.validate_radius(self.kernel_radius).map_err(BuildError::InvalidKernel)?;
+        for (kernel, weight) in &self.extra_kernels {
+            kernel.validate().map_err(BuildError::InvalidKernel)?;
+            kernel.validate_radius(self.kernel_radius).map_err(BuildError::InvalidKernel)?;
+            if !weight.is_finite() {
+                return Err(BuildError::InvalidKernel(format!("kernel weight must be finite, got {}", weight)));
+            }
+        }
+
+        let total_weight = 1.0 + self.extra_kernels.iter().map(|(_, w)| w).sum::<Real>();
+        if self.normalize_kernel_weights && total_weight.abs() < 1e-6 {
+            return Err(BuildError::InvalidKernel("kernel weights sum to 0 and can't be normalized".to_string()));
+        }
+
+        let extent = self.extra_kernels.iter()
+            .map(|(kernel, _)| kernel.extent(self.kernel_radius))
+            .fold(self.kernel_fn.extent(self.kernel_radius), |a, b| (a.0.max(b.0), a.1.max(b.1)));
+        let max = ((self.width - 1) / 2, (self.height - 1) / 2);
+        if extent.0 > max.0 || extent.1 > max.1 {
+            return Err(BuildError::ExtentTooLarge { extent, max });
+        }
+
+        if let Some(preset) = &self.growth_preset {
+            preset.check().map_err(BuildError::InvalidGrowth)?;
+        }
+        self.growth.check().map_err(BuildError::InvalidGrowth)?;
+
+        if !self.dt.is_finite() {
+            return Err(BuildError::NonFiniteDt(self.dt));
+        }
+        if self.dt < 0.0 && !self.allow_negative_dt {
+            return Err(BuildError::NegativeDt(self.dt));
+        }
+
+        let mut sim = self.assemble();
+        sim.try_init_kernel().map_err(BuildError::InvalidKernel)?;
+        Ok(sim)
+    }
+
+    // Used by `SimpleLife::new`, which has never validated its arguments; a
+    // kernel that can't be normalized is left unscaled instead
+    pub(crate) fn build_unchecked(self) -> SimpleLife {
+        let mut sim = self.assemble();
+        if sim.try_init_kernel().is_err() {
+            sim.normalization = Normalization::None;
+            let _ = sim.try_init_kernel();
+        }
+        sim
+    }
+
+    fn assemble(mut self) -> SimpleLife {
+        self.apply_anisotropy();
+        let rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        SimpleLife {
+            width: self.width,
+            height: self.height,
+            grid: Grid::new(self.width, self.height),
+            kernel: Vec::new(),
+            kernel_radius: self.kernel_radius,
+            kernel_extent: (self.kernel_radius, self.kernel_radius),
+            kernel_fn: self.kernel_fn,
+            extra_kernels: self.extra_kernels,
+            normalize_kernel_weights: self.normalize_kernel_weights,
+            normalization: self.normalization,
+            kernel_supersampling: self.kernel_supersampling.max(1),
+            kernel_offsets: Vec::new(),
+            dt: self.dt,
+            allow_negative_dt: self.allow_negative_dt,
+            conv_method: self.conv_method,
+            boundary: self.boundary,
+            clamp: self.clamp,
+            fft: None,
+            separable: None,
+            potential: Vec::new(),
+            scratch: Scratch::default(),
+            activity_tracking: true,
+            active: ActiveTiles::default(),
+            growth: self.growth,
+            parameter: 0.0,
+            parameter_map: None,
+            growth_schedule: None,
+            steps: 0,
+            sim_time: 0.0,
+            last_step_duration: Duration::ZERO,
+            rng,
+        }
+    }
+
+    // Wrap the chosen kernel once, whatever order the setters were called in
+    fn apply_anisotropy(&mut self) {
+        if let Some((aspect_ratio, rotation_degrees)) = self.anisotropy.take() {
+            self.kernel_fn = Arc::new(Anisotropic::new(self.kernel_fn.clone(), aspect_ratio, rotation_degrees));
+        }
+    }
+}
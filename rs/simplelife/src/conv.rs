@@ -0,0 +1,460 @@
+//! Ways of computing the potential (the grid convolved with the kernel).
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+
+use crate::active::{ACTIVE_TILE, ActiveTiles};
+use crate::{Grid, Real};
+
+/// Algorithm used by [`SimpleLife::compute_potential`](crate::SimpleLife::compute_potential).
+///
+/// Methods print and parse as `direct`, `fft` and `auto`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConvMethod {
+    /// Brute-force sum over the kernel window, O(W·H·K²). When the kernel table
+    /// is separable (an outer product of a row and a column, like a square
+    /// Gaussian) it runs as a row pass and a column pass instead, O(W·H·K).
+    Direct,
+    /// Circular convolution through 2D FFTs, O(W·H·log(W·H)) regardless of the
+    /// kernel radius. The wraparound matches the toroidal grid exactly, so the
+    /// result agrees with `Direct` up to floating-point rounding.
+    Fft,
+    /// `Fft` once the kernel table reaches [`AUTO_FFT_RADIUS`] cells from its
+    /// center along either axis, `Direct` below that or for separable tables.
+    #[default]
+    Auto,
+}
+
+/// Kernel extent from which [`ConvMethod::Auto`] switches to FFT. Measured
+/// on grids from 100x100 to 1000x1000: FFT takes over between radius 5 on
+/// small grids and 7 on large ones, since its cost hardly depends on the radius.
+pub const AUTO_FFT_RADIUS: usize = 6;
+
+impl ConvMethod {
+    // `Direct` or `Fft` for a table of the given extent
+    pub(crate) fn resolve(self, extent: (usize, usize), separable: bool) -> ConvMethod {
+        match self {
+            ConvMethod::Auto if !separable && extent.0.max(extent.1) >= AUTO_FFT_RADIUS => ConvMethod::Fft,
+            ConvMethod::Auto => ConvMethod::Direct,
+            method => method,
+        }
+    }
+}
+
+impl fmt::Display for ConvMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConvMethod::Direct => "direct",
+            ConvMethod::Fft => "fft",
+            ConvMethod::Auto => "auto",
+        })
+    }
+}
+
+impl FromStr for ConvMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "direct" => Ok(ConvMethod::Direct),
+            "fft" => Ok(ConvMethod::Fft),
+            "auto" => Ok(ConvMethod::Auto),
+            _ => Err(format!("unknown convolution method '{}'; expected direct, fft or auto", s)),
+        }
+    }
+}
+
+/// What the convolution sees beyond the edges of the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Boundary {
+    /// Opposite edges are glued together, the grid is a torus.
+    #[default]
+    Toroidal,
+    /// Everything outside the grid is dead: samples there contribute 0.
+    Fixed,
+    /// The grid is mirrored at its edges (the edge cell is repeated).
+    Reflective,
+}
+
+impl Boundary {
+    /// Map a coordinate that may lie up to one grid size outside `0..size` back
+    /// into the grid, or `None` if it samples the dead outside of a fixed boundary.
+    #[inline]
+    pub(crate) fn resolve(self, i: isize, size: isize) -> Option<usize> {
+        if (0..size).contains(&i) {
+            return Some(i as usize);
+        }
+        match self {
+            Boundary::Toroidal => Some(((i + size) % size) as usize),
+            Boundary::Fixed => None,
+            Boundary::Reflective if i < 0 => Some((-i - 1) as usize),
+            Boundary::Reflective => Some((2 * size - i - 1) as usize),
+        }
+    }
+}
+
+/// A kernel table factored into a row and a column, each stored as its
+/// non-zero `(offset, weight)` entries.
+#[derive(Clone)]
+pub(crate) struct Separable {
+    row: Vec<(isize, Real)>,
+    column: Vec<(isize, Real)>,
+}
+
+// The cast is a no-op without the f64 feature
+#[allow(clippy::unnecessary_cast)]
+const SAMPLE_EPSILON: Real = f32::EPSILON as Real;
+
+impl Separable {
+    /// Bytes held by the two factors.
+    pub(crate) fn memory_bytes(&self) -> usize {
+        (self.row.capacity() + self.column.capacity()) * std::mem::size_of::<(isize, Real)>()
+    }
+
+    /// Factor a `(2rx+1)x(2ry+1)` table, or `None` if it is not an outer product
+    /// up to rounding.
+    pub(crate) fn factor(kernel: &[Real], (rx, ry): (usize, usize)) -> Option<Self> {
+        let kernel_width = 2 * rx + 1;
+        let (pivot_index, &pivot) = kernel.iter()
+            .enumerate()
+            .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))?;
+        if pivot == 0.0 {
+            return None;
+        }
+
+        // Any non-zero entry's row and column determine the whole table
+        let (px, py) = (pivot_index % kernel_width, pivot_index / kernel_width);
+        let row: Vec<Real> = kernel[py * kernel_width..(py + 1) * kernel_width].to_vec();
+        let column: Vec<Real> = kernel.chunks(kernel_width).map(|r| r[px] / pivot).collect();
+
+        // Kernels are sampled in f32, so that is the rounding to allow for
+        let tolerance = 8.0 * SAMPLE_EPSILON * pivot.abs();
+        let separable = kernel.chunks(kernel_width)
+            .zip(&column)
+            .all(|(r, &c)| r.iter().zip(&row).all(|(&k, &w)| (k - c * w).abs() <= tolerance));
+        if !separable {
+            return None;
+        }
+
+        let sparse = |factor: Vec<Real>, radius: usize| factor.into_iter()
+            .enumerate()
+            .filter(|&(_, k)| k != 0.0)
+            .map(|(i, k)| (i as isize - radius as isize, k))
+            .collect();
+        Some(Separable { row: sparse(row, rx), column: sparse(column, ry) })
+    }
+
+    /// Convolve `grid` with the row factor along x, then the column factor along y,
+    /// writing the result to `potential`.
+    pub(crate) fn convolve_into(
+        &self,
+        grid: &[Real],
+        width: usize,
+        height: usize,
+        boundary: Boundary,
+        potential: &mut [Real],
+        scratch: &mut Scratch,
+    ) {
+        let (w, h) = (width as isize, height as isize);
+
+        let horizontal = &mut scratch.real;
+        horizontal.resize(grid.len(), 0.0);
+        for_each_row(horizontal, width, |y, out| {
+            let src = &grid[y * width..(y + 1) * width];
+            for (x, o) in out.iter_mut().enumerate() {
+                *o = self.row.iter()
+                    .filter_map(|&(dx, k)| boundary.resolve(x as isize + dx, w).map(|gx| src[gx] * k))
+                    .sum();
+            }
+        });
+
+        // Whole rows are scaled and added, which keeps the column pass cache friendly
+        let horizontal = &*horizontal;
+        for_each_row(potential, width, |y, out| {
+            out.fill(0.0);
+            for &(dy, k) in &self.column {
+                if let Some(gy) = boundary.resolve(y as isize + dy, h) {
+                    let src = &horizontal[gy * width..(gy + 1) * width];
+                    for (o, &v) in out.iter_mut().zip(src) {
+                        *o += v * k;
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Buffers the direct, separable and FFT paths work in, kept across steps so a
+/// convolution does not allocate once they have grown to the grid size.
+#[derive(Clone, Default)]
+pub(crate) struct Scratch {
+    real: Vec<Real>,
+    data: Vec<Complex<Real>>,
+    transposed: Vec<Complex<Real>>,
+    fft: Vec<Complex<Real>>,
+    // One per thread of the blocked direct convolution
+    halos: Vec<Vec<Real>>,
+}
+
+impl Scratch {
+    /// Bytes currently held by the buffers only the FFT path uses.
+    pub(crate) fn fft_memory_bytes(&self) -> usize {
+        (self.data.capacity() + self.transposed.capacity() + self.fft.capacity()) * std::mem::size_of::<Complex<Real>>()
+    }
+
+    /// Bytes currently held by the other buffers.
+    pub(crate) fn memory_bytes(&self) -> usize {
+        let halos: usize = self.halos.iter().map(Vec::capacity).sum();
+        (self.real.capacity() + halos) * std::mem::size_of::<Real>()
+    }
+
+    /// Free the buffers only the FFT path uses.
+    pub(crate) fn release_fft(&mut self) {
+        self.data = Vec::new();
+        self.transposed = Vec::new();
+        self.fft = Vec::new();
+    }
+}
+
+/// Output block of the cache-blocked direct convolution, in cells. Bands of
+/// rows line up with the activity tiles, so a band shares one set of spans.
+/// A block with its halo stays well within L2 for radii up to 25, and each
+/// output row only revisits a few KiB of it.
+const BLOCK_WIDTH: usize = 256;
+const BLOCK_HEIGHT: usize = ACTIVE_TILE;
+
+/// Output cells summed side by side in registers.
+const LANES: usize = 16;
+
+/// Direct convolution over `offsets` (see `kernel_offsets`), for the whole
+/// grid or only the spans of `active` with zeros elsewhere.
+///
+/// The output is cut into blocks. Each block first copies its input, grown by
+/// the kernel extent and seen through `boundary`, into a contiguous halo
+/// buffer, so the inner loop is a multiply-add over slices with no edge
+/// handling, summing runs of `LANES` cells in registers. Every cell still adds
+/// its terms in offset order, as a per-cell loop would, so the result is the
+/// same bit for bit.
+pub(crate) fn convolve_blocked(
+    grid: &Grid,
+    offsets: &[(isize, isize, Real)],
+    (rx, ry): (usize, usize),
+    boundary: Boundary,
+    active: Option<&ActiveTiles>,
+    potential: &mut [Real],
+    scratch: &mut Scratch,
+) {
+    let width = grid.width();
+    let whole_row = 0..width;
+    let band = |(band, rows): (usize, &mut [Real]), halo: &mut Vec<Real>| {
+        let y0 = band * BLOCK_HEIGHT;
+        let block_height = rows.len() / width;
+        let spans = match active {
+            Some(active) => {
+                rows.fill(0.0);
+                active.spans(y0)
+            }
+            None => std::slice::from_ref(&whole_row),
+        };
+
+        for span in spans {
+            for x0 in span.clone().step_by(BLOCK_WIDTH) {
+                let block_width = BLOCK_WIDTH.min(span.end - x0);
+                // Rows are padded so every run of LANES cells can be read in full
+                let stride = block_width.next_multiple_of(LANES) + 2 * rx;
+                fill_halo(grid, boundary, (x0 as isize - rx as isize, y0 as isize - ry as isize),
+                    (block_width + 2 * rx, block_height + 2 * ry), stride, halo);
+
+                for y in 0..block_height {
+                    let out = &mut rows[y * width + x0..y * width + x0 + block_width];
+                    for (run, cells) in out.chunks_mut(LANES).enumerate() {
+                        let mut sums = [0.0; LANES];
+                        for &(dx, dy, k) in offsets {
+                            let start = (y as isize + ry as isize + dy) as usize * stride
+                                + (rx as isize + dx) as usize + run * LANES;
+                            for (sum, &v) in sums.iter_mut().zip(&halo[start..start + LANES]) {
+                                *sum += v * k;
+                            }
+                        }
+                        cells.copy_from_slice(&sums[..cells.len()]);
+                    }
+                }
+            }
+        }
+    };
+
+    let band_len = width * BLOCK_HEIGHT;
+
+    // Each thread works through a run of consecutive bands in its own halo
+    #[cfg(feature = "parallel")]
+    {
+        let bands = potential.len().div_ceil(band_len);
+        let per_thread = bands.div_ceil(rayon::current_num_threads()).max(1);
+        scratch.halos.resize_with(bands.div_ceil(per_thread), Vec::new);
+        potential.par_chunks_mut(per_thread * band_len).zip(scratch.halos.par_iter_mut()).enumerate()
+            .for_each(|(run, (rows, halo))| {
+                for (i, rows) in rows.chunks_mut(band_len).enumerate() {
+                    band((run * per_thread + i, rows), halo);
+                }
+            });
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        scratch.halos.resize_with(1, Vec::new);
+        potential.chunks_mut(band_len).enumerate().for_each(|rows| band(rows, &mut scratch.halos[0]));
+    }
+}
+
+// Copy the `size` cells starting at `origin`, which may lie outside the grid,
+// into rows of `stride` cells in `halo`; cells a fixed boundary reads as dead
+// and the padding past `size` become 0
+fn fill_halo(
+    grid: &Grid,
+    boundary: Boundary,
+    (x0, y0): (isize, isize),
+    (halo_width, halo_height): (usize, usize),
+    stride: usize,
+    halo: &mut Vec<Real>,
+) {
+    let (width, height) = (grid.width() as isize, grid.height() as isize);
+    halo.resize(stride * halo_height, 0.0);
+
+    // Columns inside the grid are one contiguous copy per row
+    let inner_start = (-x0).clamp(0, halo_width as isize) as usize;
+    let inner_end = (width - x0).clamp(0, halo_width as isize) as usize;
+    for (hy, row) in halo.chunks_exact_mut(stride).enumerate() {
+        let (out, padding) = row.split_at_mut(halo_width);
+        padding.fill(0.0);
+        let Some(gy) = boundary.resolve(y0 + hy as isize, height) else {
+            out.fill(0.0);
+            continue;
+        };
+        let src = grid.row(gy);
+        if inner_start < inner_end {
+            out[inner_start..inner_end].copy_from_slice(&src[(x0 + inner_start as isize) as usize..(x0 + inner_end as isize) as usize]);
+        }
+        for hx in (0..inner_start).chain(inner_end..halo_width) {
+            out[hx] = boundary.resolve(x0 + hx as isize, width).map_or(0.0, |gx| src[gx]);
+        }
+    }
+}
+
+// Run `f` on every row of a row-major buffer, in parallel if enabled
+fn for_each_row(data: &mut [Real], width: usize, f: impl Fn(usize, &mut [Real]) + Send + Sync) {
+    #[cfg(feature = "parallel")]
+    data.par_chunks_mut(width).enumerate().for_each(|(y, row)| f(y, row));
+
+    #[cfg(not(feature = "parallel"))]
+    data.chunks_mut(width).enumerate().for_each(|(y, row)| f(y, row));
+}
+
+/// Precomputed FFT plans and kernel spectrum for one grid size and kernel.
+#[derive(Clone)]
+pub(crate) struct FftConvolver {
+    width: usize,
+    height: usize,
+    row_forward: Arc<dyn Fft<Real>>,
+    row_inverse: Arc<dyn Fft<Real>>,
+    col_forward: Arc<dyn Fft<Real>>,
+    col_inverse: Arc<dyn Fft<Real>>,
+    // Kernel spectrum in transposed (column-major) layout, already scaled by 1/(W·H)
+    kernel_spectrum: Vec<Complex<Real>>,
+}
+
+impl FftConvolver {
+    /// Transform a `(2rx+1)x(2ry+1)` kernel table once for a `width` x `height` grid.
+    pub(crate) fn new(width: usize, height: usize, kernel: &[Real], (rx, ry): (usize, usize)) -> Self {
+        let mut planner = FftPlanner::new();
+        let mut conv = FftConvolver {
+            width,
+            height,
+            row_forward: planner.plan_fft_forward(width),
+            row_inverse: planner.plan_fft_inverse(width),
+            col_forward: planner.plan_fft_forward(height),
+            col_inverse: planner.plan_fft_inverse(height),
+            kernel_spectrum: Vec::new(),
+        };
+
+        // compute_potential correlates (grid[x + d] * kernel[d]), so the kernel is
+        // embedded mirrored, with offset d landing at index -d (mod size)
+        let (kernel_width, kernel_height) = (2 * rx + 1, 2 * ry + 1);
+        let mut scratch = Scratch::default();
+        let embedded = &mut scratch.data;
+        embedded.resize(width * height, Complex::new(0.0, 0.0));
+        for ky in 0..kernel_height {
+            for kx in 0..kernel_width {
+                let x = (width * kernel_width + rx - kx) % width;
+                let y = (height * kernel_height + ry - ky) % height;
+                embedded[y * width + x].re += kernel[ky * kernel_width + kx];
+            }
+        }
+
+        let scale = 1.0 / (width * height) as Real;
+        conv.forward(&mut scratch);
+        conv.kernel_spectrum = scratch.transposed.iter().map(|&c| c * scale).collect();
+        conv
+    }
+
+    /// Bytes held by the cached spectrum.
+    pub(crate) fn memory_bytes(&self) -> usize {
+        self.kernel_spectrum.len() * std::mem::size_of::<Complex<Real>>()
+    }
+
+    /// Circular convolution of `grid` with the kernel, written to `potential`.
+    pub(crate) fn convolve_into(&self, grid: &[Real], potential: &mut [Real], scratch: &mut Scratch) {
+        scratch.data.clear();
+        scratch.data.extend(grid.iter().map(|&v| Complex::new(v, 0.0)));
+        self.forward(scratch);
+
+        for (s, k) in scratch.transposed.iter_mut().zip(&self.kernel_spectrum) {
+            *s *= k;
+        }
+
+        self.inverse(scratch);
+        for (p, c) in potential.iter_mut().zip(&scratch.data) {
+            *p = c.re;
+        }
+    }
+
+    // Row-major `scratch.data` in, transposed spectrum out in `scratch.transposed`
+    fn forward(&self, scratch: &mut Scratch) {
+        let Scratch { data, transposed, fft, .. } = scratch;
+        process(self.row_forward.as_ref(), data, fft);
+        transpose(data, transposed, self.width, self.height);
+        process(self.col_forward.as_ref(), transposed, fft);
+    }
+
+    // Transposed spectrum in `scratch.transposed`, row-major result out in `scratch.data`
+    fn inverse(&self, scratch: &mut Scratch) {
+        let Scratch { data, transposed, fft, .. } = scratch;
+        process(self.col_inverse.as_ref(), transposed, fft);
+        transpose(transposed, data, self.height, self.width);
+        process(self.row_inverse.as_ref(), data, fft);
+    }
+}
+
+// Run `plan` in place, growing the shared scratch buffer if the plan needs more
+fn process(plan: &dyn Fft<Real>, data: &mut [Complex<Real>], scratch: &mut Vec<Complex<Real>>) {
+    let len = plan.get_inplace_scratch_len();
+    if scratch.len() < len {
+        scratch.resize(len, Complex::new(0.0, 0.0));
+    }
+    plan.process_with_scratch(data, &mut scratch[..len]);
+}
+
+// Transpose a row-major `width` x `height` matrix into `out`
+fn transpose(data: &[Complex<Real>], out: &mut Vec<Complex<Real>>, width: usize, height: usize) {
+    out.resize(data.len(), Complex::new(0.0, 0.0));
+    for y in 0..height {
+        for x in 0..width {
+            out[x * height + y] = data[y * width + x];
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! Crate-wide error type.
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::BuildError;
+
+/// Everything that can go wrong while setting up, running or exporting a simulation.
+#[derive(Debug)]
+pub enum SimError {
+    /// Reading or writing `path` failed. `step` is filled in by callers that
+    /// know which step of a run they were on.
+    Io {
+        path: PathBuf,
+        step: Option<u64>,
+        source: io::Error,
+    },
+    /// Encoding or decoding an image at `path` failed.
+    Image {
+        path: PathBuf,
+        source: image::ImageError,
+    },
+    /// The grid size, kernel radius or another setup parameter was rejected,
+    /// e.g. zero dimensions or a kernel that no longer fits the grid.
+    Build(BuildError),
+    /// A parameter was out of range or could not be parsed.
+    InvalidParameter(String),
+    /// The display window could not be created or updated.
+    Window(String),
+    /// No GPU device was found, or talking to it failed.
+    Gpu(String),
+}
+
+impl SimError {
+    pub fn io(path: impl AsRef<Path>, source: io::Error) -> Self {
+        SimError::Io {
+            path: path.as_ref().to_path_buf(),
+            step: None,
+            source,
+        }
+    }
+
+    pub fn image(path: impl AsRef<Path>, source: image::ImageError) -> Self {
+        SimError::Image {
+            path: path.as_ref().to_path_buf(),
+            source,
+        }
+    }
+
+    /// Attach the step number to an I/O error so long runs say where they died.
+    pub fn at_step(self, step: u64) -> Self {
+        match self {
+            SimError::Io { path, source, .. } => SimError::Io { path, step: Some(step), source },
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimError::Io { path, step: Some(step), source } =>
+                write!(f, "failed to access {} at step {}: {}", path.display(), step, source),
+            SimError::Io { path, step: None, source } =>
+                write!(f, "failed to access {}: {}", path.display(), source),
+            SimError::Image { path, source } =>
+                write!(f, "image error for {}: {}", path.display(), source),
+            SimError::Build(e) => write!(f, "invalid parameter: {}", e),
+            SimError::InvalidParameter(msg) => write!(f, "invalid parameter: {}", msg),
+            SimError::Window(msg) => write!(f, "window error: {}", msg),
+            SimError::Gpu(msg) => write!(f, "GPU error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SimError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SimError::Io { source, .. } => Some(source),
+            SimError::Image { source, .. } => Some(source),
+            SimError::Build(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Why a single-cell write was refused.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellError {
+    /// The coordinates lie outside the grid.
+    OutOfBounds { x: usize, y: usize, width: usize, height: usize },
+    /// NaN has no meaningful place in the grid.
+    NotANumber,
+}
+
+impl fmt::Display for CellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CellError::OutOfBounds { x, y, width, height } =>
+                write!(f, "cell ({}, {}) is outside the {}x{} grid", x, y, width, height),
+            CellError::NotANumber => write!(f, "cell value is NaN"),
+        }
+    }
+}
+
+impl std::error::Error for CellError {}
+
+impl From<CellError> for SimError {
+    fn from(e: CellError) -> Self {
+        SimError::InvalidParameter(e.to_string())
+    }
+}
+
+impl From<BuildError> for SimError {
+    fn from(e: BuildError) -> Self {
+        SimError::Build(e)
+    }
+}
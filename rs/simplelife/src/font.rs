@@ -0,0 +1,55 @@
+//! A 5x7 bitmap font for the labels drawn into exported images.
+//!
+//! It only has the characters the labels need, digits and the lowercase
+//! letters of "step"; anything else is drawn as a blank.
+
+pub(crate) const GLYPH_HEIGHT: usize = 7;
+const GLYPH_WIDTH: usize = 5;
+// A column of space after every glyph
+const ADVANCE: usize = GLYPH_WIDTH + 1;
+
+// Rows from the top, the leftmost pixel in bit 4
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c {
+        '0' => [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110],
+        '1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        '2' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111],
+        '3' => [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110],
+        '4' => [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
+        '5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
+        '6' => [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
+        '7' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000],
+        '8' => [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
+        '9' => [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100],
+        's' => [0b00000, 0b00000, 0b01110, 0b10000, 0b01110, 0b00001, 0b11110],
+        't' => [0b01000, 0b01000, 0b11100, 0b01000, 0b01000, 0b01001, 0b00110],
+        'e' => [0b00000, 0b00000, 0b01110, 0b10001, 0b11111, 0b10000, 0b01110],
+        'p' => [0b00000, 0b00000, 0b11110, 0b10001, 0b11110, 0b10000, 0b10000],
+        _ => [0; GLYPH_HEIGHT],
+    }
+}
+
+// Width in pixels of `text` with every font pixel drawn as a `scale` x `scale` block
+pub(crate) fn text_width(text: &str, scale: usize) -> usize {
+    (text.chars().count() * ADVANCE).saturating_sub(1) * scale
+}
+
+// Draw `text` in `color` into a `width` pixels wide 0xRRGGBB image, its top
+// left corner at `(x, y)`; whatever falls outside the image is clipped
+pub(crate) fn draw_text(pixels: &mut [u32], width: usize, (x, y): (usize, usize), text: &str, scale: usize, color: u32) {
+    let height = pixels.len() / width;
+    for (i, c) in text.chars().enumerate() {
+        let left = x + i * ADVANCE * scale;
+        for (row, bits) in glyph(c).into_iter().enumerate() {
+            for column in (0..GLYPH_WIDTH).filter(|column| bits & (0b10000 >> column) != 0) {
+                for py in y + row * scale..y + (row + 1) * scale {
+                    for px in left + column * scale..left + (column + 1) * scale {
+                        if px < width && py < height {
+                            pixels[py * width + px] = color;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! Stepping on the GPU through a wgpu compute shader, for grids too large for
+//! the CPU convolution to keep up with.
+
+use wgpu::util::DeviceExt;
+
+use crate::{Boundary, Clamp, GrowthRule, Real, SimError, SimpleLife};
+
+// Samples the growth curve is uploaded as, unless it already is a table
+const GROWTH_SAMPLES: usize = 1024;
+// Must match `@workgroup_size` in gpu.wgsl
+const WORKGROUP: u32 = 16;
+
+/// A [`SimpleLife`] whose grid lives on the GPU between steps.
+///
+/// Each [`update`](Self::update) runs the direct convolution and the growth
+/// step in one compute dispatch; the grid only comes back to the CPU when
+/// [`sim`](Self::sim) or [`sim_mut`](Self::sim_mut) asks for it, e.g. to
+/// display or save a frame. The shader works in `f32` and reads the growth
+/// curve from a table, so runs match the CPU visually but not bit for bit.
+///
+/// Anything that can be set on the wrapped simulation carries over, except
+/// extra kernels, parameter maps, growth schedules and growth rules that look
+/// at the cell value; [`update`](Self::update) rejects those.
+pub struct GpuLife {
+    sim: SimpleLife,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    // Built from `sim` on the next update, dropped whenever it is handed out mutably
+    buffers: Option<Buffers>,
+    // Whether the GPU grid has steps the CPU grid has not seen yet
+    ahead: bool,
+}
+
+struct Buffers {
+    grids: [wgpu::Buffer; 2],
+    readback: wgpu::Buffer,
+    // Reading grids[i] and writing the other one
+    bind_groups: [wgpu::BindGroup; 2],
+    // Which grid holds the latest step
+    current: usize,
+}
+
+impl SimpleLife {
+    /// Create an empty simulation like [`SimpleLife::try_new`] that steps on
+    /// the GPU, blocking until a device is ready.
+    pub fn new_gpu(width: usize, height: usize, kernel_radius: usize, dt: Real) -> Result<GpuLife, SimError> {
+        GpuLife::new(SimpleLife::try_new(width, height, kernel_radius, dt)?)
+    }
+}
+
+impl GpuLife {
+    /// Move `sim` onto the default GPU, blocking until the device is ready.
+    pub fn new(sim: SimpleLife) -> Result<Self, SimError> {
+        pollster::block_on(Self::new_async(sim))
+    }
+
+    /// [`GpuLife::new`] for callers that already run an executor.
+    pub async fn new_async(sim: SimpleLife) -> Result<Self, SimError> {
+        let instance = wgpu::Instance::default();
+        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        }).await.map_err(|e| SimError::Gpu(e.to_string()))?;
+        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("simplelife"),
+            required_limits: adapter.limits(),
+            ..Default::default()
+        }).await.map_err(|e| SimError::Gpu(e.to_string()))?;
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("gpu.wgsl"));
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("update"),
+            layout: None,
+            module: &shader,
+            entry_point: Some("update"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        Ok(GpuLife { sim, device, queue, pipeline, buffers: None, ahead: false })
+    }
+
+    /// The simulation with the grid of the latest step, read back from the
+    /// GPU if it has moved on since.
+    pub fn sim(&mut self) -> Result<&SimpleLife, SimError> {
+        self.download()?;
+        Ok(&self.sim)
+    }
+
+    /// The simulation to change, e.g. to paint cells or swap the growth rule;
+    /// the changes are uploaded before the next step.
+    pub fn sim_mut(&mut self) -> Result<&mut SimpleLife, SimError> {
+        self.download()?;
+        self.buffers = None;
+        Ok(&mut self.sim)
+    }
+
+    /// Give the simulation back, with the grid of the latest step.
+    pub fn into_sim(mut self) -> Result<SimpleLife, SimError> {
+        self.download()?;
+        Ok(self.sim)
+    }
+
+    /// Advance the simulation by one time step.
+    ///
+    /// Unlike [`SimpleLife::update`] this does not report extinction, which
+    /// would need the grid back from the GPU every step.
+    pub fn update(&mut self) -> Result<(), SimError> {
+        self.step_n(1)
+    }
+
+    /// Advance the simulation by `n` steps in a single submission.
+    pub fn step_n(&mut self, n: usize) -> Result<(), SimError> {
+        let buffers = match self.buffers.take() {
+            Some(buffers) => buffers,
+            None => self.upload()?,
+        };
+        let buffers = self.buffers.insert(buffers);
+        if n == 0 || self.sim.dt == 0.0 {
+            self.sim.count_steps(n as u64);
+            return Ok(());
+        }
+
+        let groups = (self.sim.width.div_ceil(WORKGROUP as usize) as u32, self.sim.height.div_ceil(WORKGROUP as usize) as u32);
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_pipeline(&self.pipeline);
+            for _ in 0..n {
+                pass.set_bind_group(0, &buffers.bind_groups[buffers.current], &[]);
+                pass.dispatch_workgroups(groups.0, groups.1, 1);
+                buffers.current ^= 1;
+            }
+        }
+        self.queue.submit([encoder.finish()]);
+
+        self.sim.count_steps(n as u64);
+        self.ahead = true;
+        Ok(())
+    }
+
+    // Everything the shader reads, taken from the current state of `sim`; the
+    // casts are no-ops without the f64 feature
+    #[allow(clippy::unnecessary_cast)]
+    fn upload(&self) -> Result<Buffers, SimError> {
+        let sim = &self.sim;
+        if sim.kernel_count() > 1 {
+            return Err(SimError::InvalidParameter("the GPU path only takes a single kernel".to_string()));
+        }
+        if sim.parameter_map.is_some() {
+            return Err(SimError::InvalidParameter("parameter maps are not supported on the GPU".to_string()));
+        }
+        if sim.growth_schedule.is_some() {
+            return Err(SimError::InvalidParameter("growth schedules are not supported on the GPU".to_string()));
+        }
+        let table: Vec<f32> = match &sim.growth {
+            GrowthRule::State(_) | GrowthRule::SmoothLife { .. } =>
+                return Err(SimError::InvalidParameter("growth rules that depend on the cell value are not supported on the GPU".to_string())),
+            GrowthRule::Table(table) => table.iter().map(|&v| v as f32).collect(),
+            rule => (0..GROWTH_SAMPLES)
+                .map(|i| rule.value(i as Real / (GROWTH_SAMPLES - 1) as Real, sim.parameter) as f32)
+                .collect(),
+        };
+
+        // An empty storage buffer is not allowed, so a kernel without weights gets one zero entry
+        let mut offsets: Vec<[u32; 4]> = sim.kernel_offsets.iter()
+            .map(|&(dx, dy, weight)| [dx as i32 as u32, dy as i32 as u32, (weight as f32).to_bits(), 0])
+            .collect();
+        let offset_count = offsets.len() as u32;
+        if offsets.is_empty() {
+            offsets.push([0; 4]);
+        }
+        let boundary = match sim.boundary {
+            Boundary::Toroidal => 0,
+            Boundary::Fixed => 1,
+            Boundary::Reflective => 2,
+        };
+        let params = [
+            sim.width as u32, sim.height as u32, offset_count, boundary,
+            (sim.dt as f32).to_bits(), table.len() as u32, (sim.clamp == Clamp::Soft) as u32, 0,
+        ];
+        let grid: Vec<f32> = sim.grid.iter().map(|&v| v as f32).collect();
+
+        let init = |label, contents: &[u8], usage| self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents,
+            usage,
+        });
+        let params = init("params", bytemuck::cast_slice(&params), wgpu::BufferUsages::UNIFORM);
+        let offsets = init("kernel offsets", bytemuck::cast_slice(&offsets), wgpu::BufferUsages::STORAGE);
+        let table = init("growth table", bytemuck::cast_slice(&table), wgpu::BufferUsages::STORAGE);
+        let grid_usage = wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC;
+        let grids = [init("grid", bytemuck::cast_slice(&grid), grid_usage), init("grid", bytemuck::cast_slice(&grid), grid_usage)];
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback"),
+            size: grids[0].size(),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let layout = self.pipeline.get_bind_group_layout(0);
+        let bind_group = |src: &wgpu::Buffer, dst: &wgpu::Buffer| self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: params.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: offsets.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: table.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: src.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 4, resource: dst.as_entire_binding() },
+            ],
+        });
+        let bind_groups = [bind_group(&grids[0], &grids[1]), bind_group(&grids[1], &grids[0])];
+
+        Ok(Buffers { grids, readback, bind_groups, current: 0 })
+    }
+
+    // Copy the latest GPU grid into `sim` if it is ahead
+    #[allow(clippy::unnecessary_cast)]
+    fn download(&mut self) -> Result<(), SimError> {
+        let Some(buffers) = &self.buffers else { return Ok(()) };
+        if !self.ahead {
+            return Ok(());
+        }
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.copy_buffer_to_buffer(&buffers.grids[buffers.current], 0, &buffers.readback, 0, buffers.readback.size());
+        self.queue.submit([encoder.finish()]);
+
+        let slice = buffers.readback.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::PollType::Wait).map_err(|e| SimError::Gpu(e.to_string()))?;
+        receiver.recv()
+            .map_err(|e| SimError::Gpu(e.to_string()))?
+            .map_err(|e| SimError::Gpu(e.to_string()))?;
+        {
+            let data = slice.get_mapped_range();
+            for (cell, &v) in self.sim.grid.iter_mut().zip(bytemuck::cast_slice::<u8, f32>(&data)) {
+                *cell = v as Real;
+            }
+        }
+        buffers.readback.unmap();
+
+        self.ahead = false;
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+// One simulation step per dispatch: the direct convolution over the non-zero
+// kernel entries, then the growth table and the clamp into 0..=1
+
+// Must match `SOFT_CLAMP_FLOOR`
+const SOFT_CLAMP_FLOOR: f32 = 1e-6;
+
+struct Params {
+    width: u32,
+    height: u32,
+    offset_count: u32,
+    // 0 toroidal, 1 fixed, 2 reflective, as in `Boundary`
+    boundary: u32,
+    dt: f32,
+    table_len: u32,
+    // 1 for `Clamp::Soft`, 0 for the hard clamp
+    soft_clamp: u32,
+    _pad: u32,
+}
+
+struct Offset {
+    dx: i32,
+    dy: i32,
+    weight: f32,
+    _pad: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> offsets: array<Offset>;
+@group(0) @binding(2) var<storage, read> growth: array<f32>;
+@group(0) @binding(3) var<storage, read> src: array<f32>;
+@group(0) @binding(4) var<storage, read_write> dst: array<f32>;
+
+// Coordinate i mapped back into 0..size, or -1 outside a fixed edge; the
+// kernel never reaches more than one grid size out
+fn wrap(i: i32, size: i32) -> i32 {
+    if i >= 0 && i < size {
+        return i;
+    }
+    switch params.boundary {
+        case 0u: {
+            return (i + size) % size;
+        }
+        case 1u: {
+            return -1;
+        }
+        default: {
+            if i < 0 {
+                return -i - 1;
+            }
+            return 2 * size - i - 1;
+        }
+    }
+}
+
+// Linear interpolation in the table covering potentials 0..=1
+fn growth_rate(u: f32) -> f32 {
+    let t = clamp(u, 0.0, 1.0) * f32(params.table_len - 1u);
+    let i = min(u32(t), params.table_len - 2u);
+    return mix(growth[i], growth[i + 1u], t - f32(i));
+}
+
+@compute @workgroup_size(16, 16)
+fn update(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.width || id.y >= params.height {
+        return;
+    }
+    let width = i32(params.width);
+    let height = i32(params.height);
+
+    var potential = 0.0;
+    for (var k = 0u; k < params.offset_count; k++) {
+        let offset = offsets[k];
+        let x = wrap(i32(id.x) + offset.dx, width);
+        let y = wrap(i32(id.y) + offset.dy, height);
+        if x >= 0 && y >= 0 {
+            potential += offset.weight * src[u32(y) * params.width + u32(x)];
+        }
+    }
+
+    let i = id.y * params.width + id.x;
+    let cell = src[i];
+    let delta = params.dt * growth_rate(potential);
+    if params.soft_clamp == 0u {
+        dst[i] = clamp(cell + delta, 0.0, 1.0);
+        return;
+    }
+    let room = select(cell, 1.0 - cell, delta > 0.0);
+    let value = clamp(cell + delta * room, 0.0, 1.0);
+    dst[i] = select(value, 0.0, value < SOFT_CLAMP_FLOOR);
+}
@@ -0,0 +1,133 @@
+//! Cell storage that knows its own dimensions.
+
+use std::ops::{Deref, DerefMut, Index, IndexMut};
+
+use crate::{Boundary, CellError, Real};
+
+/// Row-major cell values of a `width` x `height` grid.
+///
+/// Dereferences to the flat slice for whole-grid passes; the methods take
+/// coordinates so callers don't repeat the `y * width + x` arithmetic.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid {
+    data: Vec<Real>,
+    width: usize,
+    height: usize,
+}
+
+impl Grid {
+    /// An all-zero grid.
+    pub fn new(width: usize, height: usize) -> Self {
+        Grid { data: vec![0.0; width * height], width, height }
+    }
+
+    /// Width in cells.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Height in cells.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Value at `(x, y)`, or `None` outside the grid.
+    #[inline]
+    pub fn get(&self, x: usize, y: usize) -> Option<Real> {
+        if x < self.width && y < self.height {
+            Some(self.data[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// Cells of row `y`, left to right.
+    ///
+    /// Panics if `y` is outside the grid.
+    #[inline]
+    pub fn row(&self, y: usize) -> &[Real] {
+        &self.data[y * self.width..(y + 1) * self.width]
+    }
+
+    /// Store `value` at `(x, y)` as is.
+    pub fn set(&mut self, x: usize, y: usize, value: Real) -> Result<(), CellError> {
+        if x >= self.width || y >= self.height {
+            return Err(CellError::OutOfBounds { x, y, width: self.width, height: self.height });
+        }
+        self.data[y * self.width + x] = value;
+        Ok(())
+    }
+
+    /// Value at `(x, y)` on the torus: any coordinate wraps around the edges.
+    #[inline]
+    pub fn get_wrapped(&self, x: isize, y: isize) -> Real {
+        let x = x.rem_euclid(self.width as isize) as usize;
+        let y = y.rem_euclid(self.height as isize) as usize;
+        self.data[y * self.width + x]
+    }
+
+    /// Value at `(x, y)` as seen through `boundary`, for coordinates at most one
+    /// grid size outside; `None` where a fixed boundary reads dead cells.
+    #[inline]
+    pub fn get_bounded(&self, x: isize, y: isize, boundary: Boundary) -> Option<Real> {
+        let x = boundary.resolve(x, self.width as isize)?;
+        let y = boundary.resolve(y, self.height as isize)?;
+        Some(self.data[y * self.width + x])
+    }
+
+    /// Allocated cells, which may exceed the grid size.
+    pub(crate) fn capacity(&self) -> usize {
+        self.data.capacity()
+    }
+}
+
+impl Deref for Grid {
+    type Target = [Real];
+
+    fn deref(&self) -> &[Real] {
+        &self.data
+    }
+}
+
+impl DerefMut for Grid {
+    fn deref_mut(&mut self) -> &mut [Real] {
+        &mut self.data
+    }
+}
+
+impl<'a> IntoIterator for &'a Grid {
+    type Item = &'a Real;
+    type IntoIter = std::slice::Iter<'a, Real>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Grid {
+    type Item = &'a mut Real;
+    type IntoIter = std::slice::IterMut<'a, Real>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.iter_mut()
+    }
+}
+
+/// Cell at `(x, y)`; panics outside the grid, even where the flat index would exist.
+impl Index<(usize, usize)> for Grid {
+    type Output = Real;
+
+    #[inline]
+    fn index(&self, (x, y): (usize, usize)) -> &Real {
+        assert!(x < self.width && y < self.height, "cell ({}, {}) is outside the {}x{} grid", x, y, self.width, self.height);
+        &self.data[y * self.width + x]
+    }
+}
+
+impl IndexMut<(usize, usize)> for Grid {
+    #[inline]
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Real {
+        assert!(x < self.width && y < self.height, "cell ({}, {}) is outside the {}x{} grid", x, y, self.width, self.height);
+        &mut self.data[y * self.width + x]
+    }
+}
@@ -0,0 +1,477 @@
+//! Growth curves beyond the default quadratic.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::{GrowthFn, Real, SimError};
+
+/// A growth curve that also takes the local parameter of the cell, see
+/// [`SimpleLife::set_parameter_map`](crate::SimpleLife::set_parameter_map).
+pub type ParametricGrowthFn = Arc<dyn Fn(Real, Real) -> Real + Send + Sync>;
+
+/// A growth curve of the cell's own value and its potential, in that order.
+pub type StateGrowthFn = Arc<dyn Fn(Real, Real) -> Real + Send + Sync>;
+
+/// How a simulation maps a potential to the rate of change of a cell.
+#[derive(Clone)]
+pub enum GrowthRule {
+    /// Any function, called once per cell through a pointer.
+    Function(GrowthFn),
+    /// `a u (1 - u) - b`, the default family, kept as its two coefficients so
+    /// they can be read back and tuned on a running simulation; see
+    /// [`SimpleLife::set_growth_b`](crate::SimpleLife::set_growth_b).
+    Quadratic { a: Real, b: Real },
+    /// The curve sampled at evenly spaced potentials covering `0..=1`,
+    /// linearly interpolated in between and clamped at the ends. Cheap to
+    /// evaluate whatever curve it was sampled from.
+    Table(Vec<Real>),
+    /// A function of the potential and a parameter, which can vary across
+    /// the grid, e.g. a bell whose center shifts from left to right.
+    Parametric(ParametricGrowthFn),
+    /// A function of the cell's current value and the potential, for rules
+    /// where live and dead cells react differently to the same neighborhood.
+    State(StateGrowthFn),
+    /// SmoothLife's smooth time-stepping rule: `2 s(n, m) - 1` for potential
+    /// `n` and cell value `m`, where `s` is 1 for potentials inside an interval
+    /// that slides from the birth interval `b1..=b2` for dead cells to the
+    /// survival interval `d1..=d2` for live ones.
+    ///
+    /// `alpha_n` is the width of the sigmoid edges of the interval and
+    /// `alpha_m` that of the transition from dead to alive around 0.5; 0 makes
+    /// either a hard step.
+    SmoothLife { b1: Real, b2: Real, d1: Real, d2: Real, alpha_n: Real, alpha_m: Real },
+}
+
+impl GrowthRule {
+    /// Sample `f` at `n` evenly spaced potentials from 0 to 1 (at least 2).
+    pub fn from_samples(f: impl Fn(Real) -> Real, n: usize) -> Self {
+        let n = n.max(2);
+        GrowthRule::Table((0..n).map(|i| f(i as Real / (n - 1) as Real)).collect())
+    }
+
+    /// Rate of change for potential `u` where the parameter is `param`, as
+    /// seen by an empty cell for rules that depend on the cell value.
+    pub fn value(&self, u: Real, param: Real) -> Real {
+        self.rate(0.0, u, param)
+    }
+
+    /// Rate of change of a cell holding `cell` at potential `u` where the
+    /// parameter is `param`. Rules ignore whichever inputs they don't use, so
+    /// potential-only curves behave exactly as they always have.
+    pub fn rate(&self, cell: Real, u: Real, param: Real) -> Real {
+        match *self {
+            GrowthRule::Function(ref f) => f(u),
+            GrowthRule::Quadratic { a, b } => a * u * (1.0 - u) - b,
+            GrowthRule::Table(ref table) => table_value(table, u),
+            GrowthRule::Parametric(ref f) => f(u, param),
+            GrowthRule::State(ref f) => f(cell, u),
+            GrowthRule::SmoothLife { b1, b2, d1, d2, alpha_n, alpha_m } =>
+                smooth_life(cell, u, (b1, b2), (d1, d2), alpha_n, alpha_m),
+        }
+    }
+
+    /// Check the parameters, returning a description of the problem if any.
+    pub fn check(&self) -> Result<(), String> {
+        match self {
+            GrowthRule::Function(_) | GrowthRule::Parametric(_) | GrowthRule::State(_) => Ok(()),
+            &GrowthRule::Quadratic { a, b } => GrowthPreset::Quadratic { a, b }.check(),
+            GrowthRule::Table(table) if table.len() < 2 =>
+                Err(format!("growth table needs at least two samples, got {}", table.len())),
+            GrowthRule::Table(table) if table.iter().any(|v| !v.is_finite()) =>
+                Err("growth table samples must be finite".to_string()),
+            GrowthRule::Table(_) => Ok(()),
+            &GrowthRule::SmoothLife { b1, b2, d1, d2, alpha_n, alpha_m } => {
+                if ![b1, b2, d1, d2, alpha_n, alpha_m].iter().all(|v| v.is_finite()) {
+                    Err("SmoothLife growth needs finite parameters".to_string())
+                } else if b1 > b2 || d1 > d2 {
+                    Err(format!("SmoothLife growth needs b1 <= b2 and d1 <= d2, got {},{} and {},{}", b1, b2, d1, d2))
+                } else if alpha_n < 0.0 || alpha_m < 0.0 {
+                    Err(format!("SmoothLife growth needs non-negative edge widths, got {} and {}", alpha_n, alpha_m))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+// SmoothLife's s(n, m) mapped to -1..=1: the interval moves from birth to
+// survival as the cell comes alive
+fn smooth_life(m: Real, n: Real, birth: (Real, Real), survival: (Real, Real), alpha_n: Real, alpha_m: Real) -> Real {
+    let alive = logistic_step(m, 0.5, alpha_m);
+    let low = birth.0 + (survival.0 - birth.0) * alive;
+    let high = birth.1 + (survival.1 - birth.1) * alive;
+    2.0 * within(n, low, high, alpha_n) - 1.0
+}
+
+/// Growth rules that take over at given steps, e.g. to anneal from a forgiving
+/// rule into a strict one and see which structures survive.
+///
+/// Installed with [`SimpleLife::set_growth_schedule`](crate::SimpleLife::set_growth_schedule),
+/// which consults it at the top of every update: an entry for step `s` takes
+/// effect once `s` steps have run, so an entry for step 0 applies from the start.
+///
+/// Clones share the [`on_change`](Self::on_change) callback.
+#[derive(Clone, Default)]
+pub struct GrowthSchedule {
+    // Sorted by step, at most one entry per step
+    entries: Vec<(u64, GrowthRule)>,
+    active: Option<usize>,
+    on_change: Option<ChangeCallback>,
+}
+
+// Shared between clones, so it needs the lock to be called
+type ChangeCallback = Arc<Mutex<dyn FnMut(u64, usize) + Send + Sync>>;
+
+impl GrowthSchedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Switch to `rule` from `step` on; replaces an earlier entry for the same step.
+    pub fn at(mut self, step: u64, rule: GrowthRule) -> Self {
+        match self.entries.binary_search_by_key(&step, |&(s, _)| s) {
+            Ok(i) => self.entries[i].1 = rule,
+            Err(i) => self.entries.insert(i, (step, rule)),
+        }
+        self
+    }
+
+    /// Call `f(step, entry)` whenever the schedule installs a rule, with
+    /// `entry` counting the entries in step order from 0.
+    pub fn on_change(mut self, f: impl FnMut(u64, usize) + Send + Sync + 'static) -> Self {
+        self.on_change = Some(Arc::new(Mutex::new(f)));
+        self
+    }
+
+    /// Step and rule of every entry, in step order.
+    pub fn entries(&self) -> &[(u64, GrowthRule)] {
+        &self.entries
+    }
+
+    /// Check every rule, returning a description of the first problem.
+    pub fn check(&self) -> Result<(), String> {
+        for (step, rule) in &self.entries {
+            rule.check().map_err(|e| format!("rule for step {}: {}", step, e))?;
+        }
+        Ok(())
+    }
+
+    // The rule to install before running step `step + 1`, if it changed
+    pub(crate) fn advance(&mut self, step: u64) -> Option<GrowthRule> {
+        let index = self.entries.partition_point(|&(s, _)| s <= step).checked_sub(1)?;
+        if self.active == Some(index) {
+            return None;
+        }
+
+        self.active = Some(index);
+        if let Some(on_change) = &self.on_change {
+            (on_change.lock().unwrap_or_else(PoisonError::into_inner))(step, index);
+        }
+        Some(self.entries[index].1.clone())
+    }
+}
+
+// Linear interpolation in a table covering 0..=1 with at least two samples
+#[inline]
+pub(crate) fn table_value(table: &[Real], u: Real) -> Real {
+    let t = u.clamp(0.0, 1.0) * (table.len() - 1) as Real;
+    let i = (t as usize).min(table.len() - 2);
+    let frac = t - i as Real;
+    table[i] + (table[i + 1] - table[i]) * frac
+}
+
+/// The bell-shaped growth curve of continuous Lenia-style automata,
+/// `2 * exp(-(u - mu)^2 / (2 sigma^2)) - 1`.
+///
+/// Cells grow where the potential is close to `mu` and decay everywhere else;
+/// `sigma` sets how close. Typical values are around `mu = 0.15`,
+/// `sigma = 0.015`, which together with a ring kernel give self-organizing
+/// blobs rather than waves filling the grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GaussianGrowth {
+    mu: Real,
+    sigma: Real,
+}
+
+impl GaussianGrowth {
+    /// Fails unless `mu` is finite and `sigma` finite and positive.
+    pub fn new(mu: Real, sigma: Real) -> Result<Self, SimError> {
+        check_bell(mu, sigma).map_err(SimError::InvalidParameter)?;
+        Ok(GaussianGrowth { mu, sigma })
+    }
+
+    /// Potential with the fastest growth.
+    pub fn mu(&self) -> Real {
+        self.mu
+    }
+
+    /// Width of the band of potentials that grow.
+    pub fn sigma(&self) -> Real {
+        self.sigma
+    }
+
+    /// Rate of change for potential `u`, in `-1.0..=1.0`.
+    pub fn value(&self, u: Real) -> Real {
+        let d = u - self.mu;
+        2.0 * (-d * d / (2.0 * self.sigma * self.sigma)).exp() - 1.0
+    }
+}
+
+/// The growth curves that keep coming up, by name.
+///
+/// Presets print and parse as `quadratic-wide`, `quadratic-original`,
+/// `quadratic:A,B`, `gaussian:MU,SIGMA`, `band:LOW,HIGH`, `interval:B1,B2,D1,D2[,WIDTH]` and
+/// `mexican-hat:MU1,SIGMA1,AMP1,MU2,SIGMA2,AMP2`, so they can be picked on
+/// the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum GrowthPreset {
+    /// `1.8 u (1 - u) - 0.2`, the default; see [`default_growth`](crate::default_growth).
+    #[default]
+    QuadraticWide,
+    /// `2 u (1 - u) - 0.5`, the original, narrower curve.
+    QuadraticOriginal,
+    /// `a u (1 - u) - b` with any coefficients; `b` is the death pressure that
+    /// every cell pays regardless of its neighborhood.
+    Quadratic { a: Real, b: Real },
+    /// The Lenia bell, see [`GaussianGrowth`].
+    GaussianBell { mu: Real, sigma: Real },
+    /// `+1` for potentials within `low..=high` and `-1` elsewhere, as in SmoothLife.
+    StepBand { low: Real, high: Real },
+    /// SmoothLife-style birth and survival intervals: `+1` for potentials in
+    /// `b1..=b2`, `0` (hold) for ones in `d1..=d2`, `-1` elsewhere; birth wins
+    /// where they overlap. A positive `width` replaces each hard edge with a
+    /// sigmoid about that wide.
+    Interval { b1: Real, b2: Real, d1: Real, d2: Real, width: Real },
+    /// Difference of Gaussians over the potential:
+    /// `amp1 * exp(-(u - mu1)^2 / (2 sigma1^2)) - amp2 * exp(-(u - mu2)^2 / (2 sigma2^2))`.
+    ///
+    /// With a narrow tall bell over a wide flat one on the same center, mid
+    /// densities grow while both sparser and more crowded neighborhoods fall
+    /// into negative lobes, more sharply than the quadratic's.
+    MexicanHat { mu1: Real, sigma1: Real, amp1: Real, mu2: Real, sigma2: Real, amp2: Real },
+}
+
+impl GrowthPreset {
+    /// Rate of change for potential `u`.
+    pub fn value(&self, u: Real) -> Real {
+        match *self {
+            GrowthPreset::QuadraticWide => 1.8 * u * (1.0 - u) - 0.2,
+            GrowthPreset::QuadraticOriginal => 2.0 * u * (1.0 - u) - 0.5,
+            GrowthPreset::Quadratic { a, b } => a * u * (1.0 - u) - b,
+            GrowthPreset::GaussianBell { mu, sigma } => GaussianGrowth { mu, sigma }.value(u),
+            GrowthPreset::StepBand { low, high } => if (low..=high).contains(&u) { 1.0 } else { -1.0 },
+            GrowthPreset::Interval { b1, b2, d1, d2, width } => {
+                let birth = within(u, b1, b2, width);
+                let hold = within(u, d1, d2, width);
+                birth - (1.0 - birth) * (1.0 - hold)
+            }
+            GrowthPreset::MexicanHat { mu1, sigma1, amp1, mu2, sigma2, amp2 } =>
+                amp1 * bell(u, mu1, sigma1) - amp2 * bell(u, mu2, sigma2),
+        }
+    }
+
+    /// The tunable parameters by name, in spec order, for live editing; call
+    /// [`check`](Self::check) after changing them.
+    pub fn params_mut(&mut self) -> Vec<(&'static str, &mut Real)> {
+        match self {
+            GrowthPreset::QuadraticWide | GrowthPreset::QuadraticOriginal => Vec::new(),
+            GrowthPreset::Quadratic { a, b } => vec![("a", a), ("b", b)],
+            GrowthPreset::GaussianBell { mu, sigma } => vec![("mu", mu), ("sigma", sigma)],
+            GrowthPreset::StepBand { low, high } => vec![("low", low), ("high", high)],
+            GrowthPreset::Interval { b1, b2, d1, d2, width } =>
+                vec![("b1", b1), ("b2", b2), ("d1", d1), ("d2", d2), ("width", width)],
+            GrowthPreset::MexicanHat { mu1, sigma1, amp1, mu2, sigma2, amp2 } =>
+                vec![("mu1", mu1), ("sigma1", sigma1), ("amp1", amp1), ("mu2", mu2), ("sigma2", sigma2), ("amp2", amp2)],
+        }
+    }
+
+    /// Check the parameters, returning a description of the problem if any.
+    pub fn check(&self) -> Result<(), String> {
+        match *self {
+            GrowthPreset::QuadraticWide | GrowthPreset::QuadraticOriginal => Ok(()),
+            GrowthPreset::Quadratic { a, b } => {
+                if a.is_finite() && b.is_finite() {
+                    Ok(())
+                } else {
+                    Err(format!("quadratic growth needs finite coefficients, got a={} b={}", a, b))
+                }
+            }
+            GrowthPreset::GaussianBell { mu, sigma } => check_bell(mu, sigma),
+            GrowthPreset::StepBand { low, high } => {
+                if !low.is_finite() || !high.is_finite() || low > high {
+                    Err(format!("step band needs finite bounds with low <= high, got low={} high={}", low, high))
+                } else {
+                    Ok(())
+                }
+            }
+            GrowthPreset::Interval { b1, b2, d1, d2, width } => {
+                if ![b1, b2, d1, d2, width].iter().all(|v| v.is_finite()) {
+                    Err(format!("interval growth needs finite parameters, got {}", self))
+                } else if b1 > b2 || d1 > d2 {
+                    Err(format!("interval growth needs b1 <= b2 and d1 <= d2, got {}", self))
+                } else if width < 0.0 {
+                    Err(format!("interval growth needs a non-negative edge width, got {}", width))
+                } else {
+                    Ok(())
+                }
+            }
+            GrowthPreset::MexicanHat { mu1, sigma1, amp1, mu2, sigma2, amp2 } => {
+                if ![mu1, sigma1, amp1, mu2, sigma2, amp2].iter().all(|v| v.is_finite()) {
+                    Err(format!("mexican hat growth needs finite parameters, got {}", self))
+                } else if sigma1 <= 0.0 || sigma2 <= 0.0 {
+                    Err(format!("mexican hat growth needs positive sigmas, got {} and {}", sigma1, sigma2))
+                } else if amp1 < 0.0 || amp2 < 0.0 {
+                    Err(format!("mexican hat growth needs non-negative amplitudes, got {} and {}", amp1, amp2))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+impl From<GrowthPreset> for GrowthRule {
+    fn from(preset: GrowthPreset) -> Self {
+        match preset {
+            GrowthPreset::QuadraticWide => GrowthRule::Quadratic { a: 1.8, b: 0.2 },
+            GrowthPreset::QuadraticOriginal => GrowthRule::Quadratic { a: 2.0, b: 0.5 },
+            GrowthPreset::Quadratic { a, b } => GrowthRule::Quadratic { a, b },
+            preset => GrowthRule::Function(Arc::new(move |u| preset.value(u))),
+        }
+    }
+}
+
+impl fmt::Display for GrowthPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GrowthPreset::QuadraticWide => write!(f, "quadratic-wide"),
+            GrowthPreset::QuadraticOriginal => write!(f, "quadratic-original"),
+            GrowthPreset::Quadratic { a, b } => write!(f, "quadratic:{},{}", a, b),
+            GrowthPreset::GaussianBell { mu, sigma } => write!(f, "gaussian:{},{}", mu, sigma),
+            GrowthPreset::StepBand { low, high } => write!(f, "band:{},{}", low, high),
+            GrowthPreset::Interval { b1, b2, d1, d2, width } => write!(f, "interval:{},{},{},{},{}", b1, b2, d1, d2, width),
+            GrowthPreset::MexicanHat { mu1, sigma1, amp1, mu2, sigma2, amp2 } =>
+                write!(f, "mexican-hat:{},{},{},{},{},{}", mu1, sigma1, amp1, mu2, sigma2, amp2),
+        }
+    }
+}
+
+impl FromStr for GrowthPreset {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (name, args) = spec.split_once(':').unwrap_or((spec, ""));
+        let numbers = args.split(',')
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+            .map(|a| a.parse::<Real>().map_err(|_| format!("'{}' is not a number in growth spec '{}'", a, spec)))
+            .collect::<Result<Vec<Real>, String>>()?;
+
+        let preset = match (name, numbers.as_slice()) {
+            ("quadratic-wide", []) => GrowthPreset::QuadraticWide,
+            ("quadratic-original", []) => GrowthPreset::QuadraticOriginal,
+            ("quadratic", &[a, b]) => GrowthPreset::Quadratic { a, b },
+            ("gaussian", &[mu, sigma]) => GrowthPreset::GaussianBell { mu, sigma },
+            ("band", &[low, high]) => GrowthPreset::StepBand { low, high },
+            ("interval", &[b1, b2, d1, d2]) => GrowthPreset::Interval { b1, b2, d1, d2, width: 0.0 },
+            ("interval", &[b1, b2, d1, d2, width]) => GrowthPreset::Interval { b1, b2, d1, d2, width },
+            ("mexican-hat", &[mu1, sigma1, amp1, mu2, sigma2, amp2]) =>
+                GrowthPreset::MexicanHat { mu1, sigma1, amp1, mu2, sigma2, amp2 },
+            _ => return Err(format!(
+                "unknown growth spec '{}'; expected quadratic-wide, quadratic-original, quadratic:A,B, gaussian:MU,SIGMA, \
+                 band:LOW,HIGH, interval:B1,B2,D1,D2[,WIDTH] or mexican-hat:MU1,SIGMA1,AMP1,MU2,SIGMA2,AMP2", spec)),
+        };
+        preset.check()?;
+        Ok(preset)
+    }
+}
+
+/// How a step that would take a cell outside `0..=1` is kept in range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Clamp {
+    /// Add the full step and cut the result off at 0 and 1, which leaves flat
+    /// saturated plateaus inside dense blobs.
+    #[default]
+    Hard,
+    /// Scale a step towards either bound by the distance left to it, as in
+    /// logistic growth, so cells approach 0 and 1 asymptotically. Cells that
+    /// decay below [`SOFT_CLAMP_FLOOR`] snap to 0.
+    Soft,
+}
+
+/// Below this a softly clamped cell counts as dead and is set to 0, so that
+/// decaying regions end up empty instead of lingering as subnormal values.
+pub const SOFT_CLAMP_FLOOR: Real = 1e-6;
+
+impl Clamp {
+    // `cell` after a step of `delta`, kept within 0..=1; the soft clamp still
+    // cuts off the overshoot of steps larger than the whole range
+    #[inline]
+    pub(crate) fn apply(self, cell: Real, delta: Real) -> Real {
+        match self {
+            Clamp::Hard => (cell + delta).clamp(0.0, 1.0),
+            Clamp::Soft => {
+                let room = if delta > 0.0 { 1.0 - cell } else { cell };
+                let value = (cell + delta * room).clamp(0.0, 1.0);
+                if value < SOFT_CLAMP_FLOOR { 0.0 } else { value }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Clamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Clamp::Hard => "hard",
+            Clamp::Soft => "soft",
+        })
+    }
+}
+
+impl FromStr for Clamp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hard" => Ok(Clamp::Hard),
+            "soft" => Ok(Clamp::Soft),
+            _ => Err(format!("unknown clamp '{}'; expected hard or soft", s)),
+        }
+    }
+}
+
+// Unit-height Gaussian bump
+fn bell(u: Real, mu: Real, sigma: Real) -> Real {
+    let d = u - mu;
+    (-d * d / (2.0 * sigma * sigma)).exp()
+}
+
+// How far `u` lies inside `low..=high`: exactly 0 or 1 for a hard edge, a
+// product of two logistic steps `width` wide otherwise
+fn within(u: Real, low: Real, high: Real, width: Real) -> Real {
+    if width == 0.0 {
+        return if (low..=high).contains(&u) { 1.0 } else { 0.0 };
+    }
+
+    logistic_step(u, low, width) * (1.0 - logistic_step(u, high, width))
+}
+
+// Smooth step from 0 to 1 around `edge`, or a hard one for a width of 0
+fn logistic_step(x: Real, edge: Real, width: Real) -> Real {
+    if width == 0.0 {
+        return if x >= edge { 1.0 } else { 0.0 };
+    }
+
+    // A slope of 4/width makes the step go from about 12% to 88% across `width`
+    1.0 / (1.0 + (-(x - edge) * 4.0 / width).exp())
+}
+
+fn check_bell(mu: Real, sigma: Real) -> Result<(), String> {
+    if !mu.is_finite() || !sigma.is_finite() || sigma <= 0.0 {
+        Err(format!("gaussian growth needs a finite mu and a positive sigma, got mu={} sigma={}", mu, sigma))
+    } else {
+        Ok(())
+    }
+}
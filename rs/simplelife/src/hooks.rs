@@ -0,0 +1,160 @@
+//! Step hooks: callbacks that run around each simulation step so loggers,
+//! recorders and the like don't have to be wired into update() or the main loop.
+//!
+//! [`SimpleLife::run_steps`] takes a single observer closure for the simple
+//! cases; the [`HookRegistry`] is for runners juggling several of them.
+//!
+//! Execution order guarantees:
+//! - Within a step, stages run as PreStep, then the update, then PostStep, then
+//!   rendering, then PostRender.
+//! - Within a stage, hooks run in ascending priority; hooks with the same
+//!   priority run in the order they were added.
+//! - A hook that returns an error is disabled straight away and never runs
+//!   again; [`HookRegistry::run`] hands the failure back for the caller to
+//!   log, and the remaining hooks of that stage still run.
+//! - Hooks added or removed between steps take effect on the next stage run.
+
+use std::fmt;
+use std::ops::ControlFlow;
+
+use crate::{Real, SimError, SimpleLife};
+
+/// Observer for [`SimpleLife::run_steps`] that prints a warning when every cell
+/// has died. It warns once per extinction instead of on every dead step, and
+/// re-arms when the grid comes back to life (e.g. after a reinit).
+pub fn warn_on_extinction() -> impl FnMut(&SimpleLife, usize) -> ControlFlow<()> {
+    let mut warned = false;
+    
+    move |sim, _| {
+        let alive = sim.is_alive();
+        if !alive && !warned {
+            eprintln!("WARNING: All cells have died! The simulation might need adjustment.");
+        }
+        warned = !alive;
+        ControlFlow::Continue(())
+    }
+}
+
+/// Point in the step cycle at which a hook runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookStage {
+    PreStep,
+    PostStep,
+    PostRender,
+}
+
+/// Snapshot of the simulation handed to every hook.
+#[derive(Clone, Copy, Debug)]
+pub struct StepReport {
+    pub step: u64,
+    pub active_cells: usize,
+    pub mass: Real,
+    /// [`SimpleLife::mean_local_variance`], only filled in on request by
+    /// [`with_local_variance`](Self::with_local_variance) as it costs a pass
+    /// over the grid.
+    pub mean_local_variance: Option<Real>,
+}
+
+impl StepReport {
+    pub fn new(sim: &SimpleLife, step: u64) -> Self {
+        StepReport {
+            step,
+            active_cells: sim.active_cells(),
+            mass: sim.total_mass(),
+            mean_local_variance: None,
+        }
+    }
+
+    /// Also record the mean local variance over `(2r+1)x(2r+1)` windows.
+    pub fn with_local_variance(self, sim: &SimpleLife, window_radius: usize) -> Self {
+        StepReport { mean_local_variance: Some(sim.mean_local_variance(window_radius)), ..self }
+    }
+}
+
+/// Error returned by a hook; the hook is disabled when it happens.
+#[derive(Debug)]
+pub struct HookError(pub String);
+
+impl fmt::Display for HookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<SimError> for HookError {
+    fn from(e: SimError) -> Self {
+        HookError(e.to_string())
+    }
+}
+
+/// A hook that failed and was disabled, as returned by [`HookRegistry::run`].
+#[derive(Debug)]
+pub struct HookFailure {
+    pub name: String,
+    pub step: u64,
+    pub error: HookError,
+}
+
+impl fmt::Display for HookFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "hook '{}' failed at step {}: {}", self.name, self.step, self.error)
+    }
+}
+
+/// Callback run by the [`HookRegistry`].
+pub type HookFn = Box<dyn FnMut(&mut SimpleLife, &StepReport) -> Result<(), HookError>>;
+
+struct Hook {
+    name: String,
+    stage: HookStage,
+    priority: i32,
+    enabled: bool,
+    callback: HookFn,
+}
+
+/// Ordered collection of hooks, see the module docs for the ordering guarantees.
+#[derive(Default)]
+pub struct HookRegistry {
+    // Kept sorted by priority; the stable sort preserves insertion order for ties
+    hooks: Vec<Hook>,
+}
+
+impl HookRegistry {
+    /// Register a callback under `name` for `stage`; lower priorities run first.
+    pub fn add_hook(&mut self, name: &str, stage: HookStage, priority: i32, callback: HookFn) {
+        self.hooks.push(Hook {
+            name: name.to_string(),
+            stage,
+            priority,
+            enabled: true,
+            callback,
+        });
+        self.hooks.sort_by_key(|h| h.priority);
+    }
+
+    /// Remove every hook registered under this name, returning whether any was found.
+    pub fn remove_hook(&mut self, name: &str) -> bool {
+        let before = self.hooks.len();
+        self.hooks.retain(|h| h.name != name);
+        self.hooks.len() != before
+    }
+
+    /// Whether any enabled hook is registered for `stage`, so callers can skip
+    /// building a [`StepReport`] nobody would read.
+    pub fn has_hooks(&self, stage: HookStage) -> bool {
+        self.hooks.iter().any(|h| h.stage == stage && h.enabled)
+    }
+
+    /// Run all enabled hooks of one stage, returning the ones that failed
+    /// and have been disabled.
+    pub fn run(&mut self, stage: HookStage, sim: &mut SimpleLife, report: &StepReport) -> Vec<HookFailure> {
+        let mut failures = Vec::new();
+        for hook in self.hooks.iter_mut().filter(|h| h.stage == stage && h.enabled) {
+            if let Err(error) = (hook.callback)(sim, report) {
+                hook.enabled = false;
+                failures.push(HookFailure { name: hook.name.clone(), step: report.step, error });
+            }
+        }
+        failures
+    }
+}
@@ -0,0 +1,505 @@
+//! Kernel weight functions used to build the convolution table.
+
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::SimError;
+
+/// Anything that can say how much a neighbor at offset `(dx, dy)` contributes
+/// to the potential of a kernel with the given radius.
+///
+/// The returned weights don't need to be normalized; `init_kernel` evaluates the
+/// kernel over the table given by [`extent`](Kernel::extent) and scales the
+/// result according to the [`Normalization`].
+pub trait Kernel: Send + Sync {
+    /// Unnormalized weight at offset `(dx, dy)` from the center.
+    fn weight(&self, dx: f32, dy: f32, radius: f32) -> f32;
+
+    /// Half-width and half-height of the table needed to hold every non-zero
+    /// weight, i.e. the table is `(2 * rx + 1) x (2 * ry + 1)`. Square by default.
+    fn extent(&self, radius: usize) -> (usize, usize) {
+        (radius, radius)
+    }
+
+    /// Check the parameters, returning a description of the problem if any.
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Check the parameters against the kernel radius they will be used with.
+    fn validate_radius(&self, _radius: usize) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Shared handle to a kernel, as stored by the simulation.
+pub type KernelFn = Arc<dyn Kernel>;
+
+/// Shape of the convolution kernel as a function of the distance from its
+/// center, measured as a fraction `r` of the kernel radius.
+///
+/// Whatever the shape, the table built from it is normalized, by default to sum to 1.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum KernelShape {
+    /// Cone falling off linearly from 1 at the center to 0 at the radius.
+    #[default]
+    Linear,
+    /// Gaussian shell `exp(-(r - mu)^2 / (2 sigma^2))` peaking at `mu`.
+    /// `mu = 0` gives a plain centered Gaussian blob; something like
+    /// `mu = 0.5, sigma = 0.15` is the annular kernel behind Lenia's gliders.
+    Gaussian { mu: f32, sigma: f32 },
+    /// Centered Gaussian `exp(-d^2 / (2 sigma^2))` with `sigma` in cells, so the
+    /// falloff is independent of the radius, which only truncates the table.
+    GaussianBlob { sigma: f32 },
+    /// Lenia-style concentric rings: the radius is split into `peaks.len()`
+    /// equal bands, ring `i` covering `[i/n, (i+1)/n]` of the radius, each
+    /// holding a smooth bump scaled by its peak value.
+    Ring { peaks: Vec<f32> },
+    /// Rings like [`Ring`](KernelShape::Ring), but each band holds Lenia's
+    /// polynomial core `(4 u (1 - u))^alpha`, `u` being the position within the
+    /// band. A single shell is `peaks: vec![1.0]`.
+    Polynomial { alpha: f32, peaks: Vec<f32> },
+    /// Exponential decay `exp(-lambda * r)`, concentrating influence closer to
+    /// the center than the linear cone as `lambda` grows; 0 outside the radius.
+    Exponential { lambda: f32 },
+    /// Piecewise-linear profile through `(r, weight)` samples with `r` a fraction
+    /// of the radius, strictly increasing within `0..=1`. The first weight holds
+    /// towards the center and the kernel is 0 past the last sample, so
+    /// `[(0, 1), (1, 0)]` is exactly the linear cone. See [`KernelShape::from_csv`].
+    RadialProfile { samples: Vec<(f32, f32)> },
+    /// Weight 1 between `inner` and `outer` cells from the center, 0 elsewhere,
+    /// like SmoothLife's outer neighborhood. `antialias` ramps each edge over
+    /// one cell, which avoids the directional artifacts of a jagged ring.
+    Annulus { inner: f32, outer: f32, antialias: bool },
+}
+
+impl KernelShape {
+    /// Weight at `distance` cells from the center of a kernel with the given
+    /// radius; 0 outside the radius.
+    pub fn profile(&self, distance: f32, radius: f32) -> f32 {
+        let r = distance / radius;
+        match self {
+            // Kept as a single expression so the default kernel stays bit-for-bit identical
+            KernelShape::Linear => (1.0 - r).max(0.0),
+            KernelShape::Gaussian { mu, sigma } => {
+                if r > 1.0 {
+                    0.0
+                } else {
+                    (-(r - mu) * (r - mu) / (2.0 * sigma * sigma)).exp()
+                }
+            }
+            KernelShape::GaussianBlob { sigma } => {
+                if r > 1.0 {
+                    0.0
+                } else {
+                    (-distance * distance / (2.0 * sigma * sigma)).exp()
+                }
+            }
+            KernelShape::Ring { peaks } => rings(peaks, r, bump),
+            KernelShape::Polynomial { alpha, peaks } => rings(peaks, r, |u| (4.0 * u * (1.0 - u)).max(0.0).powf(*alpha)),
+            KernelShape::Exponential { lambda } => {
+                if r > 1.0 {
+                    0.0
+                } else {
+                    (-lambda * r).exp()
+                }
+            }
+            KernelShape::RadialProfile { samples } => {
+                let Some(next) = samples.iter().position(|&(sr, _)| sr >= r) else {
+                    return 0.0;
+                };
+                if next == 0 {
+                    return samples[0].1;
+                }
+
+                let ((r0, w0), (r1, w1)) = (samples[next - 1], samples[next]);
+                w0 + (w1 - w0) * ((r - r0) / (r1 - r0))
+            }
+            KernelShape::Annulus { inner, outer, antialias } => {
+                if r > 1.0 {
+                    0.0
+                } else if *antialias {
+                    (distance - inner + 0.5).clamp(0.0, 1.0) * (outer - distance + 0.5).clamp(0.0, 1.0)
+                } else if distance >= *inner && distance <= *outer {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+
+    /// The scalar parameters by name, for live editing; call [`check`](Self::check)
+    /// after changing them. Ring peaks and profile samples are not included.
+    pub fn params_mut(&mut self) -> Vec<(&'static str, &mut f32)> {
+        match self {
+            KernelShape::Linear | KernelShape::Ring { .. } | KernelShape::RadialProfile { .. } => Vec::new(),
+            KernelShape::Gaussian { mu, sigma } => vec![("mu", mu), ("sigma", sigma)],
+            KernelShape::GaussianBlob { sigma } => vec![("sigma", sigma)],
+            KernelShape::Polynomial { alpha, .. } => vec![("alpha", alpha)],
+            KernelShape::Exponential { lambda } => vec![("lambda", lambda)],
+            KernelShape::Annulus { inner, outer, .. } => vec![("inner", inner), ("outer", outer)],
+        }
+    }
+
+    /// Check the parameters, returning a description of the problem if any.
+    pub fn check(&self) -> Result<(), String> {
+        match self {
+            KernelShape::Linear => Ok(()),
+            KernelShape::Gaussian { mu, sigma } => {
+                if !mu.is_finite() || !sigma.is_finite() || *sigma <= 0.0 {
+                    Err(format!("gaussian kernel needs a finite mu and a positive sigma, got mu={} sigma={}", mu, sigma))
+                } else {
+                    Ok(())
+                }
+            }
+            KernelShape::GaussianBlob { sigma } => {
+                if !sigma.is_finite() || *sigma <= 0.0 {
+                    Err(format!("gaussian blob kernel needs a positive sigma, got {}", sigma))
+                } else {
+                    Ok(())
+                }
+            }
+            KernelShape::Ring { peaks } => check_peaks(peaks),
+            KernelShape::Polynomial { alpha, peaks } => {
+                if !alpha.is_finite() || *alpha <= 0.0 {
+                    Err(format!("polynomial kernel needs a positive alpha, got {}", alpha))
+                } else {
+                    check_peaks(peaks)
+                }
+            }
+            KernelShape::Exponential { lambda } => {
+                if !lambda.is_finite() || *lambda < 0.0 {
+                    Err(format!("exponential kernel needs a non-negative lambda, got {}", lambda))
+                } else {
+                    Ok(())
+                }
+            }
+            KernelShape::RadialProfile { samples } => {
+                if samples.len() < 2 {
+                    return Err(format!("radial profile needs at least two samples, got {}", samples.len()));
+                }
+                if let Some(&(r, w)) = samples.iter().find(|(r, w)| !(0.0..=1.0).contains(r) || !w.is_finite()) {
+                    return Err(format!("radial profile sample ({}, {}) must have r in 0..=1 and a finite weight", r, w));
+                }
+                if let Some(pair) = samples.windows(2).find(|pair| pair[1].0 <= pair[0].0) {
+                    return Err(format!("radial profile radii must increase, got {} after {}", pair[1].0, pair[0].0));
+                }
+                if samples.iter().all(|&(_, w)| w == 0.0) {
+                    return Err("radial profile is zero everywhere".to_string());
+                }
+                Ok(())
+            }
+            KernelShape::Annulus { inner, outer, .. } => {
+                if !inner.is_finite() || !outer.is_finite() || *inner < 0.0 || inner >= outer {
+                    Err(format!("annulus kernel needs 0 <= inner < outer, got inner={} outer={}", inner, outer))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+impl KernelShape {
+    /// Load a [`RadialProfile`](KernelShape::RadialProfile) from a CSV file with
+    /// one `r_fraction,weight` pair per line. Blank lines, `#` comments and a
+    /// header line are skipped.
+    pub fn from_csv(path: &str) -> Result<KernelShape, SimError> {
+        let text = fs::read_to_string(path).map_err(|e| SimError::io(path, e))?;
+        let invalid = |msg: String| SimError::InvalidParameter(format!("{}: {}", path, msg));
+
+        let mut samples = Vec::new();
+        let mut header_skipped = false;
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let pair = line.split_once(',')
+                .and_then(|(r, w)| Some((parse_number(r.trim())?, parse_number(w.trim())?)));
+            match pair {
+                Some(sample) => samples.push(sample),
+                // Anything non-numeric ahead of the data is taken as the header
+                None if samples.is_empty() && !header_skipped => header_skipped = true,
+                None => return Err(invalid(format!("line {} is not an 'r_fraction,weight' pair: '{}'", i + 1, line))),
+            }
+        }
+
+        let shape = KernelShape::RadialProfile { samples };
+        shape.check().map_err(invalid)?;
+        Ok(shape)
+    }
+}
+
+impl Kernel for KernelShape {
+    fn weight(&self, dx: f32, dy: f32, radius: f32) -> f32 {
+        let distance = (dx*dx + dy*dy).sqrt();
+        self.profile(distance, radius)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        self.check()
+    }
+
+    fn validate_radius(&self, radius: usize) -> Result<(), String> {
+        match self {
+            KernelShape::Annulus { outer, .. } if *outer > radius as f32 =>
+                Err(format!("annulus outer radius {} exceeds the kernel radius {}", outer, radius)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Prints the spec [`FromStr`] reads back, e.g. `gaussian:0.5,0.15`. Radial
+/// profiles come from CSV files, so they print their samples as
+/// `profile:R,W,R,W,...` instead, which does not parse.
+impl fmt::Display for KernelShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |values: &[f32]| values.iter().map(f32::to_string).collect::<Vec<_>>().join(",");
+        match self {
+            KernelShape::Linear => write!(f, "linear"),
+            KernelShape::Gaussian { mu, sigma } => write!(f, "gaussian:{},{}", mu, sigma),
+            KernelShape::GaussianBlob { sigma } => write!(f, "blob:{}", sigma),
+            KernelShape::Ring { peaks } => write!(f, "ring:{}", list(peaks)),
+            KernelShape::Polynomial { alpha, peaks } => write!(f, "poly:{},{}", alpha, list(peaks)),
+            KernelShape::Exponential { lambda } => write!(f, "exp:{}", lambda),
+            KernelShape::RadialProfile { samples } => {
+                let flat: Vec<f32> = samples.iter().flat_map(|&(r, w)| [r, w]).collect();
+                write!(f, "profile:{}", list(&flat))
+            }
+            KernelShape::Annulus { inner, outer, antialias: false } => write!(f, "annulus:{},{}", inner, outer),
+            KernelShape::Annulus { inner, outer, antialias: true } => write!(f, "annulus-aa:{},{}", inner, outer),
+        }
+    }
+}
+
+impl FromStr for KernelShape {
+    type Err = String;
+
+    /// Parse a command-line spec: `linear`, `blob:SIGMA`, `gaussian:MU,SIGMA`,
+    /// `ring:PEAK,PEAK,...`, `poly:ALPHA[,PEAK,...]`, `exp:LAMBDA`, `annulus:INNER,OUTER` (`annulus-aa` for the
+    /// antialiased edges), or `csv:PATH` for a radial profile file.
+    ///
+    /// Numbers may be written as fractions and the list may be bracketed, so
+    /// published Lenia ring weights like `ring:[1,1/2,1/4]` paste in directly.
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (name, args) = spec.split_once(':').unwrap_or((spec, ""));
+        if name == "csv" {
+            return KernelShape::from_csv(args).map_err(|e| e.to_string());
+        }
+
+        let args = args.trim().trim_start_matches('[').trim_end_matches(']');
+        let numbers = args.split(',')
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+            .map(|a| parse_number(a).ok_or_else(|| format!("'{}' is not a number in kernel spec '{}'", a, spec)))
+            .collect::<Result<Vec<f32>, String>>()?;
+
+        let shape = match (name, numbers.as_slice()) {
+            ("linear", []) => KernelShape::Linear,
+            ("blob", &[sigma]) => KernelShape::GaussianBlob { sigma },
+            ("gaussian", &[mu, sigma]) => KernelShape::Gaussian { mu, sigma },
+            ("ring", peaks) if !peaks.is_empty() => KernelShape::Ring { peaks: peaks.to_vec() },
+            ("poly", &[alpha]) => KernelShape::Polynomial { alpha, peaks: vec![1.0] },
+            ("poly", &[alpha, ref peaks @ ..]) if !peaks.is_empty() => KernelShape::Polynomial { alpha, peaks: peaks.to_vec() },
+            ("exp", &[lambda]) => KernelShape::Exponential { lambda },
+            ("annulus", &[inner, outer]) => KernelShape::Annulus { inner, outer, antialias: false },
+            ("annulus-aa", &[inner, outer]) => KernelShape::Annulus { inner, outer, antialias: true },
+            _ => return Err(format!(
+                "unknown kernel spec '{}'; expected linear, blob:SIGMA, gaussian:MU,SIGMA, ring:PEAK,..., \
+                 poly:ALPHA[,PEAK,...], exp:LAMBDA, annulus:INNER,OUTER or csv:PATH", spec)),
+        };
+        shape.check()?;
+        Ok(shape)
+    }
+}
+
+/// Stretches another kernel along a rotated axis, for directional growth.
+///
+/// Distances are measured in a frame rotated by `rotation_degrees` from the x
+/// axis towards the y axis (clockwise on screen, as y points down) and scaled so the
+/// kernel reaches `radius * aspect_ratio` along that axis and `radius` across it.
+pub struct Anisotropic {
+    base: KernelFn,
+    aspect_ratio: f32,
+    rotation_degrees: f32,
+}
+
+impl Anisotropic {
+    pub fn new(base: KernelFn, aspect_ratio: f32, rotation_degrees: f32) -> Self {
+        Anisotropic { base, aspect_ratio, rotation_degrees }
+    }
+}
+
+impl Kernel for Anisotropic {
+    fn weight(&self, dx: f32, dy: f32, radius: f32) -> f32 {
+        let (sin, cos) = self.rotation_degrees.to_radians().sin_cos();
+        let along = dx * cos + dy * sin;
+        let across = -dx * sin + dy * cos;
+        self.base.weight(along / self.aspect_ratio, across, radius)
+    }
+
+    fn extent(&self, radius: usize) -> (usize, usize) {
+        let (rx, ry) = self.base.extent(radius);
+        let (sin, cos) = self.rotation_degrees.to_radians().sin_cos();
+        let along = rx as f32 * self.aspect_ratio;
+        let across = ry as f32;
+
+        // Bounding box of the rotated ellipse; the slack keeps rounding noise
+        // (cos 90° is not exactly 0) from adding a whole extra row or column
+        let half_width = ((along * cos).powi(2) + (across * sin).powi(2)).sqrt();
+        let half_height = ((along * sin).powi(2) + (across * cos).powi(2)).sqrt();
+        ((half_width - 1e-3).ceil() as usize, (half_height - 1e-3).ceil() as usize)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if !self.aspect_ratio.is_finite() || self.aspect_ratio <= 0.0 {
+            return Err(format!("aspect ratio must be positive, got {}", self.aspect_ratio));
+        }
+        if !self.rotation_degrees.is_finite() {
+            return Err(format!("rotation must be finite, got {}", self.rotation_degrees));
+        }
+        self.base.validate()
+    }
+
+    fn validate_radius(&self, radius: usize) -> Result<(), String> {
+        self.base.validate_radius(radius)
+    }
+}
+
+/// Pins a kernel to its own radius, whatever the simulation's kernel radius,
+/// so kernels of different sizes can be combined.
+pub struct WithRadius {
+    base: KernelFn,
+    radius: usize,
+}
+
+impl WithRadius {
+    pub fn new(base: KernelFn, radius: usize) -> Self {
+        WithRadius { base, radius }
+    }
+}
+
+impl Kernel for WithRadius {
+    fn weight(&self, dx: f32, dy: f32, _radius: f32) -> f32 {
+        self.base.weight(dx, dy, self.radius as f32)
+    }
+
+    fn extent(&self, _radius: usize) -> (usize, usize) {
+        self.base.extent(self.radius)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.radius == 0 {
+            return Err("kernel radius must be at least 1".to_string());
+        }
+        self.base.validate()
+    }
+
+    fn validate_radius(&self, _radius: usize) -> Result<(), String> {
+        self.base.validate_radius(self.radius)
+    }
+}
+
+/// How `init_kernel` scales the kernel table once it is built.
+///
+/// The potential is the kernel-weighted sum of the neighborhood, so the
+/// normalization decides which potentials the growth function gets to see:
+/// - `SumToOne`: a weighted average, always in `0..=1` like the cells. The
+///   default growth curve is tuned for this.
+/// - `PeakToOne`: up to the number of cells the kernel covers (hundreds for a
+///   radius of 13), so the growth curve has to be rescaled accordingly.
+/// - `None`: whatever the kernel function returns; the range depends entirely
+///   on the kernel, which suits kernels with physically meaningful weights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Normalization {
+    /// Weights sum to 1.
+    #[default]
+    SumToOne,
+    /// The largest weight is 1.
+    PeakToOne,
+    /// Weights are used as the kernel returns them.
+    None,
+}
+
+/// Kernel drawn as a grayscale image, pixel brightness being the weight.
+///
+/// The image is centered on the cell, so both sides have to be odd; the table
+/// size comes from the image and ignores the kernel radius.
+pub(crate) struct ImageKernel {
+    width: usize,
+    height: usize,
+    weights: Vec<f32>,
+}
+
+impl ImageKernel {
+    /// Wrap a row-major table of `width` x `height` weights; both have to be odd.
+    pub(crate) fn new(width: usize, height: usize, weights: Vec<f32>) -> Result<Self, String> {
+        if width.is_multiple_of(2) || height.is_multiple_of(2) {
+            return Err(format!("kernel image must have odd dimensions to have a center cell, got {}x{}", width, height));
+        }
+        if !weights.iter().any(|&w| w > 0.0) {
+            return Err("kernel image is completely black".to_string());
+        }
+        Ok(ImageKernel { width, height, weights })
+    }
+}
+
+impl Kernel for ImageKernel {
+    fn weight(&self, dx: f32, dy: f32, _radius: f32) -> f32 {
+        // Rounded, so supersampled points land in the pixel they fall into
+        let x = dx.round() as isize + (self.width / 2) as isize;
+        let y = dy.round() as isize + (self.height / 2) as isize;
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return 0.0;
+        }
+        self.weights[y as usize * self.width + x as usize]
+    }
+
+    fn extent(&self, _radius: usize) -> (usize, usize) {
+        (self.width / 2, self.height / 2)
+    }
+}
+
+// A plain number or a fraction like `2/3`
+fn parse_number(s: &str) -> Option<f32> {
+    match s.split_once('/') {
+        Some((num, den)) => Some(num.trim().parse::<f32>().ok()? / den.trim().parse::<f32>().ok()?),
+        None => s.parse().ok(),
+    }
+}
+
+// Split the radius into one band per peak and evaluate `core` on the position
+// within the band, scaled by that band's peak
+fn rings(peaks: &[f32], r: f32, core: impl Fn(f32) -> f32) -> f32 {
+    if r >= 1.0 {
+        return 0.0;
+    }
+
+    let scaled = r * peaks.len() as f32;
+    let band = scaled.floor() as usize;
+    peaks[band.min(peaks.len() - 1)] * core(scaled - band as f32)
+}
+
+fn check_peaks(peaks: &[f32]) -> Result<(), String> {
+    if peaks.iter().any(|p| !p.is_finite() || *p < 0.0) {
+        Err(format!("ring kernel peaks must be finite and non-negative, got {:?}", peaks))
+    } else if !peaks.iter().any(|&p| p > 0.0) {
+        Err("ring kernel needs at least one positive peak".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+// Smooth bump on 0..1 that vanishes at both ends and peaks at 1 in the middle
+fn bump(u: f32) -> f32 {
+    if u <= 0.0 || u >= 1.0 {
+        0.0
+    } else {
+        (4.0 - 1.0 / (u * (1.0 - u))).exp()
+    }
+}
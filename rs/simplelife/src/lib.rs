@@ -0,0 +1,51 @@
+//! A continuous Lenia-type cellular automaton, but simpler.
+//!
+//! [`SimpleLife`] holds the grid and the kernel and knows how to step, render
+//! and save itself; the binaries in this crate are thin front ends around it.
+
+/// Floating-point type of the grid, kernel and potential.
+///
+/// `f32` by default; the `f64` feature switches the whole simulation to double
+/// precision for long runs where f32 rounding starts to show. Rendering and
+/// export convert to 8-bit color either way.
+#[cfg(not(feature = "f64"))]
+pub type Real = f32;
+#[cfg(feature = "f64")]
+pub type Real = f64;
+
+mod active;
+pub mod backends;
+mod builder;
+mod conv;
+mod error;
+mod font;
+#[cfg(feature = "gpu")]
+mod gpu;
+mod grid;
+mod growth;
+mod kernel;
+mod multichannel;
+mod palette;
+mod quantized;
+mod render;
+pub mod hooks;
+pub mod randomizer;
+pub mod recorder;
+pub mod report;
+pub mod selftest;
+mod sim;
+mod state;
+
+pub use builder::{BuildError, SimpleLifeBuilder};
+pub use conv::{AUTO_FFT_RADIUS, Boundary, ConvMethod};
+pub use error::{CellError, SimError};
+#[cfg(feature = "gpu")]
+pub use gpu::GpuLife;
+pub use grid::Grid;
+pub use growth::{Clamp, GaussianGrowth, GrowthPreset, GrowthRule, GrowthSchedule, ParametricGrowthFn, SOFT_CLAMP_FLOOR, StateGrowthFn};
+pub use kernel::{Anisotropic, Kernel, KernelFn, KernelShape, Normalization, WithRadius};
+pub use multichannel::MultiLife;
+pub use palette::Palette;
+pub use quantized::{QuantizedLife, dequantize, quantize};
+pub use render::{PaletteRenderer, PixelFormat, Renderer, ScaleMode, Viewport};
+pub use sim::{ACTIVE_THRESHOLD, EXPLOSION_FRACTION, GrowthFn, MemoryBudget, MemoryEntry, Outcome, SimpleLife, default_growth};
@@ -1,215 +1,555 @@
-use std::fs::File;
-use std::io::Write;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
 use std::time::{Duration, Instant};
-use rand::Rng;
-use minifb::{Key, Window, WindowOptions};
+use clap::Parser;
+use minifb::{Key, MouseButton, Window, WindowOptions};
+use simplelife::{Clamp, ConvMethod, GrowthPreset, KernelShape, Palette, Real, ScaleMode, SimError, SimpleLife};
+use simplelife::backends::{BackendMatrix, MatrixCase};
+use simplelife::hooks::{HookRegistry, HookStage, StepReport, warn_on_extinction};
+use simplelife::randomizer::{Bound, Bounds, History, Param, ParamSet, ParamsLog, Randomizer};
+use simplelife::recorder::{GifRecorder, StripOptions, export_strip};
+use simplelife::selftest::{report_self_test, self_test};
 
-struct SimpleLife {
+mod presenter;
+mod triple_buffer;
+use presenter::Presenter;
+use triple_buffer::TripleBuffer;
+
+// dt = 0 is a legitimate "frozen" state; negative dt runs the (irreversible) dynamics
+// backwards, which the builder only accepts when explicitly allowed
+fn log_dt(dt: Real) {
+    if dt < 0.0 {
+        println!("WARNING: Running with negative dt {}. The dynamics are not reversible, expect artifacts.", dt);
+    } else if dt == 0.0 {
+        println!("dt is 0: dynamics are frozen, rendering and input still work.");
+    }
+}
+
+/// Interactive SimpleLife viewer.
+#[derive(Parser)]
+struct Args {
+    /// Grid width in cells
+    #[arg(long, default_value_t = 400)]
     width: usize,
+    /// Grid height in cells
+    #[arg(long, default_value_t = 400)]
     height: usize,
-    grid: Vec<f32>,
-    kernel: Vec<f32>,
-    kernel_radius: usize,
-    dt: f32,
+    /// Kernel radius in cells
+    #[arg(long, default_value_t = 13)]
+    radius: usize,
+    /// Time step; 0 freezes the dynamics
+    #[arg(long, default_value_t = 0.05, allow_negative_numbers = true)]
+    dt: Real,
+    /// Accept a negative dt
+    #[arg(long)]
+    allow_negative_dt: bool,
+    /// Close after this many steps instead of running until the window is closed
+    #[arg(long)]
+    steps: Option<u64>,
+    /// Kernel shape: linear, blob:SIGMA, gaussian:MU,SIGMA, ring:PEAK,..., poly:ALPHA[,PEAK,...], exp:LAMBDA, annulus[-aa]:INNER,OUTER or csv:PATH
+    #[arg(long, default_value = "linear")]
+    kernel: KernelShape,
+    /// Growth curve: quadratic-wide, quadratic-original, quadratic:A,B, gaussian:MU,SIGMA,
+    /// band:LOW,HIGH, `interval:B1,B2,D1,D2[,WIDTH]` or mexican-hat:MU1,SIGMA1,AMP1,MU2,SIGMA2,AMP2
+    #[arg(long, default_value = "quadratic-wide")]
+    growth: GrowthPreset,
+    /// Convolution method: direct, fft, or auto to pick FFT for kernels of radius 6 and up
+    #[arg(long, default_value = "auto")]
+    conv: ConvMethod,
+    /// How cells are kept within 0..=1: hard, or soft to let dense blobs saturate smoothly
+    #[arg(long, default_value = "hard")]
+    clamp: Clamp,
+    /// Stretch the kernel by this factor along the --rotation axis
+    #[arg(long, default_value_t = 1.0)]
+    aspect_ratio: f32,
+    /// Direction of the kernel stretch in degrees
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    rotation: f32,
+    /// Average NxN samples per kernel cell for a smoother, rounder kernel
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    supersample: usize,
+    /// Print the kernel table before starting
+    #[arg(long)]
+    dump_kernel: bool,
+    /// Save the kernel table as a PGM or PNG image before starting
+    #[arg(long, value_name = "PATH")]
+    save_kernel: Option<String>,
+    /// Run the full self-test and exit
+    #[arg(long)]
+    self_test: bool,
+    /// Exit successfully even if the self-test fails
+    #[arg(long)]
+    force: bool,
+    /// Skip the quick self-test at startup
+    #[arg(long)]
+    skip_self_test: bool,
+    /// Step every convolution backend on a suite of seeded grids, compare
+    /// each with a brute-force reference, print the table and exit; the exit
+    /// code is nonzero if any backend is off by more than its tolerance
+    #[arg(long)]
+    backend_matrix: bool,
+    /// Print a breakdown of the memory used by the simulation
+    #[arg(long)]
+    memory: bool,
+    /// Keep the simulation within this many MiB, giving up the FFT and then activity
+    /// tracking if needed, and warn if even that is not enough
+    #[arg(long, value_name = "MB")]
+    memory_budget: Option<f64>,
+    /// Seed for the initial pattern
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Start from a grayscale PGM or PNG image instead of a random pattern
+    #[arg(long, value_name = "PATH")]
+    pattern: Option<String>,
+    /// Stretch the picture to the window instead of letterboxing it
+    #[arg(long)]
+    stretch: bool,
+    /// What F3 randomizes: any of growth, kernel and dt, comma-separated
+    #[arg(long, value_name = "PARTS", value_delimiter = ',', default_value = "growth,kernel,dt")]
+    randomize: Vec<Param>,
+    /// Largest change F3 makes to a parameter, as a fraction of its value
+    #[arg(long, default_value_t = 0.25)]
+    randomize_spread: f64,
+    /// Keep a randomized parameter within a range, e.g. dt=0.02..0.2 or growth.sigma=0.01..0.05;
+    /// may be repeated
+    #[arg(long, value_name = "NAME=MIN..MAX")]
+    randomize_bound: Vec<Bound>,
+    /// Start from a fresh random pattern whenever F3 or F4 changes the parameters
+    #[arg(long)]
+    randomize_reinit: bool,
+    /// Append every parameter set in use to this JSON Lines file, with a
+    /// thumbnail of the grid saved next to it for each
+    #[arg(long, value_name = "PATH")]
+    params_log: Option<String>,
 }
 
-impl SimpleLife {
-    // All your existing methods remain unchanged...
-    
-    fn new(width: usize, height: usize, kernel_radius: usize, dt: f32) -> Self {
-        let mut sim = SimpleLife {
-            width,
-            height,
-            grid: vec![0.0; width * height],
-            kernel: vec![0.0; (2 * kernel_radius + 1) * (2 * kernel_radius + 1)],
-            kernel_radius,
-            dt,
-        };
-        
-        sim.init_kernel();
-        sim
-    }
+// Set every cell within `radius` of (cx, cy) to `value`, wrapping around the edges
+fn paint_brush(sim: &mut SimpleLife, cx: usize, cy: usize, radius: usize, value: Real) {
+    let (width, height) = (sim.width() as isize, sim.height() as isize);
+    let r = radius as isize;
     
-    fn init_kernel(&mut self) {
-        let kernel_size = 2 * self.kernel_radius + 1;
-        let mut kernel_sum = 0.0;
-        
-        for y in 0..kernel_size {
-            for x in 0..kernel_size {
-                let dx = x as f32 - self.kernel_radius as f32;
-                let dy = y as f32 - self.kernel_radius as f32;
-                let distance = (dx*dx + dy*dy).sqrt();
-                
-                // Linear falloff from center
-                let value = (1.0 - distance / self.kernel_radius as f32).max(0.0);
-                self.kernel[y * kernel_size + x] = value;
-                kernel_sum += value;
+    for dy in -r..=r {
+        for dx in -r..=r {
+            if dx * dx + dy * dy <= r * r {
+                let x = (cx as isize + dx).rem_euclid(width) as usize;
+                let y = (cy as isize + dy).rem_euclid(height) as usize;
+                sim.set_unchecked(x, y, value);
             }
         }
-        
-        // Normalize kernel
-        for k in &mut self.kernel {
-            *k /= kernel_sum;
+    }
+}
+
+fn finish_recording(recorder: GifRecorder) {
+    let path = recorder.path().display().to_string();
+    match recorder.finish() {
+        Ok(frames) => println!("Saved {} frames to {}", frames, path),
+        Err(e) => println!("WARNING: Failed to finish recording: {}", e),
+    }
+}
+
+// Signed offset for a pair of opposing pan keys
+fn pan_delta(back: bool, forward: bool, step: usize) -> isize {
+    (forward as isize - back as isize) * step as isize
+}
+
+fn window_title(fps: f64, steps_per_second: f64, paused: bool, frame: &Frame) -> String {
+    if paused {
+        format!("SimpleLife - PAUSED at step {}, t = {:.2} (Space resumes, . steps) - {}", frame.step, frame.time, frame.growth)
+    } else {
+        format!("SimpleLife - t = {:.2} - FPS: {:.1} - {:.1} steps/s - {}", frame.time, fps, steps_per_second, frame.growth)
+    }
+}
+
+// Input from the window thread for the simulation thread
+enum Command {
+    Pause(bool),
+    // Advance a single step while paused
+    Step,
+    // Shortest time between two steps, changed with + and -
+    StepInterval(Duration),
+    ResizeKernel(isize),
+    // Nudge parameter `index` of the growth preset by `delta`
+    TuneGrowth { index: usize, delta: Real },
+    // Jump to a random parameter set (F3) or back to the one before (F4)
+    Randomize,
+    Revisit,
+    Palette(Palette),
+    ShowVariance(bool),
+    // Show the cells from (x0, y0) on, each drawn `zoom` pixels wide
+    View { x0: usize, y0: usize, zoom: usize },
+    Paint { x: usize, y: usize, radius: usize, value: Real },
+    Reinit,
+    Clear,
+    StopSaving,
+}
+
+// A rendered region of the grid and the step and simulated time it shows
+struct Frame {
+    pixels: Vec<u32>,
+    // Size of the picture in pixels
+    width: usize,
+    height: usize,
+    // Cell in the top left corner and the pixels per cell along each axis
+    origin: (usize, usize),
+    scale: usize,
+    step: u64,
+    time: f64,
+    // Growth preset in use, for the title
+    growth: GrowthPreset,
+}
+
+impl Frame {
+    // Cell under pixel (x, y) of the picture
+    fn cell(&self, x: usize, y: usize) -> (usize, usize) {
+        (self.origin.0 + x / self.scale, self.origin.1 + y / self.scale)
+    }
+}
+
+// Everything that lives on the simulation thread: the simulation, its hooks
+// and the view settings its frames are rendered with
+struct Simulation {
+    sim: SimpleLife,
+    hooks: HookRegistry,
+    commands: Receiver<Command>,
+    frames: Arc<TripleBuffer<Frame>>,
+    // The frame being rendered, traded for an older one on every publish
+    frame: Frame,
+    max_steps: Option<u64>,
+    step_interval: Duration,
+    paused: bool,
+    single_step: bool,
+    palette: Palette,
+    show_variance: bool,
+    // Top left cell and zoom factor of the view, see Command::View
+    view: (usize, usize, usize),
+    // Whether a command changed what the last published frame shows
+    redraw: bool,
+    // Parameters in use, the randomizer behind F3 and the sets F4 goes back to
+    params: ParamSet,
+    randomizer: Randomizer,
+    history: History,
+    reinit_on_randomize: bool,
+    // Every applied parameter set with a thumbnail per line, if --params-log asked for it
+    params_log: Option<ParamsLog>,
+    thumbnails: usize,
+}
+
+impl Simulation {
+    // Step, render and publish until the window thread hangs up
+    fn run(mut self) {
+        self.log_params();
+        self.publish();
+        let mut last_step = Instant::now();
+        loop {
+            // Wait for input while there is nothing to step, otherwise only until the next step is due
+            let idle = (self.paused && !self.single_step) || self.max_steps.is_some_and(|n| self.sim.steps() >= n);
+            if idle && std::mem::take(&mut self.redraw) {
+                self.publish();
+            }
+            let received = if idle {
+                self.commands.recv().map_err(RecvTimeoutError::from)
+            } else {
+                self.commands.recv_timeout(self.step_interval.saturating_sub(last_step.elapsed()))
+            };
+            match received {
+                Ok(command) => {
+                    self.apply(command);
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => return,
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+            
+            last_step = Instant::now();
+            self.single_step = false;
+            self.redraw = false;
+            
+            // Reports cost a pass over the grid, so only stages with hooks get one
+            if self.hooks.has_hooks(HookStage::PreStep) {
+                let report = StepReport::new(&self.sim, self.sim.steps());
+                self.run_hooks(HookStage::PreStep, &report);
+            }
+            self.sim.update();
+            let report = [HookStage::PostStep, HookStage::PostRender].into_iter()
+                .any(|stage| self.hooks.has_hooks(stage))
+                .then(|| StepReport::new(&self.sim, self.sim.steps()));
+            if let Some(report) = &report {
+                self.run_hooks(HookStage::PostStep, report);
+            }
+            
+            self.publish();
+            if let Some(report) = &report {
+                self.run_hooks(HookStage::PostRender, report);
+            }
+        }
+    }
+    
+    fn run_hooks(&mut self, stage: HookStage, report: &StepReport) {
+        for failure in self.hooks.run(stage, &mut self.sim, report) {
+            println!("WARNING: {}. It has been disabled.", failure);
         }
     }
     
-    fn growth_function(&self, u: f32) -> f32 {
-        // More forgiving growth function with a wider "alive" range
-        1.8 * u * (1.0 - u) - 0.2
+    // Render the view into the back frame and make it the latest one
+    fn publish(&mut self) {
+        let (width, height) = (self.sim.width(), self.sim.height());
+        let (x0, y0, zoom) = self.view;
+        // The variance view always shows the whole grid
+        let (origin, size, scale) = if self.show_variance {
+            self.frame.pixels = self.sim.create_variance_buffer(VARIANCE_WINDOW);
+            ((0, 0), (width, height), 1)
+        } else if zoom > 1 {
+            let (x0, y0, w, h) = self.sim.clamp_region(x0, y0, (width / zoom).max(1), (height / zoom).max(1));
+            self.frame.pixels = self.sim.create_buffer_region_with(self.palette, x0, y0, w, h, zoom);
+            ((x0, y0), (w * zoom, h * zoom), zoom)
+        } else {
+            // The back frame may still hold a zoomed picture of another size
+            self.frame.pixels.resize(width * height, 0);
+            self.sim.create_buffer_into(self.palette, &mut self.frame.pixels);
+            ((0, 0), (width, height), 1)
+        };
+        (self.frame.width, self.frame.height) = size;
+        self.frame.origin = origin;
+        self.frame.scale = scale;
+        self.frame.step = self.sim.steps();
+        self.frame.time = self.sim.sim_time();
+        self.frame.growth = self.params.growth;
+        self.frames.publish(&mut self.frame);
     }
     
-    fn compute_potential(&self) -> Vec<f32> {
-        let mut potential = vec![0.0; self.width * self.height];
-        let kernel_size = 2 * self.kernel_radius + 1;
+    // Nudge the growth preset's parameter `index` by `delta`, keeping the old
+    // value if the result is invalid
+    fn tune_growth(&mut self, index: usize, delta: Real) {
+        let mut tuned = self.params.growth;
+        let Some((name, value)) = tuned.params_mut().into_iter().nth(index) else {
+            println!("{} has no parameter to tune with these keys", self.params.growth);
+            return;
+        };
+        *value += delta;
+        let value = *value;
         
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let mut sum = 0.0;
-                
-                for ky in 0..kernel_size {
-                    for kx in 0..kernel_size {
-                        let gx = (x + kx + self.width - self.kernel_radius) % self.width;
-                        let gy = (y + ky + self.height - self.kernel_radius) % self.height;
-                        
-                        sum += self.grid[gy * self.width + gx] * self.kernel[ky * kernel_size + kx];
-                    }
-                }
-                
-                potential[y * self.width + x] = sum;
+        match tuned.check().map_err(SimError::InvalidParameter).and_then(|()| self.sim.set_growth(tuned.into())) {
+            Ok(()) => {
+                self.params.growth = tuned;
+                println!("Growth {} = {:.4} (reproduce with --growth {})", name, value, tuned);
+                self.log_params();
             }
+            Err(e) => println!("WARNING: {}", e),
         }
-        
-        potential
     }
     
-    fn random_init(&mut self, radius: f32, density: f32) {
-        // Clear the grid
-        for i in &mut self.grid {
-            *i = 0.0;
+    // Switch to `next`, remembering the current set for F4 if `remember`
+    fn switch_params(&mut self, next: ParamSet, remember: bool) {
+        if let Err(e) = next.apply(&mut self.sim) {
+            println!("WARNING: {}", e);
+            // The current set was in use a moment ago, so it goes back in
+            let _ = self.params.apply(&mut self.sim);
+            return;
         }
-        
-        let center_x = self.width / 2;
-        let center_y = self.height / 2;
-        let max_r = (self.width.min(self.height) as f32 * radius) as usize;
-        let mut rng = rand::thread_rng();
-        
-        // Create a more structured initial pattern
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let dx = x as isize - center_x as isize;
-                let dy = y as isize - center_y as isize;
-                let dist = ((dx*dx + dy*dy) as f32).sqrt();
-                
-                if dist < max_r as f32 {
-                    let r: f32 = rng.r#gen();
-                    
-                    // More cells start alive
-                    if r < density {
-                        // Higher initial values
-                        self.grid[y * self.width + x] = r * 0.5 + 0.3;
-                    } else if r < density + 0.2 {
-                        // Create some medium-valued cells too
-                        self.grid[y * self.width + x] = r * 0.3;
-                    }
+        let previous = std::mem::replace(&mut self.params, next);
+        if remember {
+            self.history.push(previous);
+        }
+        if self.reinit_on_randomize {
+            println!("Initializing with seed {}", self.sim.random_init(0.3, 0.3));
+        }
+        println!("Parameters: --growth {} --kernel {} --dt {} ({} earlier sets)",
+                 self.params.growth, self.params.kernel, self.params.dt, self.history.len());
+        self.log_params();
+    }
+    
+    // Append the current parameters and a thumbnail of the grid to the log
+    fn log_params(&mut self) {
+        let Some(log) = &mut self.params_log else {
+            return;
+        };
+        // Saved next to the log, which names it relative to itself
+        let thumbnail = format!("params_{:03}.png", self.thumbnails);
+        self.thumbnails += 1;
+        let step = self.sim.steps();
+        let options = StripOptions { thumbnail_height: 64, palette: self.palette, ..StripOptions::default() };
+        let saved = export_strip(&mut self.sim, &[step], step, log.path().with_file_name(&thumbnail), options)
+            .and_then(|_| log.log(&self.params, step, &thumbnail));
+        if let Err(e) = saved {
+            println!("WARNING: Parameter history stopped: {}", e);
+            self.params_log = None;
+        }
+    }
+    
+    fn apply(&mut self, command: Command) {
+        self.redraw = true;
+        match command {
+            Command::Pause(paused) => self.paused = paused,
+            Command::Step => self.single_step = true,
+            Command::StepInterval(interval) => self.step_interval = interval,
+            Command::ResizeKernel(delta) => {
+                let radius = self.sim.kernel_radius().saturating_add_signed(delta);
+                match self.sim.set_kernel_radius(radius) {
+                    Ok(()) => println!("Kernel radius: {}", radius),
+                    Err(e) => println!("WARNING: {}", e),
                 }
             }
-        }
-        
-        // Add some stable structures (like a simple "block" pattern)
-        if self.width > 50 && self.height > 50 {
-            // Add a few stable blocks in different locations
-            for i in 0..5 {
-                let bx = center_x as isize + (i as isize - 2) * 10;
-                let by = center_y as isize + (i as isize - 2) * 10;
-                
-                if bx > 2 && bx < self.width as isize - 2 && 
-                   by > 2 && by < self.height as isize - 2 {
-                    // Create a 2x2 block with high values
-                    for yi in 0..2 {
-                        for xi in 0..2 {
-                            self.grid[(by as usize + yi) * self.width + (bx as usize + xi)] = 0.9;
-                        }
-                    }
+            Command::TuneGrowth { index, delta } => self.tune_growth(index, delta),
+            Command::Randomize => {
+                let next = self.randomizer.randomize(&self.params);
+                self.switch_params(next, true);
+            }
+            Command::Revisit => match self.history.pop() {
+                Some(previous) => self.switch_params(previous, false),
+                None => println!("No earlier parameters to go back to"),
+            },
+            Command::Palette(palette) => self.palette = palette,
+            Command::ShowVariance(show) => self.show_variance = show,
+            Command::View { x0, y0, zoom } => self.view = (x0, y0, zoom),
+            Command::Paint { x, y, radius, value } => paint_brush(&mut self.sim, x, y, radius, value),
+            Command::Reinit => {
+                println!("Reinitializing simulation...");
+                println!("Initializing with seed {}", self.sim.random_init(0.3, 0.3));
+            }
+            // Blank the world so it can be painted from scratch
+            Command::Clear => {
+                println!("Clearing the grid");
+                self.sim.clear();
+            }
+            // Stop saving frames for the rest of the run
+            Command::StopSaving => {
+                if self.hooks.remove_hook("periodic save") {
+                    println!("Periodic frame saving stopped.");
                 }
             }
         }
     }
+}
 
-    fn update(&mut self) {
-        let potential = self.compute_potential();
-        let mut has_active_cells = false;
-        
-        for i in 0..self.grid.len() {
-            let growth = self.growth_function(potential[i]);
-            self.grid[i] += self.dt * growth;
-            self.grid[i] = self.grid[i].clamp(0.0, 1.0);
-            
-            // Check if we have any active cells
-            if self.grid[i] > 0.01 {
-                has_active_cells = true;
+// Largest zoom factor, in pixels per cell
+const MAX_ZOOM: usize = 16;
+
+// Window of the local variance ("temperature") view
+const VARIANCE_WINDOW: usize = 2;
+
+fn step_hooks(memory_budget: Option<usize>) -> HookRegistry {
+    let mut hooks = HookRegistry::default();
+    
+    let mut extinction_warning = warn_on_extinction();
+    hooks.add_hook("extinction warning", HookStage::PostStep, -10, Box::new(move |sim, report| {
+        let _ = extinction_warning(sim, report.step as usize);
+        Ok(())
+    }));
+    
+    // Print active cells count occasionally
+    let mut last_stats = Instant::now();
+    hooks.add_hook("stats", HookStage::PostStep, 0, Box::new(move |sim, report| {
+        if last_stats.elapsed().as_secs() >= 1 {
+            last_stats = Instant::now();
+            println!("Active cells: {} ({:.2}% of grid), mass: {:.1} (mean {:.3}), mean local variance: {:.5}", 
+                     report.active_cells, 
+                     100.0 * report.active_cells as f32 / sim.grid().len() as f32,
+                     report.mass,
+                     sim.mean_value(),
+                     sim.mean_local_variance(VARIANCE_WINDOW));
+            if let Some((x, y)) = sim.center_of_mass() {
+                println!("Center of mass: ({:.1}, {:.1})", x, y);
             }
         }
-        
-        // Print warning if all cells died
-        if !has_active_cells {
-            println!("WARNING: All cells have died! The simulation might need adjustment.");
+        Ok(())
+    }));
+    
+    // Save a frame occasionally if desired (every 100 updates)
+    hooks.add_hook("periodic save", HookStage::PostStep, 10, Box::new(|sim, report| {
+        if report.step % 100 == 0 {
+            let filename = format!("simplelife_frame_{:04}.pgm", report.step / 100);
+            sim.save_image(&filename).map_err(|e| e.at_step(report.step))?;
         }
+        Ok(())
+    }));
+    
+    // Free optional buffers when over --memory-budget; the scratch buffers only
+    // reach their size on the first step, and resizing the kernel moves them
+    if let Some(limit) = memory_budget {
+        let mut warned = false;
+        hooks.add_hook("memory budget", HookStage::PostStep, 5, Box::new(move |sim, report| {
+            if report.step % 100 != 1 {
+                return Ok(());
+            }
+            for entry in sim.enforce_memory_budget(limit) {
+                println!("Memory budget: freed {} ({:.2} KiB)", entry.name, entry.bytes as f64 / 1024.0);
+            }
+            let used = sim.memory_report().total();
+            if used > limit && !warned {
+                warned = true;
+                println!("WARNING: Simulation needs {:.2} MiB, over the {:.2} MiB budget",
+                         used as f64 / (1024.0 * 1024.0), limit as f64 / (1024.0 * 1024.0));
+            }
+            Ok(())
+        }));
     }
+    
+    hooks
+}
 
-    // New function to convert grid values to a blue-scale color buffer for display
-    fn create_buffer(&self) -> Vec<u32> {
-        let mut buffer = vec![0; self.width * self.height];
-        
-        for (i, &value) in self.grid.iter().enumerate() {
-            // Convert value from 0.0-1.0 to a blue-scale color
-            // We'll use a slight gradient from black to blue to make the visualization more interesting
-            let blue = (value * 255.0) as u8;
-            let green = (value * value * 100.0) as u8; // Slight green component for medium values
-            let red = (value * value * value * 50.0) as u8; // Very slight red for high values
-            
-            // Pack RGB values into a single u32 (0xRRGGBB format)
-            buffer[i] = ((red as u32) << 16) | ((green as u32) << 8) | blue as u32;
+fn run() -> Result<(), SimError> {
+    let args = Args::parse();
+    let (width, height) = (args.width, args.height);
+    
+    if args.backend_matrix {
+        let matrix = BackendMatrix::run(&MatrixCase::suite());
+        println!("{}", matrix);
+        std::process::exit(if matrix.passed() { 0 } else { 1 });
+    }
+    
+    let mut builder = SimpleLife::builder()
+        .size(width, height)
+        .kernel_radius(args.radius)
+        .kernel_shape(args.kernel.clone())
+        .growth_preset(args.growth)
+        .conv_method(args.conv)
+        .clamp(args.clamp)
+        .kernel_supersampling(args.supersample)
+        .dt(args.dt)
+        .allow_negative_dt(args.allow_negative_dt);
+    if args.aspect_ratio != 1.0 || args.rotation != 0.0 {
+        builder = builder.anisotropy(args.aspect_ratio, args.rotation);
+    }
+    let mut sim = builder.build()?;
+    log_dt(args.dt);
+    
+    if args.dump_kernel {
+        print!("{}", sim.dump_kernel());
+    }
+    if let Some(path) = &args.save_kernel {
+        sim.save_kernel_image(path)?;
+    }
+    
+    // --self-test runs the full check and exits; otherwise a quick variant runs at startup
+    if args.self_test {
+        println!("Running self-test...");
+        let passed = report_self_test(&self_test(&sim, 50));
+        if !passed && !args.force {
+            std::process::exit(1);
         }
-        
-        buffer
+        return Ok(());
     }
-
-    fn save_image(&self, filename: &str) -> std::io::Result<()> {
-        let mut file = File::create(filename)?;
-        
-        // Write PGM header with proper line endings
-        writeln!(file, "P5")?;
-        writeln!(file, "{} {}", self.width, self.height)?;
-        writeln!(file, "255")?;
-        
-        // Count non-zero pixels for debugging
-        let mut non_zero_pixels = 0;
-        
-        // Write pixel data
-        for value in &self.grid {
-            let pixel = (*value * 255.0) as u8;
-            file.write_all(&[pixel])?;
-            
-            if pixel > 0 {
-                non_zero_pixels += 1;
-            }
+    
+    if !args.skip_self_test {
+        let passed = report_self_test(&self_test(&sim, 10));
+        if !passed {
+            println!("WARNING: Self-test failed, the window may stay black. Run with --self-test for details.");
         }
-        
-        println!("Saved image with {} non-zero pixels out of {}", 
-                non_zero_pixels, self.width * self.height);
-        
-        Ok(())
     }
-}
-
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Create our simulation with slightly larger dimensions for better visualization
-    let width = 400;
-    let height = 400;
-    let mut sim = SimpleLife::new(width, height, 13, 0.05);
     
-    // Initialize with random pattern
-    sim.random_init(0.3, 0.3);
+    // --memory prints a breakdown; --memory-budget MB is enforced by a step hook
+    if args.memory {
+        print!("{}", sim.memory_report());
+    }
+    let memory_budget = args.memory_budget.map(|mb| (mb * 1024.0 * 1024.0) as usize);
+    
+    // Initialize from --pattern, or with a random pattern, reproducibly if a seed was given
+    match (&args.pattern, args.seed) {
+        (Some(path), _) => sim.load_from_image(path)?,
+        (None, Some(seed)) => sim.random_init_seeded(0.3, 0.3, seed),
+        (None, None) => println!("Initializing with seed {}", sim.random_init(0.3, 0.3)),
+    }
     
     // Create a window for visualization
     let mut window = Window::new(
@@ -217,57 +557,271 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         width,
         height,
         WindowOptions::default(),
-    )?;
+    ).map_err(|e| SimError::Window(e.to_string()))?;
+    
+    // The window redraws at about 60 fps whatever the simulation manages
+    window.limit_update_rate(Some(Duration::from_micros(16_600)));
     
-    // Set a reasonable update rate (30 fps is good for visualization)
-    window.limit_update_rate(Some(Duration::from_micros(5555)));
+    // All drawing goes through the presenter, which fits buffers to the window
+    let scale_mode = if args.stretch { ScaleMode::Stretch } else { ScaleMode::Letterbox };
+    let mut presenter = Presenter::new(window, scale_mode);
     
+    // The simulation steps on its own thread and publishes every rendered frame;
+    // this thread shows the latest one and sends input back as commands
+    let growth = args.growth;
+    let blank = move || Frame { pixels: vec![0; width * height], width, height, origin: (0, 0), scale: 1, step: 0, time: 0.0, growth };
+    let frames = Arc::new(TripleBuffer::new(blank()));
+    let (commands, received) = mpsc::channel();
+    // Steps are at least this far apart; + and - change it
+    let mut step_interval = Duration::from_micros(5555);
+    let published = Arc::clone(&frames);
+    let max_steps = args.steps;
+    
+    // F3 draws from its own seeded RNG, so --seed replays a session's jumps too
+    let params = ParamSet { growth: args.growth, kernel: args.kernel.clone(), dt: args.dt };
+    let mut randomized = args.randomize;
+    if args.aspect_ratio != 1.0 || args.rotation != 0.0 {
+        // A new kernel shape would drop the stretch
+        randomized.retain(|&param| param != Param::Kernel);
+    }
+    let bounds = Bounds { spread: args.randomize_spread, ranges: args.randomize_bound };
+    let randomizer = Randomizer::new(args.seed.unwrap_or_else(rand::random), bounds, &randomized);
+    let params_log = args.params_log.and_then(|path| match ParamsLog::create(path) {
+        Ok(log) => Some(log),
+        Err(e) => {
+            println!("WARNING: Parameter history will not be kept: {}", e);
+            None
+        }
+    });
+    let reinit_on_randomize = args.randomize_reinit;
+    // Hooks need not be Send, so they are set up on the thread that runs them
+    let sim_thread = thread::spawn(move || Simulation {
+        sim,
+        hooks: step_hooks(memory_budget),
+        commands: received,
+        frames: published,
+        frame: blank(),
+        max_steps,
+        step_interval,
+        paused: false,
+        single_step: false,
+        palette: Palette::default(),
+        show_variance: false,
+        view: (0, 0, 1),
+        redraw: false,
+        params,
+        randomizer,
+        history: History::default(),
+        reinit_on_randomize,
+        params_log,
+        thumbnails: 0,
+    }.run());
+    
+    // The frame on screen, redrawn until a newer one comes in
+    let mut frame = blank();
     let mut frame_count = 0;
     let mut last_time = Instant::now();
-    let mut fps = 0.0;
+    let mut last_step = 0;
     
-    // Main loop
-    while window.is_open() && !window.is_key_down(Key::Escape) {
-        // Update the simulation
-        sim.update();
-        
-        // Convert the grid to a displayable buffer
-        let buffer = sim.create_buffer();
-        
-        // Update the window with the new buffer
-        window.update_with_buffer(&buffer, width, height)?;
+    // Press V to switch between the normal view and the local variance view
+    let mut show_variance = false;
+    
+    // Press P to cycle through the color palettes
+    let mut palette = Palette::default();
+    
+    // The scroll wheel zooms in and out by powers of two, the arrow keys pan
+    let (mut view_x, mut view_y, mut zoom) = (0, 0, 1);
+    
+    // Left mouse button paints live cells, the right one erases; [ and ] resize the brush
+    let mut brush_radius: usize = 5;
+    
+    // G starts and stops recording the view into simplelife_clip_NNN.gif
+    let mut recorder: Option<GifRecorder> = None;
+    let mut clip_count = 0;
+    
+    // Q/A and E/D tune the first two parameters of the growth curve, e.g. mu and sigma;
+    // the title follows whatever growth the simulation reports
+    let mut title_growth = args.growth;
+    
+    // Space pauses; while paused, . advances a single step
+    let mut paused = false;
+    let (mut fps, mut steps_per_second) = (0.0, 0.0);
+    
+    // Main loop; it also ends if the simulation thread dies, which the join below reports
+    while presenter.window().is_open() && !presenter.window().is_key_down(Key::Escape)
+        && args.steps.is_none_or(|n| frame.step < n) && !sim_thread.is_finished() {
+        // Record every new frame once, however often it is drawn
+        if frames.take_latest(&mut frame) && let Some(rec) = &mut recorder && let Err(e) = rec.push_frame(&frame.pixels) {
+            println!("WARNING: Recording stopped: {}", e);
+            recorder = None;
+        }
+        presenter.present(&frame.pixels, frame.width, frame.height);
+        if frame.growth != title_growth {
+            title_growth = frame.growth;
+            presenter.window_mut().set_title(&window_title(fps, steps_per_second, paused, &frame));
+        }
         
-        // Calculate FPS every second
+        // Calculate FPS and the simulation rate every second
         frame_count += 1;
         let current_time = Instant::now();
         let elapsed = current_time.duration_since(last_time);
         
         if elapsed.as_secs() >= 1 {
             fps = frame_count as f64 / elapsed.as_secs_f64();
-            window.set_title(&format!("SimpleLife - FPS: {:.1}", fps));
+            steps_per_second = (frame.step - last_step) as f64 / elapsed.as_secs_f64();
+            presenter.window_mut().set_title(&window_title(fps, steps_per_second, paused, &frame));
             frame_count = 0;
             last_time = current_time;
-            
-            // Print active cells count occasionally
-            let active_cells = sim.grid.iter().filter(|&&v| v > 0.01).count();
-            println!("Active cells: {} ({:.2}% of grid)", 
-                     active_cells, 
-                     100.0 * active_cells as f32 / (width * height) as f32);
+            last_step = frame.step;
+        }
+        
+        if presenter.window().is_key_pressed(Key::Space, minifb::KeyRepeat::No) {
+            paused = !paused;
+            let _ = commands.send(Command::Pause(paused));
+            presenter.window_mut().set_title(&window_title(fps, steps_per_second, paused, &frame));
+        }
+        if paused && presenter.window().is_key_pressed(Key::Period, minifb::KeyRepeat::Yes) {
+            let _ = commands.send(Command::Step);
         }
         
-        // Save a frame occasionally if desired (every 100 updates)
-        if frame_count % 100 == 0 {
-            let filename = format!("simplelife_frame_{:04}.pgm", frame_count / 100);
-            sim.save_image(&filename)?;
+        // + shortens the step interval, - lengthens it; with Shift they resize the kernel instead
+        let shift = presenter.window().is_key_down(Key::LeftShift) || presenter.window().is_key_down(Key::RightShift);
+        let faster = presenter.window().is_key_pressed(Key::Equal, minifb::KeyRepeat::Yes)
+            || presenter.window().is_key_pressed(Key::NumPadPlus, minifb::KeyRepeat::Yes);
+        let slower = presenter.window().is_key_pressed(Key::Minus, minifb::KeyRepeat::Yes)
+            || presenter.window().is_key_pressed(Key::NumPadMinus, minifb::KeyRepeat::Yes);
+        if shift && (faster || slower) {
+            let _ = commands.send(Command::ResizeKernel(if faster { 1 } else { -1 }));
+        } else if faster || slower {
+            step_interval = if faster { step_interval / 2 } else { step_interval * 2 };
+            step_interval = step_interval.clamp(Duration::from_millis(1), Duration::from_secs(1));
+            let _ = commands.send(Command::StepInterval(step_interval));
+            println!("Step interval: {:.1} ms", step_interval.as_secs_f64() * 1000.0);
+        }
+        
+        // Live growth tuning, mu on Q/A and sigma on W/S
+        let tuning = [(Key::Q, 0, 0.005), (Key::A, 0, -0.005), (Key::W, 1, 0.001), (Key::S, 1, -0.001)];
+        for (key, index, delta) in tuning {
+            if presenter.window().is_key_pressed(key, minifb::KeyRepeat::Yes) {
+                let _ = commands.send(Command::TuneGrowth { index, delta });
+            }
+        }
+        
+        // F3 jumps to random parameters, F4 goes back through the earlier ones
+        if presenter.window().is_key_pressed(Key::F3, minifb::KeyRepeat::No) {
+            let _ = commands.send(Command::Randomize);
+        }
+        if presenter.window().is_key_pressed(Key::F4, minifb::KeyRepeat::No) {
+            let _ = commands.send(Command::Revisit);
         }
         
         // Allow user interaction
-        if window.is_key_pressed(Key::R, minifb::KeyRepeat::No) {
-            println!("Reinitializing simulation...");
-            sim.random_init(0.3, 0.3);
+        if presenter.window().is_key_pressed(Key::R, minifb::KeyRepeat::No) {
+            let _ = commands.send(Command::Reinit);
+        }
+        
+        if presenter.window().is_key_pressed(Key::C, minifb::KeyRepeat::No) {
+            let _ = commands.send(Command::Clear);
+        }
+        
+        if presenter.window().is_key_pressed(Key::G, minifb::KeyRepeat::No) {
+            match recorder.take() {
+                Some(rec) => finish_recording(rec),
+                None => {
+                    let filename = format!("simplelife_clip_{:03}.gif", clip_count);
+                    clip_count += 1;
+                    match GifRecorder::start(&filename, width, height) {
+                        Ok(rec) => {
+                            println!("Recording to {}...", filename);
+                            recorder = Some(rec);
+                        }
+                        Err(e) => println!("WARNING: Could not start recording: {}", e),
+                    }
+                }
+            }
+        }
+        
+        // X stops the periodic frame saving, S being taken by the sigma tuning
+        if presenter.window().is_key_pressed(Key::X, minifb::KeyRepeat::No) {
+            let _ = commands.send(Command::StopSaving);
+        }
+        
+        // Toggle the local variance ("temperature") view
+        if presenter.window().is_key_pressed(Key::V, minifb::KeyRepeat::No) {
+            show_variance = !show_variance;
+            let _ = commands.send(Command::ShowVariance(show_variance));
+        }
+        
+        if presenter.window().is_key_pressed(Key::P, minifb::KeyRepeat::No) {
+            palette = palette.next();
+            let _ = commands.send(Command::Palette(palette));
+            println!("Palette: {:?}", palette);
+        }
+        
+        if presenter.window().is_key_pressed(Key::LeftBracket, minifb::KeyRepeat::Yes) {
+            brush_radius = brush_radius.saturating_sub(1);
+            println!("Brush radius: {}", brush_radius);
+        }
+        if presenter.window().is_key_pressed(Key::RightBracket, minifb::KeyRepeat::Yes) {
+            brush_radius = (brush_radius + 1).min(width.min(height) / 2);
+            println!("Brush radius: {}", brush_radius);
         }
+        
+        // Zoom about the middle of the view and pan by an eighth of it, keeping it on the grid
+        let scroll = presenter.window().get_scroll_wheel().map_or(0.0, |(_, y)| y);
+        let new_zoom = if scroll > 0.0 {
+            (zoom * 2).min(MAX_ZOOM)
+        } else if scroll < 0.0 {
+            (zoom / 2).max(1)
+        } else {
+            zoom
+        };
+        let (view_w, view_h) = ((width / zoom).max(1), (height / zoom).max(1));
+        let (new_w, new_h) = ((width / new_zoom).max(1), (height / new_zoom).max(1));
+        let (step_x, step_y) = ((new_w / 8).max(1), (new_h / 8).max(1));
+        let pan = |key| presenter.window().is_key_pressed(key, minifb::KeyRepeat::Yes);
+        let center_x = (view_x + view_w / 2).saturating_add_signed(pan_delta(pan(Key::Left), pan(Key::Right), step_x));
+        let center_y = (view_y + view_h / 2).saturating_add_signed(pan_delta(pan(Key::Up), pan(Key::Down), step_y));
+        let new_x = center_x.saturating_sub(new_w / 2).min(width - new_w);
+        let new_y = center_y.saturating_sub(new_h / 2).min(height - new_h);
+        if (new_x, new_y, new_zoom) != (view_x, view_y, zoom) {
+            (view_x, view_y, zoom) = (new_x, new_y, new_zoom);
+            let _ = commands.send(Command::View { x0: view_x, y0: view_y, zoom });
+            if scroll != 0.0 {
+                println!("Zoom: {}x", zoom);
+            }
+        }
+        
+        let paint = if presenter.window().get_mouse_down(MouseButton::Left) {
+            Some(1.0)
+        } else if presenter.window().get_mouse_down(MouseButton::Right) {
+            Some(0.0)
+        } else {
+            None
+        };
+        if let (Some(value), Some((x, y))) = (paint, presenter.mouse_cell(frame.width, frame.height)) {
+            let (x, y) = frame.cell(x, y);
+            let _ = commands.send(Command::Paint { x, y, radius: brush_radius, value });
+        }
+    }
+    
+    // Hanging up stops the simulation thread after the step it is on
+    drop(commands);
+    if let Err(panic) = sim_thread.join() {
+        std::panic::resume_unwind(panic);
+    }
+    
+    if let Some(rec) = recorder {
+        finish_recording(rec);
     }
     
     println!("Simulation ended successfully!");
     Ok(())
-}
\ No newline at end of file
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -1,204 +1,246 @@
-use std::fs::File;
-use std::io::Write;
-use rand::Rng;
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+use std::time::Duration;
+use clap::Parser;
+use simplelife::{Clamp, ConvMethod, GrowthPreset, GrowthSchedule, Outcome, Palette, Real, SimError, SimpleLife};
+use simplelife::backends::{BackendMatrix, MatrixCase};
+use simplelife::hooks::warn_on_extinction;
+use simplelife::recorder::{StatsLogger, StripOptions, export_strip};
+use simplelife::report::{FINAL_FRAME, RunRecord, STATS_FILE, write_comparison};
+use simplelife::selftest::{report_self_test, self_test};
 
-struct SimpleLife {
+/// Headless SimpleLife run that saves frames as PGM images.
+#[derive(Parser)]
+struct Args {
+    /// Grid width in cells
+    #[arg(long, default_value_t = 200)]
     width: usize,
+    /// Grid height in cells
+    #[arg(long, default_value_t = 200)]
     height: usize,
-    grid: Vec<f32>,
-    kernel: Vec<f32>,
-    kernel_radius: usize,
-    dt: f32,
+    /// Kernel radius in cells
+    #[arg(long, default_value_t = 13)]
+    radius: usize,
+    /// Time step; 0 freezes the dynamics
+    #[arg(long, default_value_t = 0.05, allow_negative_numbers = true)]
+    dt: Real,
+    /// Accept a negative dt
+    #[arg(long)]
+    allow_negative_dt: bool,
+    /// Growth curve: quadratic-wide, quadratic-original, quadratic:A,B, gaussian:MU,SIGMA,
+    /// band:LOW,HIGH, `interval:B1,B2,D1,D2[,WIDTH]` or mexican-hat:MU1,SIGMA1,AMP1,MU2,SIGMA2,AMP2
+    #[arg(long, default_value = "quadratic-wide")]
+    growth: GrowthPreset,
+    /// Convolution method: direct, fft, or auto to pick FFT for kernels of radius 6 and up
+    #[arg(long, default_value = "auto")]
+    conv: ConvMethod,
+    /// How cells are kept within 0..=1: hard, or soft to let dense blobs saturate smoothly
+    #[arg(long, default_value = "hard")]
+    clamp: Clamp,
+    /// Number of steps to run
+    #[arg(long, default_value_t = 500)]
+    steps: usize,
+    /// Save a frame every N steps, starting with the first
+    #[arg(long, value_name = "N", default_value = "20")]
+    save_every: NonZeroUsize,
+    /// Directory the frames are written to
+    #[arg(long, value_name = "PATH", default_value = ".")]
+    output_dir: PathBuf,
+    /// Run even if the self-test fails
+    #[arg(long)]
+    force: bool,
+    /// Skip the self-test
+    #[arg(long)]
+    skip_self_test: bool,
+    /// Step every convolution backend on a suite of seeded grids, compare
+    /// each with a brute-force reference, print the table and exit; the exit
+    /// code is nonzero if any backend is off by more than its tolerance
+    #[arg(long)]
+    backend_matrix: bool,
+    /// Seed for the initial pattern; with the same seed, f32 and f64 builds
+    /// (`--features f64`) start from the same grid and their masses can be compared
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Don't save frames, just run up to --steps and print how the run ended
+    #[arg(long)]
+    classify: bool,
+    /// Also plot the growth curve into the output directory
+    #[arg(long)]
+    plot_growth: bool,
+    /// Instead of saving single frames, draw the frames at these steps side by
+    /// side into simplelife_strip.png, e.g. `--strip 0,200,400,600,800`; steps
+    /// past --steps are skipped with a warning
+    #[arg(long, value_name = "STEPS", value_delimiter = ',')]
+    strip: Vec<u64>,
+    /// Height of each frame in the strip, in pixels
+    #[arg(long, value_name = "PX", default_value_t = 128)]
+    strip_height: usize,
+    /// Colors of the strip: blue, viridis, magma, grayscale or heat
+    #[arg(long, default_value = "blue")]
+    palette: Palette,
+    /// Switch to another growth curve once STEP steps have run, e.g.
+    /// `--schedule 500=gaussian:0.15,0.02`; repeat to anneal in stages
+    #[arg(long, value_name = "STEP=GROWTH", value_parser = parse_scheduled)]
+    schedule: Vec<(u64, GrowthPreset)>,
+    /// Don't run, but compare earlier runs: read the statistics and final frame
+    /// from each of these output directories and write simplelife_report.md
+    /// with their mass curves, outcomes and final frames into --output-dir
+    #[arg(long, value_name = "DIRS", value_delimiter = ',')]
+    compare: Vec<PathBuf>,
 }
 
-impl SimpleLife {
-    fn new(width: usize, height: usize, kernel_radius: usize, dt: f32) -> Self {
-        let mut sim = SimpleLife {
-            width,
-            height,
-            grid: vec![0.0; width * height],
-            kernel: vec![0.0; (2 * kernel_radius + 1) * (2 * kernel_radius + 1)],
-            kernel_radius,
-            dt,
-        };
-        
-        sim.init_kernel();
-        sim
+fn parse_scheduled(s: &str) -> Result<(u64, GrowthPreset), String> {
+    let (step, preset) = s.split_once('=').ok_or("expected STEP=GROWTH")?;
+    let step = step.trim().parse().map_err(|_| format!("invalid step '{}'", step))?;
+    Ok((step, preset.parse()?))
+}
+
+fn run() -> Result<(), SimError> {
+    let args = Args::parse();
+    
+    if args.backend_matrix {
+        let matrix = BackendMatrix::run(&MatrixCase::suite());
+        println!("{}", matrix);
+        std::process::exit(if matrix.passed() { 0 } else { 1 });
     }
     
-    fn init_kernel(&mut self) {
-        let kernel_size = 2 * self.kernel_radius + 1;
-        let mut kernel_sum = 0.0;
-        
-        for y in 0..kernel_size {
-            for x in 0..kernel_size {
-                let dx = x as f32 - self.kernel_radius as f32;
-                let dy = y as f32 - self.kernel_radius as f32;
-                let distance = (dx*dx + dy*dy).sqrt();
-                
-                // Linear falloff from center
-                let value = (1.0 - distance / self.kernel_radius as f32).max(0.0);
-                self.kernel[y * kernel_size + x] = value;
-                kernel_sum += value;
-            }
-        }
-        
-        // Normalize kernel
-        for k in &mut self.kernel {
-            *k /= kernel_sum;
-        }
+    if !args.compare.is_empty() {
+        let runs = args.compare.iter().map(RunRecord::load).collect::<Result<Vec<_>, _>>()?;
+        std::fs::create_dir_all(&args.output_dir).map_err(|e| SimError::io(&args.output_dir, e))?;
+        let path = args.output_dir.join("simplelife_report.md");
+        let report = write_comparison(&runs, &path)?;
+        println!("Compared {} runs in {} ({} images)", runs.len(), path.display(), report.images);
+        return Ok(());
     }
     
-    fn growth_function(&self, u: f32) -> f32 {
-        // More forgiving growth function with a wider "alive" range
-        // Original was: 2.0 * u * (1.0 - u) - 0.5
-        1.8 * u * (1.0 - u) - 0.2  // This creates a wider band of survival
+    // Defaults keep the simulation small to reduce computation time
+    let mut sim = SimpleLife::builder()
+        .size(args.width, args.height)
+        .kernel_radius(args.radius)
+        .dt(args.dt)
+        .allow_negative_dt(args.allow_negative_dt)
+        .growth_preset(args.growth)
+        .conv_method(args.conv)
+        .clamp(args.clamp)
+        .build()?;
+    if args.dt < 0.0 {
+        println!("WARNING: Running with negative dt {}. The dynamics are not reversible, expect artifacts.", args.dt);
     }
     
-    fn compute_potential(&self) -> Vec<f32> {
-        let mut potential = vec![0.0; self.width * self.height];
-        let kernel_size = 2 * self.kernel_radius + 1;
-        
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let mut sum = 0.0;
-                
-                for ky in 0..kernel_size {
-                    for kx in 0..kernel_size {
-                        let gx = (x + kx + self.width - self.kernel_radius) % self.width;
-                        let gy = (y + ky + self.height - self.kernel_radius) % self.height;
-                        
-                        sum += self.grid[gy * self.width + gx] * self.kernel[ky * kernel_size + kx];
-                    }
-                }
-                
-                potential[y * self.width + x] = sum;
-            }
+    // Headless runs have nobody watching, so refuse to burn time on a broken config
+    if !args.skip_self_test {
+        let passed = report_self_test(&self_test(&sim, 50));
+        if !passed && !args.force {
+            eprintln!("Self-test failed; pass --force to run anyway.");
+            std::process::exit(1);
         }
-        
-        potential
     }
     
-    fn random_init(&mut self, radius: f32, density: f32) {
-        // Clear the grid
-        for i in &mut self.grid {
-            *i = 0.0;
-        }
-        
-        let center_x = self.width / 2;
-        let center_y = self.height / 2;
-        let max_r = (self.width.min(self.height) as f32 * radius) as usize;
-        let mut rng = rand::thread_rng();
-        
-        // Create a more structured initial pattern
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let dx = x as isize - center_x as isize;
-                let dy = y as isize - center_y as isize;
-                let dist = ((dx*dx + dy*dy) as f32).sqrt();
-                
-                if dist < max_r as f32 {
-                    let r: f32 = rng.r#gen();
-                    
-                    // More cells start alive
-                    if r < density {
-                        // Higher initial values
-                        self.grid[y * self.width + x] = r * 0.5 + 0.3;
-                    } else if r < density + 0.2 {
-                        // Create some medium-valued cells too
-                        self.grid[y * self.width + x] = r * 0.3;
-                    }
-                }
-            }
-        }
+    // Initialize with random pattern, reproducibly if a seed was given
+    match args.seed {
+        Some(seed) => sim.random_init_seeded(0.3, 0.3, seed),
+        None => println!("Initializing with seed {}", sim.random_init(0.3, 0.3)),
+    }
+    if !args.schedule.is_empty() {
+        // Step 0 keeps the --growth curve unless scheduled otherwise; later entries for a step win
+        let presets: BTreeMap<u64, GrowthPreset> = std::iter::once((0, args.growth))
+            .chain(args.schedule.iter().copied())
+            .collect();
+        let labels: Vec<String> = presets.values().map(GrowthPreset::to_string).collect();
         
-        // Add some stable structures (like a simple "block" pattern)
-        if self.width > 50 && self.height > 50 {
-            // Add a few stable blocks in different locations
-            for i in 0..5 {
-                let bx = center_x as isize + (i as isize - 2) * 10;
-                let by = center_y as isize + (i as isize - 2) * 10;
-                
-                if bx > 2 && bx < self.width as isize - 2 && 
-                   by > 2 && by < self.height as isize - 2 {
-                    // Create a 2x2 block with high values
-                    for yi in 0..2 {
-                        for xi in 0..2 {
-                            self.grid[(by as usize + yi) * self.width + (bx as usize + xi)] = 0.9;
-                        }
-                    }
+        let schedule = presets.into_iter()
+            .fold(GrowthSchedule::new(), |schedule, (step, preset)| schedule.at(step, preset.into()))
+            .on_change(move |step, entry| {
+                if step > 0 {
+                    println!("Step {}: growth curve is now {}", step, labels[entry]);
                 }
-            }
-        }
+            });
+        sim.set_growth_schedule(schedule)?;
     }
-
-    fn update(&mut self) {
-        let potential = self.compute_potential();
-        let mut has_active_cells = false;
-        
-        for i in 0..self.grid.len() {
-            let growth = self.growth_function(potential[i]);
-            self.grid[i] += self.dt * growth;
-            self.grid[i] = self.grid[i].clamp(0.0, 1.0);
-            
-            // Check if we have any active cells
-            if self.grid[i] > 0.01 {
-                has_active_cells = true;
-            }
+    println!("Running in {}, initial mass {:.6}", std::any::type_name::<Real>(), sim.total_mass());
+    
+    // --classify only reports the outcome, on one line in a fixed format so sweeps can parse it
+    if args.classify {
+        match sim.classify_outcome(args.steps) {
+            Outcome::Extinct(step) => println!("outcome: extinct at step {}", step),
+            Outcome::Exploded(step) => println!("outcome: exploded at step {}", step),
+            Outcome::Stable => println!("outcome: stable after {} steps", args.steps),
         }
-        
-        // Print warning if all cells died
-        if !has_active_cells {
-            println!("WARNING: All cells have died! The simulation might need adjustment.");
+        return Ok(());
+    }
+    
+    std::fs::create_dir_all(&args.output_dir).map_err(|e| SimError::io(&args.output_dir, e))?;
+    
+    if !args.strip.is_empty() {
+        let path = args.output_dir.join("simplelife_strip.png");
+        let options = StripOptions { thumbnail_height: args.strip_height, palette: args.palette, ..StripOptions::default() };
+        let strip = export_strip(&mut sim, &args.strip, args.steps as u64, &path, options)?;
+        for step in &strip.skipped {
+            eprintln!("Warning: skipped step {}, the run only has {} steps", step, args.steps);
         }
+        println!("Saved a {}x{} strip of {} frames to {}", strip.width, strip.height, strip.frames.len(), path.display());
+        return Ok(());
     }
-
-    fn save_image(&self, filename: &str) -> std::io::Result<()> {
-        let mut file = File::create(filename)?;
-        
-        // Write PGM header with proper line endings
-        writeln!(file, "P5")?;
-        writeln!(file, "{} {}", self.width, self.height)?;
-        writeln!(file, "255")?;
+    
+    // Every step goes into a CSV next to the frames
+    let mut stats = StatsLogger::create(args.output_dir.join(STATS_FILE))?;
+    stats.log(&sim, 0)?;
+    println!("Logging statistics to {}", stats.path().display());
+    
+    if args.plot_growth {
+        let path = args.output_dir.join("simplelife_growth.png");
+        sim.plot_growth_curve(&path.to_string_lossy(), 512, 256)?;
+        println!("Plotted the growth curve to {}", path.display());
+    }
+    
+    // Save every N-th frame
+    let save_every = args.save_every.get();
+    let mut extinction_warning = warn_on_extinction();
+    let mut run_error = None;
+    // Step times since the last saved frame, for the average printed with it
+    let mut step_time = Duration::ZERO;
+    let mut timed_steps = 0;
+    sim.run_steps(args.steps, |sim, step| {
+        let _ = extinction_warning(sim, step);
+        step_time += sim.last_step_duration();
+        timed_steps += 1;
         
-        // Count non-zero pixels for debugging
-        let mut non_zero_pixels = 0;
+        if let Err(e) = stats.log(sim, step) {
+            run_error = Some(e.at_step(step as u64));
+            return ControlFlow::Break(());
+        }
         
-        // Write pixel data
-        for value in &self.grid {
-            let pixel = (*value * 255.0) as u8;
-            file.write_all(&[pixel])?;
-            
-            if pixel > 0 {
-                non_zero_pixels += 1;
+        let i = step - 1;
+        if i % save_every == 0 {
+            let path = args.output_dir.join(format!("simplelife_{:03}.pgm", i / save_every));
+            if let Err(e) = sim.save_image(&path.to_string_lossy()) {
+                run_error = Some(e.at_step(step as u64));
+                return ControlFlow::Break(());
             }
+            println!("Saved frame {} (step {}, t = {:.2}, mass {:.6}, {:.2} ms/step)",
+                     i / save_every, step, sim.sim_time(), sim.total_mass(), step_time.as_secs_f64() * 1000.0 / timed_steps as f64);
+            step_time = Duration::ZERO;
+            timed_steps = 0;
         }
         
-        println!("Saved image with {} non-zero pixels out of {}", 
-                non_zero_pixels, self.width * self.height);
-        
-        Ok(())
-    }
-}
-
-fn main() -> std::io::Result<()> {
-    // Create a smaller simulation to reduce computation time
-    let mut sim = SimpleLife::new(200, 200, 13, 0.05);  // Reduced dt from 0.1 to 0.05
-    
-    // Initialize with random pattern
-    sim.random_init(0.3, 0.3);
+        ControlFlow::Continue(())
+    });
     
-    // Run for 500 steps, saving every 20th frame
-    for i in 0..500 {
-        sim.update();
-    
-        if i % 20 == 0 {
-            let filename = format!("simplelife_{:03}.pgm", i/20);
-            sim.save_image(&filename)?;
-            println!("Saved frame {}", i/20);
-        }
+    if let Some(e) = run_error {
+        return Err(e);
     }
+    // Kept for --compare, whichever step the last numbered frame was saved at
+    sim.save_image(&args.output_dir.join(FINAL_FRAME).to_string_lossy())?;
     
     println!("Simulation completed successfully!");
     Ok(())
-}
\ No newline at end of file
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,159 @@
+//! Several interacting grids, as in multi-channel Lenia.
+
+use crate::{Real, SimError, SimpleLife};
+
+/// A stack of equally sized channels, each a [`SimpleLife`] with its own grid,
+/// kernel, growth curve and dt.
+///
+/// Every step convolves each channel with its own kernel, then feeds channel
+/// `c` the mix `sum over k of interaction[c][k] * potential[k]` through its
+/// growth curve. The default interaction is the identity, i.e. independent
+/// channels; off-diagonal weights let channels feed or inhibit each other.
+pub struct MultiLife {
+    channels: Vec<SimpleLife>,
+    interaction: Vec<Vec<Real>>,
+    // Buffers kept across steps: one potential per channel and the mix fed to
+    // the channel being updated
+    potentials: Vec<Vec<Real>>,
+    mixed: Vec<Real>,
+}
+
+impl SimpleLife {
+    /// Create `channels` empty, independent channels sharing the grid size,
+    /// each with a linear kernel of the given radius and time step `dt`.
+    ///
+    /// Like [`SimpleLife::new`] this skips validation; customize the channels
+    /// through [`MultiLife::channel_mut`].
+    pub fn new_multichannel(width: usize, height: usize, channels: usize, kernel_radius: usize, dt: Real) -> MultiLife {
+        let channels = (0..channels)
+            .map(|_| SimpleLife::new(width, height, kernel_radius, dt))
+            .collect();
+        MultiLife::with_identity(channels)
+    }
+}
+
+impl MultiLife {
+    /// Combine separately built simulations into channels, e.g. from the
+    /// builder with different kernels. They all need the same grid size.
+    pub fn from_channels(channels: Vec<SimpleLife>) -> Result<Self, SimError> {
+        let Some(first) = channels.first() else {
+            return Err(SimError::InvalidParameter("a multi-channel simulation needs at least one channel".to_string()));
+        };
+        let size = (first.width, first.height);
+        if let Some(other) = channels.iter().find(|c| (c.width, c.height) != size) {
+            return Err(SimError::InvalidParameter(format!(
+                "channel is {}x{}, the first one is {}x{}", other.width, other.height, size.0, size.1)));
+        }
+
+        Ok(Self::with_identity(channels))
+    }
+
+    fn with_identity(channels: Vec<SimpleLife>) -> Self {
+        let n = channels.len();
+        let interaction = (0..n)
+            .map(|c| (0..n).map(|k| if c == k { 1.0 } else { 0.0 }).collect())
+            .collect();
+        MultiLife { channels, interaction, potentials: Vec::new(), mixed: Vec::new() }
+    }
+
+    /// Number of channels.
+    pub fn channel_count(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// Channel `c`; panics if out of range.
+    pub fn channel(&self, c: usize) -> &SimpleLife {
+        &self.channels[c]
+    }
+
+    /// Channel `c`, to change its kernel, growth curve or cells; panics if out of range.
+    pub fn channel_mut(&mut self, c: usize) -> &mut SimpleLife {
+        &mut self.channels[c]
+    }
+
+    /// Rows are the receiving channel, columns the channel whose potential is mixed in.
+    pub fn interaction(&self) -> &[Vec<Real>] {
+        &self.interaction
+    }
+
+    /// Replace the interaction matrix; it has to be square with one row per
+    /// channel and hold finite weights.
+    pub fn set_interaction(&mut self, matrix: Vec<Vec<Real>>) -> Result<(), SimError> {
+        let n = self.channels.len();
+        if matrix.len() != n || matrix.iter().any(|row| row.len() != n) {
+            return Err(SimError::InvalidParameter(format!("interaction matrix must be {}x{}", n, n)));
+        }
+        if matrix.iter().flatten().any(|w| !w.is_finite()) {
+            return Err(SimError::InvalidParameter("interaction weights must be finite".to_string()));
+        }
+
+        self.interaction = matrix;
+        Ok(())
+    }
+
+    /// Seed every channel with [`SimpleLife::random_init_seeded`], channel `c`
+    /// using `seed + c` so the channels differ but the result is reproducible.
+    pub fn random_init_seeded(&mut self, radius: f32, density: f32, seed: u64) {
+        for (c, channel) in self.channels.iter_mut().enumerate() {
+            channel.random_init_seeded(radius, density, seed.wrapping_add(c as u64));
+        }
+    }
+
+    /// Advance every channel by one step; returns whether any channel is still alive.
+    ///
+    /// Like [`SimpleLife::update`], each channel first follows its growth
+    /// schedule and counts the step even when its dt is 0.
+    pub fn update(&mut self) -> bool {
+        for channel in &mut self.channels {
+            channel.follow_growth_schedule();
+        }
+
+        // All potentials come from the same state, before any channel changes
+        self.potentials.resize_with(self.channels.len(), Vec::new);
+        for (channel, potential) in self.channels.iter_mut().zip(&mut self.potentials) {
+            potential.resize(channel.grid.len(), 0.0);
+            let mut scratch = std::mem::take(&mut channel.scratch);
+            channel.potential_into(potential, &mut scratch);
+            channel.scratch = scratch;
+        }
+
+        for (channel, weights) in self.channels.iter_mut().zip(&self.interaction) {
+            if channel.dt != 0.0 {
+                self.mixed.clear();
+                self.mixed.resize(channel.grid.len(), 0.0);
+                for (potential, &w) in self.potentials.iter().zip(weights) {
+                    if w != 0.0 {
+                        for (m, &p) in self.mixed.iter_mut().zip(potential) {
+                            *m += w * p;
+                        }
+                    }
+                }
+                channel.apply_growth(&self.mixed, channel.dt);
+            }
+            channel.count_steps(1);
+        }
+
+        self.channels.iter().any(SimpleLife::is_alive)
+    }
+
+    /// Sum of the cell values over all channels.
+    pub fn total_mass(&self) -> Real {
+        self.channels.iter().map(SimpleLife::total_mass).sum()
+    }
+
+    /// Display buffer with the first three channels as red, green and blue;
+    /// missing channels stay dark and further ones are not shown.
+    pub fn create_buffer(&self) -> Vec<u32> {
+        let Some(first) = self.channels.first() else {
+            return Vec::new();
+        };
+
+        let mut buffer = vec![0u32; first.grid.len()];
+        for (channel, shift) in self.channels.iter().zip([16, 8, 0]) {
+            for (pixel, &value) in buffer.iter_mut().zip(&channel.grid) {
+                *pixel |= ((value.clamp(0.0, 1.0) * 255.0) as u32) << shift;
+            }
+        }
+        buffer
+    }
+}
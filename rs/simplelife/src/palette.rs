@@ -0,0 +1,112 @@
+//! Color maps from cell values to displayable pixels.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::Real;
+
+/// Maps a cell value in `0.0..=1.0` to a packed `0xRRGGBB` color.
+///
+/// Palettes print and parse as `blue`, `viridis`, `magma`, `grayscale` and `heat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Palette {
+    /// The original black-to-blue ramp with a touch of green and red at the top.
+    #[default]
+    Blue,
+    /// Perceptually uniform dark purple to yellow.
+    Viridis,
+    /// Perceptually uniform black to pale yellow through magenta.
+    Magma,
+    Grayscale,
+    /// Black through red and yellow to white.
+    Heat,
+}
+
+// Control points sampled from the matplotlib maps at 0, 1/8, ..., 1
+const VIRIDIS: [(u8, u8, u8); 9] = [
+    (68, 1, 84), (71, 44, 122), (59, 81, 139), (44, 113, 142), (33, 144, 141),
+    (39, 173, 129), (92, 200, 99), (170, 220, 50), (253, 231, 37),
+];
+const MAGMA: [(u8, u8, u8); 9] = [
+    (0, 0, 4), (28, 16, 68), (79, 18, 123), (129, 37, 129), (181, 54, 122),
+    (229, 80, 100), (251, 135, 97), (254, 194, 135), (252, 253, 191),
+];
+
+impl Palette {
+    /// Every palette, in the order the window cycles through them.
+    pub const ALL: [Palette; 5] = [
+        Palette::Blue,
+        Palette::Viridis,
+        Palette::Magma,
+        Palette::Grayscale,
+        Palette::Heat,
+    ];
+
+    /// Color for one cell value; values outside `0.0..=1.0` are clamped.
+    pub fn color(self, value: Real) -> u32 {
+        match self {
+            Palette::Blue => {
+                // Kept as before so saved frames don't change
+                let blue = (value * 255.0) as u8;
+                let green = (value * value * 100.0) as u8; // Slight green component for medium values
+                let red = (value * value * value * 50.0) as u8; // Very slight red for high values
+                pack(red, green, blue)
+            }
+            Palette::Viridis => lerp_table(&VIRIDIS, value),
+            Palette::Magma => lerp_table(&MAGMA, value),
+            Palette::Grayscale => {
+                let v = (value.clamp(0.0, 1.0) * 255.0) as u8;
+                pack(v, v, v)
+            }
+            Palette::Heat => {
+                let t = value.clamp(0.0, 1.0) * 3.0;
+                let red = (t.min(1.0) * 255.0) as u8;
+                let green = ((t - 1.0).clamp(0.0, 1.0) * 255.0) as u8;
+                let blue = ((t - 2.0).clamp(0.0, 1.0) * 255.0) as u8;
+                pack(red, green, blue)
+            }
+        }
+    }
+
+    /// The palette after this one in [`Palette::ALL`], wrapping around.
+    pub fn next(self) -> Palette {
+        let index = Palette::ALL.iter().position(|&p| p == self).unwrap_or(0);
+        Palette::ALL[(index + 1) % Palette::ALL.len()]
+    }
+}
+
+impl fmt::Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Palette::Blue => "blue",
+            Palette::Viridis => "viridis",
+            Palette::Magma => "magma",
+            Palette::Grayscale => "grayscale",
+            Palette::Heat => "heat",
+        })
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Palette::ALL.into_iter()
+            .find(|palette| palette.to_string() == s)
+            .ok_or_else(|| format!("unknown palette '{}'; expected blue, viridis, magma, grayscale or heat", s))
+    }
+}
+
+fn pack(red: u8, green: u8, blue: u8) -> u32 {
+    ((red as u32) << 16) | ((green as u32) << 8) | blue as u32
+}
+
+// Piecewise-linear interpolation between evenly spaced control points
+fn lerp_table(table: &[(u8, u8, u8)], value: Real) -> u32 {
+    let t = value.clamp(0.0, 1.0) * (table.len() - 1) as Real;
+    let i = (t as usize).min(table.len() - 2);
+    let frac = t - i as Real;
+    let (a, b) = (table[i], table[i + 1]);
+    let mix = |x: u8, y: u8| (x as Real + (y as Real - x as Real) * frac).round() as u8;
+    pack(mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2))
+}
@@ -0,0 +1,101 @@
+use minifb::{MouseMode, Window};
+use simplelife::{ScaleMode, Viewport};
+
+/// Owns the window and is the only thing allowed to push pixels into it.
+///
+/// Every buffer is checked against its claimed size and adapted to the current
+/// window size, so a mismatch costs a logged dropped frame rather than the process.
+pub struct Presenter {
+    window: Window,
+    mode: ScaleMode,
+    frame: Vec<u32>,
+}
+
+impl Presenter {
+    /// Present into `window`, fitting buffers of other sizes as `mode` says.
+    pub fn new(window: Window, mode: ScaleMode) -> Self {
+        Presenter {
+            window,
+            mode,
+            frame: Vec::new(),
+        }
+    }
+
+    /// The window, for input.
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    /// The window, to change its title.
+    pub fn window_mut(&mut self) -> &mut Window {
+        &mut self.window
+    }
+
+    /// Current size of the presentation target.
+    pub fn target_size(&self) -> (usize, usize) {
+        self.window.get_size()
+    }
+
+    /// Cell of a `width` x `height` buffer under the mouse, if the pointer is over the picture.
+    pub fn mouse_cell(&self, width: usize, height: usize) -> Option<(usize, usize)> {
+        let (mx, my) = self.window.get_mouse_pos(MouseMode::Discard)?;
+        let (target_w, target_h) = self.target_size();
+        let view = if (target_w, target_h) == (width, height) || target_w == 0 || target_h == 0 {
+            Viewport { x: 0, y: 0, width, height }
+        } else {
+            Viewport::fit(width, height, target_w, target_h, self.mode)
+        };
+
+        let (x, y) = (mx as isize - view.x as isize, my as isize - view.y as isize);
+        if x < 0 || y < 0 || x as usize >= view.width || y as usize >= view.height {
+            return None;
+        }
+        Some((x as usize * width / view.width, y as usize * height / view.height))
+    }
+
+    /// Show a `width` x `height` buffer, returning false if the frame had to be dropped.
+    pub fn present(&mut self, buffer: &[u32], width: usize, height: usize) -> bool {
+        if width == 0 || height == 0 || buffer.len() != width * height {
+            eprintln!("WARNING: Dropping frame: buffer has {} pixels but claims to be {}x{}",
+                     buffer.len(), width, height);
+            // Keep the window responsive to input even though nothing new is drawn
+            self.window.update();
+            return false;
+        }
+
+        let (target_w, target_h) = self.target_size();
+
+        let result = if (target_w, target_h) == (width, height) || target_w == 0 || target_h == 0 {
+            self.window.update_with_buffer(buffer, width, height)
+        } else {
+            self.adapt(buffer, width, height, target_w, target_h);
+            self.window.update_with_buffer(&self.frame, target_w, target_h)
+        };
+
+        match result {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("WARNING: Failed to present {}x{} frame: {}", width, height, e);
+                false
+            }
+        }
+    }
+
+    // Nearest-neighbor resample of the buffer into the persistent frame at the target size
+    fn adapt(&mut self, buffer: &[u32], width: usize, height: usize, target_w: usize, target_h: usize) {
+        let view = Viewport::fit(width, height, target_w, target_h, self.mode);
+
+        self.frame.clear();
+        self.frame.resize(target_w * target_h, 0);
+
+        for y in 0..view.height {
+            let sy = y * height / view.height;
+            let row = (view.y + y) * target_w + view.x;
+
+            for x in 0..view.width {
+                let sx = x * width / view.width;
+                self.frame[row + x] = buffer[sy * width + sx];
+            }
+        }
+    }
+}
@@ -0,0 +1,296 @@
+//! Random jumps through parameter space, behind the viewer's F3 and F4 keys.
+//!
+//! A [`ParamSet`] holds what the [`Randomizer`] varies: the parameters of the
+//! growth preset, the scalar parameters of the kernel shape, which stays in its
+//! family, and `dt`. The update has no noise term, so there is no noise
+//! amplitude to vary. Sets left behind go on a [`History`] to step back
+//! through, and [`ParamsLog`] records every set that was applied.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::{GrowthPreset, KernelShape, Real, SimError, SimpleLife};
+
+/// The parameters the randomizer varies, as applied to a simulation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamSet {
+    pub growth: GrowthPreset,
+    pub kernel: KernelShape,
+    pub dt: Real,
+}
+
+impl ParamSet {
+    /// Install the growth preset, kernel shape and dt in `sim`.
+    ///
+    /// On error `sim` may have taken part of the set; applying the set it had
+    /// before puts it back.
+    pub fn apply(&self, sim: &mut SimpleLife) -> Result<(), SimError> {
+        sim.set_kernel_shape(self.kernel.clone())?;
+        sim.set_dt(self.dt)?;
+        sim.set_growth(self.growth.into())
+    }
+
+    /// The set as a single-line JSON object with the step it was applied at
+    /// and the thumbnail saved then, e.g.
+    /// `{"step":120,"growth":"gaussian:0.15,0.015","kernel":"linear","dt":0.1,"thumbnail":"params_001.png"}`.
+    ///
+    /// Growth and kernel are written as the specs `--growth` and `--kernel` take.
+    pub fn to_json(&self, step: u64, thumbnail: &str) -> String {
+        format!(
+            "{{\"step\":{},\"growth\":{},\"kernel\":{},\"dt\":{},\"thumbnail\":{}}}",
+            step,
+            json_string(&self.growth.to_string()),
+            json_string(&self.kernel.to_string()),
+            self.dt,
+            json_string(thumbnail),
+        )
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// A part of the [`ParamSet`] that can take part in randomizing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Param {
+    Growth,
+    Kernel,
+    Dt,
+}
+
+impl Param {
+    /// Every part, the default for [`Randomizer::new`].
+    pub const ALL: [Param; 3] = [Param::Growth, Param::Kernel, Param::Dt];
+}
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Param::Growth => "growth",
+            Param::Kernel => "kernel",
+            Param::Dt => "dt",
+        })
+    }
+}
+
+impl FromStr for Param {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Param::ALL.into_iter()
+            .find(|param| param.to_string() == s)
+            .ok_or_else(|| format!("unknown parameter '{}'; expected growth, kernel or dt", s))
+    }
+}
+
+/// The range one parameter is kept in, parsed from `NAME=MIN..MAX`.
+///
+/// Names are `dt`, or the names from [`GrowthPreset::params_mut`] and
+/// [`KernelShape::params_mut`] prefixed with `growth.` and `kernel.`, e.g.
+/// `growth.sigma=0.01..0.05`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bound {
+    pub name: String,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl FromStr for Bound {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let parsed = spec.split_once('=').and_then(|(name, range)| {
+            let (min, max) = range.split_once("..")?;
+            Some((name.trim(), min.trim().parse::<f64>().ok()?, max.trim().parse::<f64>().ok()?))
+        });
+        let Some((name, min, max)) = parsed else {
+            return Err(format!("'{}' is not a NAME=MIN..MAX bound", spec));
+        };
+        if !min.is_finite() || !max.is_finite() || min > max {
+            return Err(format!("bound '{}' needs finite limits with MIN <= MAX", spec));
+        }
+        Ok(Bound { name: name.to_string(), min, max })
+    }
+}
+
+/// How far the [`Randomizer`] moves each parameter and the ranges it keeps
+/// them in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bounds {
+    /// Each parameter moves by up to this fraction of its current value, or
+    /// of 1 for values near 0, before it is clamped.
+    pub spread: f64,
+    /// Ranges of individual parameters; a later bound on the same name wins.
+    /// Parameters without one are only kept from going negative.
+    pub ranges: Vec<Bound>,
+}
+
+impl Default for Bounds {
+    fn default() -> Self {
+        Bounds { spread: 0.25, ranges: Vec::new() }
+    }
+}
+
+impl Bounds {
+    /// Keep `value` of the parameter `name` within its range.
+    pub fn clamp(&self, name: &str, value: f64) -> f64 {
+        match self.ranges.iter().rev().find(|bound| bound.name == name) {
+            Some(bound) => value.clamp(bound.min, bound.max),
+            None => value.max(0.0),
+        }
+    }
+}
+
+// Attempts at a valid variation of the growth or kernel before keeping it as is
+const TRIES: usize = 16;
+
+/// Draws variations on a [`ParamSet`] from a seeded RNG, so a session can be
+/// replayed with the same seed.
+pub struct Randomizer {
+    rng: StdRng,
+    bounds: Bounds,
+    params: Vec<Param>,
+}
+
+impl Randomizer {
+    /// Vary the given parts of a set within `bounds`, drawing from `seed`.
+    pub fn new(seed: u64, bounds: Bounds, params: &[Param]) -> Self {
+        Randomizer { rng: StdRng::seed_from_u64(seed), bounds, params: params.to_vec() }
+    }
+
+    pub fn bounds(&self) -> &Bounds {
+        &self.bounds
+    }
+
+    /// The parts of a set that get varied.
+    pub fn params(&self) -> &[Param] {
+        &self.params
+    }
+
+    /// A variation on `current`, every parameter of the participating parts
+    /// moved at random and clamped to its bound.
+    ///
+    /// The fixed quadratic presets are varied as [`GrowthPreset::Quadratic`]
+    /// with their coefficients. A growth or kernel that still fails its check
+    /// after a few draws, e.g. with the low end of a band above the high one,
+    /// is kept as it was, as is everything that does not participate.
+    // The casts to f64 are no-ops with the f64 feature
+    #[allow(clippy::unnecessary_cast)]
+    pub fn randomize(&mut self, current: &ParamSet) -> ParamSet {
+        let mut next = current.clone();
+        if self.params.contains(&Param::Growth) {
+            let growth = match current.growth {
+                GrowthPreset::QuadraticWide => GrowthPreset::Quadratic { a: 1.8, b: 0.2 },
+                GrowthPreset::QuadraticOriginal => GrowthPreset::Quadratic { a: 2.0, b: 0.5 },
+                growth => growth,
+            };
+            for _ in 0..TRIES {
+                let mut candidate = growth;
+                for (name, value) in candidate.params_mut() {
+                    *value = self.vary(&format!("growth.{}", name), *value as f64) as Real;
+                }
+                if candidate.check().is_ok() {
+                    next.growth = candidate;
+                    break;
+                }
+            }
+        }
+        if self.params.contains(&Param::Kernel) {
+            for _ in 0..TRIES {
+                let mut candidate = current.kernel.clone();
+                for (name, value) in candidate.params_mut() {
+                    *value = self.vary(&format!("kernel.{}", name), *value as f64) as f32;
+                }
+                if candidate.check().is_ok() {
+                    next.kernel = candidate;
+                    break;
+                }
+            }
+        }
+        if self.params.contains(&Param::Dt) {
+            next.dt = self.vary("dt", current.dt as f64) as Real;
+        }
+        next
+    }
+
+    fn vary(&mut self, name: &str, value: f64) -> f64 {
+        let reach = self.bounds.spread * value.abs().max(1.0e-3);
+        let moved = value + reach * self.rng.gen_range(-1.0..=1.0);
+        self.bounds.clamp(name, moved)
+    }
+}
+
+/// The parameter sets visited before the current one; F4 walks back through
+/// them, most recent first.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    stack: Vec<ParamSet>,
+}
+
+impl History {
+    /// Remember a set that is being left.
+    pub fn push(&mut self, set: ParamSet) {
+        self.stack.push(set);
+    }
+
+    /// Take the most recently left set, or `None` once back at the first one.
+    pub fn pop(&mut self) -> Option<ParamSet> {
+        self.stack.pop()
+    }
+
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+}
+
+/// Appends every applied [`ParamSet`] to a JSON Lines file such as
+/// `params_history.jsonl`, one [`ParamSet::to_json`] object per line.
+///
+/// Lines are flushed as they are written, so an interrupted session keeps them.
+pub struct ParamsLog {
+    path: PathBuf,
+    file: BufWriter<File>,
+}
+
+impl ParamsLog {
+    /// Create `path`, replacing any earlier log.
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, SimError> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::create(&path).map(BufWriter::new).map_err(|e| SimError::io(&path, e))?;
+        Ok(ParamsLog { path, file })
+    }
+
+    /// Append `set`, applied at `step` with a thumbnail saved to `thumbnail`.
+    pub fn log(&mut self, set: &ParamSet, step: u64, thumbnail: &str) -> Result<(), SimError> {
+        writeln!(self.file, "{}", set.to_json(step, thumbnail))
+            .and_then(|()| self.file.flush())
+            .map_err(|e| SimError::io(&self.path, e))
+    }
+
+    /// Path the sets are written to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
@@ -0,0 +1,263 @@
+//! Recording runs: animated GIFs of the display, CSV statistics and strips
+//! of labelled frames.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::font::{GLYPH_HEIGHT, draw_text, text_width};
+use crate::{Palette, SimError, SimpleLife};
+
+/// Records `0xRRGGBB` display buffers (as produced by
+/// [`create_buffer`](crate::SimpleLife::create_buffer)) into an animated GIF.
+///
+/// Each frame is shown for as long as it took the next one to arrive, so the
+/// clip plays back at the speed it was recorded, within GIF's 10 ms resolution.
+pub struct GifRecorder {
+    path: PathBuf,
+    width: usize,
+    height: usize,
+    encoder: gif::Encoder<BufWriter<File>>,
+    // The latest frame is held back until we know how long it was on screen
+    pending: Option<(Vec<u8>, Instant)>,
+    last_delay: u16,
+    frames: usize,
+}
+
+impl GifRecorder {
+    /// Create `path` and get ready to record `width` x `height` frames.
+    pub fn start(path: impl AsRef<Path>, width: usize, height: usize) -> Result<Self, SimError> {
+        let path = path.as_ref().to_path_buf();
+        if width == 0 || height == 0 || width > u16::MAX as usize || height > u16::MAX as usize {
+            return Err(SimError::InvalidParameter(format!("GIF frames can't be {}x{}", width, height)));
+        }
+
+        let file = File::create(&path).map_err(|e| SimError::io(&path, e))?;
+        let mut encoder = gif::Encoder::new(BufWriter::new(file), width as u16, height as u16, &[])
+            .map_err(|e| gif_error(&path, e))?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(|e| gif_error(&path, e))?;
+
+        Ok(GifRecorder {
+            path,
+            width,
+            height,
+            encoder,
+            pending: None,
+            last_delay: 3,
+            frames: 0,
+        })
+    }
+
+    /// Add a frame; `buffer` must hold exactly `width * height` pixels.
+    pub fn push_frame(&mut self, buffer: &[u32]) -> Result<(), SimError> {
+        if buffer.len() != self.width * self.height {
+            return Err(SimError::InvalidParameter(format!(
+                "frame has {} pixels, the recording is {}x{}", buffer.len(), self.width, self.height)));
+        }
+
+        let now = Instant::now();
+        if let Some((rgb, shown_at)) = self.pending.take() {
+            // GIF delays are in hundredths of a second
+            let delay = (now.duration_since(shown_at).as_secs_f64() * 100.0).round().clamp(1.0, u16::MAX as f64) as u16;
+            self.write(&rgb, delay)?;
+            self.last_delay = delay;
+        }
+
+        let rgb = buffer.iter()
+            .flat_map(|&c| [(c >> 16) as u8, (c >> 8) as u8, c as u8])
+            .collect();
+        self.pending = Some((rgb, now));
+        Ok(())
+    }
+
+    /// Number of frames recorded so far.
+    pub fn frames(&self) -> usize {
+        self.frames + self.pending.is_some() as usize
+    }
+
+    /// Path the recording is written to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write the last frame and close the file, returning the number of frames.
+    pub fn finish(mut self) -> Result<usize, SimError> {
+        if let Some((rgb, _)) = self.pending.take() {
+            self.write(&rgb, self.last_delay)?;
+        }
+
+        let path = self.path;
+        self.encoder.into_inner()
+            .and_then(|mut w| io::Write::flush(&mut w).map_err(gif::EncodingError::from))
+            .map_err(|e| gif_error(&path, e))?;
+        Ok(self.frames)
+    }
+
+    fn write(&mut self, rgb: &[u8], delay: u16) -> Result<(), SimError> {
+        let mut frame = gif::Frame::from_rgb_speed(self.width as u16, self.height as u16, rgb, 10);
+        frame.delay = delay;
+        self.encoder.write_frame(&frame).map_err(|e| gif_error(&self.path, e))?;
+        self.frames += 1;
+        Ok(())
+    }
+}
+
+fn gif_error(path: &Path, e: gif::EncodingError) -> SimError {
+    match e {
+        gif::EncodingError::Io(e) => SimError::io(path, e),
+        other => SimError::io(path, io::Error::other(other)),
+    }
+}
+
+/// Writes one CSV row of statistics per logged step:
+/// `step,mass,active_cells,com_x,com_y,time`, where `time` is
+/// [`SimpleLife::sim_time`], last so readers of the original five columns
+/// keep working.
+///
+/// Every row is flushed as it is written, so an interrupted run keeps its data.
+/// The center of mass columns are empty once the grid is empty.
+pub struct StatsLogger {
+    path: PathBuf,
+    file: BufWriter<File>,
+}
+
+impl StatsLogger {
+    /// Create `path` and write the header.
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, SimError> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = File::create(&path).map(BufWriter::new).map_err(|e| SimError::io(&path, e))?;
+        writeln!(file, "step,mass,active_cells,com_x,com_y,time")
+            .and_then(|()| file.flush())
+            .map_err(|e| SimError::io(&path, e))?;
+        Ok(StatsLogger { path, file })
+    }
+
+    /// Append the statistics of `sim` at `step`.
+    pub fn log(&mut self, sim: &SimpleLife, step: usize) -> Result<(), SimError> {
+        let (com_x, com_y) = match sim.center_of_mass() {
+            Some((x, y)) => (x.to_string(), y.to_string()),
+            None => (String::new(), String::new()),
+        };
+        writeln!(self.file, "{},{},{},{},{},{}", step, sim.total_mass(), sim.active_cells(), com_x, com_y, sim.sim_time())
+            .and_then(|()| self.file.flush())
+            .map_err(|e| SimError::io(&self.path, e))
+    }
+
+    /// Path the statistics are written to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// How [`export_strip`] draws its frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StripOptions {
+    /// Height of every thumbnail in pixels; the width keeps the aspect ratio
+    /// of the grid.
+    pub thumbnail_height: usize,
+    pub palette: Palette,
+    /// Space around the strip and between thumbnails and labels, in pixels.
+    pub margin: usize,
+}
+
+impl Default for StripOptions {
+    fn default() -> Self {
+        StripOptions { thumbnail_height: 128, palette: Palette::default(), margin: 8 }
+    }
+}
+
+/// The layout of an image written by [`export_strip`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Strip {
+    pub width: usize,
+    pub height: usize,
+    /// The frames from left to right.
+    pub frames: Vec<StripFrame>,
+    /// Requested steps that are past the end of the run or were already passed.
+    pub skipped: Vec<u64>,
+}
+
+/// Where one step ended up in a [`Strip`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StripFrame {
+    pub step: u64,
+    /// Top left corner of the thumbnail.
+    pub x: usize,
+    pub y: usize,
+    /// Top left corner of the "step N" label under it.
+    pub label_x: usize,
+    pub label_y: usize,
+}
+
+/// Run `sim` on to each of `steps` and write the frames side by side into a
+/// single PNG at `path`, each labelled with its step, e.g. for a README.
+///
+/// The steps are visited in increasing order and duplicates are drawn once.
+/// Steps past `run_length` and steps `sim` has already passed are left out
+/// and listed in [`Strip::skipped`] for the caller to warn about; it is an
+/// error if that leaves nothing to draw. `sim` ends at the last drawn step,
+/// even if the pattern died out before it.
+pub fn export_strip(
+    sim: &mut SimpleLife,
+    steps: &[u64],
+    run_length: u64,
+    path: impl AsRef<Path>,
+    options: StripOptions,
+) -> Result<Strip, SimError> {
+    let path = path.as_ref();
+    if options.thumbnail_height == 0 {
+        return Err(SimError::InvalidParameter("strip thumbnails need a height of at least 1 pixel".to_string()));
+    }
+    let mut wanted = steps.to_vec();
+    wanted.sort_unstable();
+    wanted.dedup();
+    let (drawn, skipped): (Vec<u64>, Vec<u64>) = wanted.into_iter()
+        .partition(|&step| step >= sim.steps() && step <= run_length);
+    if drawn.is_empty() {
+        return Err(SimError::InvalidParameter(format!(
+            "none of the steps {:?} lie between step {} and the end of the run at {}", steps, sim.steps(), run_length)));
+    }
+
+    let margin = options.margin;
+    let thumbnail_height = options.thumbnail_height;
+    let thumbnail_width = ((sim.width() * thumbnail_height) as f64 / sim.height() as f64).round().max(1.0) as usize;
+    // Labels grow with the thumbnails so they stay readable
+    let scale = (thumbnail_height / 64).max(1);
+    let labels: Vec<String> = drawn.iter().map(|step| format!("step {}", step)).collect();
+    let columns: Vec<usize> = labels.iter().map(|label| thumbnail_width.max(text_width(label, scale))).collect();
+
+    let width = margin + columns.iter().map(|column| column + margin).sum::<usize>();
+    let height = 3 * margin + thumbnail_height + GLYPH_HEIGHT * scale;
+    let mut pixels = vec![0; width * height];
+    let mut frames = Vec::with_capacity(drawn.len());
+    let mut left = margin;
+    for ((&step, label), column) in drawn.iter().zip(&labels).zip(columns) {
+        // Not step_n, which stops at extinction and would leave the label wrong
+        while sim.steps() < step {
+            sim.update();
+        }
+        let buffer = sim.create_buffer_with(options.palette);
+
+        // Nearest-neighbor scaling, so cells stay crisp at any thumbnail size
+        let (x, y) = (left + (column - thumbnail_width) / 2, margin);
+        for ty in 0..thumbnail_height {
+            let gy = ty * sim.height() / thumbnail_height;
+            for tx in 0..thumbnail_width {
+                let gx = tx * sim.width() / thumbnail_width;
+                pixels[(y + ty) * width + x + tx] = buffer[gy * sim.width() + gx];
+            }
+        }
+
+        let (label_x, label_y) = (left + (column - text_width(label, scale)) / 2, y + thumbnail_height + margin);
+        draw_text(&mut pixels, width, (label_x, label_y), label, scale, 0xffffff);
+        frames.push(StripFrame { step, x, y, label_x, label_y });
+        left += column + margin;
+    }
+
+    let rgb: Vec<u8> = pixels.iter().flat_map(|&p| [(p >> 16) as u8, (p >> 8) as u8, p as u8]).collect();
+    image::save_buffer_with_format(path, &rgb, width as u32, height as u32, image::ExtendedColorType::Rgb8, image::ImageFormat::Png)
+        .map_err(|e| SimError::image(path, e))?;
+
+    Ok(Strip { width, height, frames, skipped })
+}
@@ -0,0 +1,115 @@
+//! Drawing the grid into byte buffers, e.g. textures of an embedding UI, and
+//! fitting buffers into targets of another size.
+
+use crate::{Palette, Real};
+
+/// Turns cell values into pixels.
+///
+/// Implement it to draw the grid yourself; [`PaletteRenderer`] covers the
+/// built-in color maps.
+pub trait Renderer {
+    /// Bytes written per cell.
+    fn bytes_per_pixel(&self) -> usize;
+
+    /// Draw the row-major `width` x `height` `grid` into `out`, which holds
+    /// exactly `width * height * bytes_per_pixel()` bytes.
+    fn render(&self, grid: &[Real], width: usize, height: usize, out: &mut [u8]);
+}
+
+/// Byte layout of a rendered pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PixelFormat {
+    /// Red, green, blue.
+    #[default]
+    Rgb8,
+    /// Red, green, blue and an opaque alpha, as most GPU textures expect.
+    Rgba8,
+}
+
+impl PixelFormat {
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelFormat::Rgb8 => 3,
+            PixelFormat::Rgba8 => 4,
+        }
+    }
+}
+
+/// Renders with a [`Palette`], giving the same colors as
+/// [`create_buffer_with`](crate::SimpleLife::create_buffer_with).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PaletteRenderer {
+    pub palette: Palette,
+    pub format: PixelFormat,
+}
+
+impl PaletteRenderer {
+    pub fn new(palette: Palette, format: PixelFormat) -> Self {
+        PaletteRenderer { palette, format }
+    }
+}
+
+impl Renderer for PaletteRenderer {
+    fn bytes_per_pixel(&self) -> usize {
+        self.format.bytes_per_pixel()
+    }
+
+    /// Panics if `grid` or `out` don't match the size.
+    fn render(&self, grid: &[Real], width: usize, height: usize, out: &mut [u8]) {
+        let bytes = self.bytes_per_pixel();
+        assert_eq!(grid.len(), width * height, "grid does not hold {}x{} cells", width, height);
+        assert_eq!(out.len(), grid.len() * bytes, "output does not hold {}x{} pixels", width, height);
+
+        for (pixel, &value) in out.chunks_exact_mut(bytes).zip(grid) {
+            let color = self.palette.color(value);
+            pixel[..3].copy_from_slice(&[(color >> 16) as u8, (color >> 8) as u8, color as u8]);
+            if let Some(alpha) = pixel.get_mut(3) {
+                *alpha = 255;
+            }
+        }
+    }
+}
+
+/// How a buffer whose size differs from its target gets fitted into it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScaleMode {
+    /// Stretch to fill the whole target, ignoring the aspect ratio.
+    Stretch,
+    /// Keep the aspect ratio and leave bars on two sides of the target.
+    Letterbox,
+}
+
+/// Region of a target a buffer gets drawn into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Viewport {
+    /// Where a `src_w` x `src_h` buffer lands in a `dst_w` x `dst_h` target.
+    ///
+    /// A letterboxed picture is centered and at least one pixel wide and high.
+    pub fn fit(src_w: usize, src_h: usize, dst_w: usize, dst_h: usize, mode: ScaleMode) -> Viewport {
+        match mode {
+            ScaleMode::Stretch => Viewport { x: 0, y: 0, width: dst_w, height: dst_h },
+            ScaleMode::Letterbox => {
+                // Compare src_w / src_h against dst_w / dst_h without going through floats
+                let (width, height) = if src_w * dst_h > dst_w * src_h {
+                    (dst_w, (src_h * dst_w / src_w).max(1))
+                } else {
+                    ((src_w * dst_h / src_h).max(1), dst_h)
+                };
+
+                Viewport {
+                    x: (dst_w - width) / 2,
+                    y: (dst_h - height) / 2,
+                    width,
+                    height,
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,279 @@
+//! Comparison reports across finished headless runs.
+//!
+//! Each run is a `simplelife_static` output directory holding the
+//! `simplelife_stats.csv` written by [`StatsLogger`](crate::recorder::StatsLogger)
+//! and the frames it saved. [`write_comparison`] aligns the runs by step and
+//! writes one Markdown file with the mass plot and the final frames embedded as
+//! base64 PNGs, so the report can be passed around on its own.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use image::ImageEncoder;
+use image::codecs::png::PngEncoder;
+use image::imageops::FilterType;
+
+use crate::SimError;
+use crate::font::{GLYPH_HEIGHT, draw_text, text_width};
+
+/// Name of the statistics file in a run directory.
+pub const STATS_FILE: &str = "simplelife_stats.csv";
+/// Name of the frame saved once a run is over.
+pub const FINAL_FRAME: &str = "simplelife_final.pgm";
+
+// Curve colors, cycled through when there are more runs than colors
+const COLORS: [u32; 8] = [0x4e79a7, 0xf28e2b, 0xe15759, 0x76b7b2, 0x59a14f, 0xedc948, 0xb07aa1, 0xff9da7];
+const PLOT_WIDTH: usize = 640;
+const PLOT_HEIGHT: usize = 320;
+const THUMBNAIL_HEIGHT: u32 = 128;
+
+/// One logged step of a run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatsRow {
+    pub step: u64,
+    pub mass: f64,
+    pub active_cells: usize,
+}
+
+/// A finished run read back from its output directory.
+#[derive(Debug, Clone)]
+pub struct RunRecord {
+    /// The directory name, used to label the run in the report.
+    pub name: String,
+    /// Logged steps in the order they were written; empty if the run was cut
+    /// short before logging anything.
+    pub rows: Vec<StatsRow>,
+    /// The frame saved at the end of the run, or the last one saved during it
+    /// for runs from before the final frame was kept.
+    pub final_frame: Option<PathBuf>,
+}
+
+impl RunRecord {
+    /// Read the statistics and find the final frame in a run directory.
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, SimError> {
+        let dir = dir.as_ref();
+        let name = dir.file_name().unwrap_or(dir.as_os_str()).to_string_lossy().into_owned();
+        let path = dir.join(STATS_FILE);
+        let csv = fs::read_to_string(&path).map_err(|e| SimError::io(&path, e))?;
+        let invalid = |line: usize, msg: &str| SimError::io(&path, io::Error::new(
+            io::ErrorKind::InvalidData, format!("line {}: {}", line + 1, msg)));
+
+        let mut lines = csv.lines().enumerate();
+        match lines.next() {
+            Some((_, header)) if header.starts_with("step,mass,active_cells") => {}
+            _ => return Err(invalid(0, "expected the step,mass,active_cells header")),
+        }
+        let rows = lines
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(i, line)| {
+                let mut fields = line.split(',');
+                let mut next = || fields.next().ok_or_else(|| invalid(i, "too few columns"));
+                let (step, mass, active_cells) = (next()?, next()?, next()?);
+                Ok(StatsRow {
+                    step: step.parse().map_err(|_| invalid(i, "invalid step"))?,
+                    mass: mass.parse().map_err(|_| invalid(i, "invalid mass"))?,
+                    active_cells: active_cells.parse().map_err(|_| invalid(i, "invalid active cell count"))?,
+                })
+            })
+            .collect::<Result<Vec<_>, SimError>>()?;
+
+        Ok(RunRecord { name, rows, final_frame: final_frame(dir) })
+    }
+
+    /// The last logged step.
+    pub fn last(&self) -> Option<&StatsRow> {
+        self.rows.last()
+    }
+
+    /// The last row logged at or before `step`.
+    pub fn at(&self, step: u64) -> Option<&StatsRow> {
+        self.rows.iter().rev().find(|row| row.step <= step)
+    }
+
+    /// The first logged step with no active cells left, if the pattern died out.
+    pub fn extinction_step(&self) -> Option<u64> {
+        self.rows.iter().find(|row| row.active_cells == 0).map(|row| row.step)
+    }
+}
+
+// The final frame if the run saved one, otherwise the highest numbered frame
+fn final_frame(dir: &Path) -> Option<PathBuf> {
+    let path = dir.join(FINAL_FRAME);
+    if path.is_file() {
+        return Some(path);
+    }
+    let mut frames: Vec<PathBuf> = fs::read_dir(dir).ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.file_name().and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix("simplelife_")?.strip_suffix(".pgm"))
+            .is_some_and(|index| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit())))
+        .collect();
+    // Zero-padded, so longer names only appear past 999 frames
+    frames.sort_by_key(|path| (path.as_os_str().len(), path.clone()));
+    frames.pop()
+}
+
+/// What [`write_comparison`] put into a report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Comparison {
+    /// The last step every run with statistics reached, where their masses
+    /// are compared side by side.
+    pub common_step: Option<u64>,
+    /// Embedded images: the mass plot plus one thumbnail per final frame.
+    pub images: usize,
+}
+
+/// Write a Markdown report comparing `runs` to `path`: their mass curves
+/// overlaid in one plot, a table of outcomes and a thumbnail of each final
+/// frame.
+///
+/// Runs may have different lengths. Each curve ends where its run did, and
+/// the table compares the masses at the last step all runs reached. Runs
+/// without statistics or frames are listed with the missing parts noted.
+pub fn write_comparison(runs: &[RunRecord], path: impl AsRef<Path>) -> Result<Comparison, SimError> {
+    let path = path.as_ref();
+    if runs.is_empty() {
+        return Err(SimError::InvalidParameter("there are no runs to compare".to_string()));
+    }
+    let common_step = runs.iter().filter_map(|run| run.last().map(|row| row.step)).min();
+    let mut images = 0;
+    let mut out = String::new();
+
+    out.push_str("# SimpleLife run comparison\n\n");
+    match common_step {
+        Some(step) => out.push_str(&format!("{} runs, aligned by step. Every run with statistics reached step {}.\n\n", runs.len(), step)),
+        None => out.push_str(&format!("{} runs, none of which logged any statistics.\n\n", runs.len())),
+    }
+
+    out.push_str("## Mass\n\n");
+    if common_step.is_some() {
+        let png = encode_png(&plot_mass(runs), PLOT_WIDTH as u32, PLOT_HEIGHT as u32, path)?;
+        out.push_str(&format!("![Total mass per step](data:image/png;base64,{})\n\n", base64(&png)));
+        images += 1;
+    } else {
+        out.push_str("_No statistics to plot._\n\n");
+    }
+
+    out.push_str("## Outcomes\n\n");
+    let aligned = common_step.map_or("-".to_string(), |step| format!("Mass at step {}", step));
+    out.push_str(&format!("| Run | Color | Steps | Final mass | Active cells | {} | Outcome |\n", aligned));
+    out.push_str("|---|---|---:|---:|---:|---:|---|\n");
+    for (i, run) in runs.iter().enumerate() {
+        let color = format!("`#{:06x}`", COLORS[i % COLORS.len()]);
+        match run.last() {
+            Some(last) => {
+                let at_common = common_step.and_then(|step| run.at(step))
+                    .map_or("-".to_string(), |row| format!("{:.3}", row.mass));
+                let outcome = match run.extinction_step() {
+                    Some(step) => format!("extinct at step {}", step),
+                    None => "alive".to_string(),
+                };
+                out.push_str(&format!("| {} | {} | {} | {:.3} | {} | {} | {} |\n",
+                                      run.name, color, last.step, last.mass, last.active_cells, at_common, outcome));
+            }
+            None => out.push_str(&format!("| {} | {} | - | - | - | - | no statistics |\n", run.name, color)),
+        }
+    }
+    out.push('\n');
+
+    out.push_str("## Final frames\n\n");
+    for run in runs {
+        out.push_str(&format!("### {}\n\n", run.name));
+        match &run.final_frame {
+            Some(frame) => {
+                let image = image::open(frame).map_err(|e| SimError::image(frame, e))?.to_rgb8();
+                let width = (image.width() * THUMBNAIL_HEIGHT).div_ceil(image.height()).max(1);
+                // Nearest-neighbor scaling, so cells stay crisp
+                let thumbnail = image::imageops::resize(&image, width, THUMBNAIL_HEIGHT, FilterType::Nearest);
+                let png = encode_rgb_png(thumbnail.as_raw(), width, THUMBNAIL_HEIGHT, path)?;
+                out.push_str(&format!("![Final frame of {}](data:image/png;base64,{})\n\n", run.name, base64(&png)));
+                images += 1;
+            }
+            None => out.push_str("_No frame saved._\n\n"),
+        }
+    }
+
+    let mut file = File::create(path).map(BufWriter::new).map_err(|e| SimError::io(path, e))?;
+    file.write_all(out.as_bytes())
+        .and_then(|()| file.flush())
+        .map_err(|e| SimError::io(path, e))?;
+
+    Ok(Comparison { common_step, images })
+}
+
+// Every run's mass against its step, on shared axes starting at 0, with the
+// largest step and mass written at the ends of the axes
+fn plot_mass(runs: &[RunRecord]) -> Vec<u32> {
+    let (width, height) = (PLOT_WIDTH, PLOT_HEIGHT);
+    let margin = 2 * GLYPH_HEIGHT + 8;
+    let (left, right, top, bottom) = (margin, width - margin, margin, height - margin);
+    let max_step = runs.iter().filter_map(|run| run.last()).map(|row| row.step).max().unwrap_or(0).max(1);
+    let max_mass = runs.iter().flat_map(|run| &run.rows).map(|row| row.mass).fold(0.0, f64::max);
+    // Don't blow up a run that stays empty
+    let max_mass = if max_mass > 0.0 { max_mass * 1.05 } else { 1.0 };
+
+    let mut pixels = vec![0; width * height];
+    for x in left..=right {
+        pixels[bottom * width + x] = 0x606060;
+    }
+    for y in top..=bottom {
+        pixels[y * width + left] = 0x606060;
+    }
+    let step_label = format!("step {}", max_step);
+    draw_text(&mut pixels, width, (right - text_width(&step_label, 1), bottom + 4), &step_label, 1, 0xffffff);
+    draw_text(&mut pixels, width, (left, top - GLYPH_HEIGHT - 4), &format!("{:.0}", max_mass), 1, 0xffffff);
+
+    let column = |step: u64| left + (step as f64 / max_step as f64 * (right - left) as f64).round() as usize;
+    let row = |mass: f64| bottom - (mass.max(0.0) / max_mass * (bottom - top) as f64).round() as usize;
+    for (i, run) in runs.iter().enumerate() {
+        let color = COLORS[i % COLORS.len()];
+        let mut previous: Option<(usize, usize)> = None;
+        for stats in &run.rows {
+            let (x, y) = (column(stats.step), row(stats.mass));
+            // Straight between samples, joined by vertical runs so steep parts stay connected
+            let (from_x, from_y) = previous.unwrap_or((x, y));
+            let mut last_y = from_y;
+            for px in from_x..=x {
+                let t = if x == from_x { 1.0 } else { (px - from_x) as f64 / (x - from_x) as f64 };
+                let py = (from_y as f64 + t * (y as f64 - from_y as f64)).round() as usize;
+                for qy in last_y.min(py)..=last_y.max(py) {
+                    pixels[qy * width + px] = color;
+                }
+                last_y = py;
+            }
+            previous = Some((x, y));
+        }
+    }
+    pixels
+}
+
+fn encode_png(pixels: &[u32], width: u32, height: u32, report: &Path) -> Result<Vec<u8>, SimError> {
+    let rgb: Vec<u8> = pixels.iter().flat_map(|&p| [(p >> 16) as u8, (p >> 8) as u8, p as u8]).collect();
+    encode_rgb_png(&rgb, width, height, report)
+}
+
+fn encode_rgb_png(rgb: &[u8], width: u32, height: u32, report: &Path) -> Result<Vec<u8>, SimError> {
+    let mut png = Vec::new();
+    PngEncoder::new(&mut png)
+        .write_image(rgb, width, height, image::ExtendedColorType::Rgb8)
+        .map_err(|e| SimError::image(report, e))?;
+    Ok(png)
+}
+
+// Standard base64 with padding, for the data URIs
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
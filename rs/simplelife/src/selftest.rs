@@ -0,0 +1,116 @@
+//! Quick sanity checks for a simulation configuration.
+//!
+//! Most "black window" reports come from a dead parameter combination, a broken
+//! kernel or a `dt` that is too large; these checks catch them in a few steps.
+
+use crate::{Normalization, Real, SimpleLife};
+
+/// Result of a single self-test check, with an actionable message when it fails.
+pub struct SelfTestCheck {
+    pub name: &'static str,
+    pub passed: bool,
+    pub message: String,
+}
+
+/// Run the configured kernel, growth, `dt` and clamp on a small 64x64 grid for
+/// `steps` steps from a seeded random pattern and make sure they can actually
+/// produce something visible before the user is shown a black screen. `sim`
+/// itself is left untouched, and a large grid costs no more to test.
+pub fn self_test(sim: &SimpleLife, steps: usize) -> Vec<SelfTestCheck> {
+    // Fixed so that a configuration passes or fails the same way on every run
+    const SEED: u64 = 217;
+    const SIZE: usize = 64;
+    let mut checks = Vec::new();
+    let mut sim = sim.resized(SIZE, SIZE);
+    
+    // The kernel must be finite and normalized, otherwise every potential is garbage
+    let non_finite = sim.kernel().iter().filter(|k| !k.is_finite()).count();
+    checks.push(SelfTestCheck {
+        name: "kernel weights finite",
+        passed: non_finite == 0,
+        message: format!("{} of {} kernel weights are NaN or infinite; check the kernel radius",
+                         non_finite, sim.kernel().len()),
+    });
+    
+    // Only SumToOne fixes the sum; extra kernels add their weights unless those are normalized too
+    if sim.normalization() == Normalization::SumToOne {
+        let weights: Real = sim.extra_kernels.iter().map(|(_, w)| w).sum();
+        let expected = if sim.normalize_kernel_weights && weights != -1.0 { 1.0 } else { 1.0 + weights };
+        let kernel_sum: Real = sim.kernel().iter().sum();
+        checks.push(SelfTestCheck {
+            name: "kernel normalized",
+            passed: (kernel_sum - expected).abs() < 1e-3,
+            message: format!("kernel sums to {:.4} instead of {:.4}; potentials will be out of range",
+                             kernel_sum, expected),
+        });
+    }
+    
+    // Run a short burst and watch the mass
+    sim.random_init_seeded(0.3, 0.3, SEED);
+    let cells = sim.grid().len() as Real;
+    let mut nan_step = None;
+    let mut extinct_step = None;
+    let mut saturated_step = None;
+    
+    for step in 1..=steps {
+        sim.update();
+        
+        if sim.grid().iter().any(|v| !v.is_finite()) {
+            nan_step = Some(step);
+            break;
+        }
+        
+        // Dead the way `is_alive` sees it: nothing left above ACTIVE_THRESHOLD
+        if sim.active_cells() == 0 && extinct_step.is_none() {
+            extinct_step = Some(step);
+        }
+        if sim.total_mass() > 0.95 * cells && saturated_step.is_none() {
+            saturated_step = Some(step);
+        }
+    }
+    
+    checks.push(SelfTestCheck {
+        name: "grid stays finite",
+        passed: nan_step.is_none(),
+        message: format!("NaN appeared in the grid at step {}; the kernel or dt is invalid",
+                         nan_step.unwrap_or(0)),
+    });
+    
+    // Find the smallest potential the growth function lets survive, to explain extinctions
+    let survival_threshold = (0..=1000)
+        .map(|i| i as Real / 1000.0)
+        .find(|&u| sim.growth_function(u) >= 0.0);
+    let extinction_message = match survival_threshold {
+        Some(u) => format!("all cells died by step {}; growth offset {:.2} kills all potentials below {:.2}; consider raising density",
+                           extinct_step.unwrap_or(0), sim.growth_function(0.0), u),
+        None => format!("all cells died by step {}; the growth function is negative for every potential",
+                        extinct_step.unwrap_or(0)),
+    };
+    checks.push(SelfTestCheck {
+        name: "pattern survives",
+        passed: extinct_step.is_none(),
+        message: extinction_message,
+    });
+    
+    checks.push(SelfTestCheck {
+        name: "pattern does not saturate",
+        passed: saturated_step.is_none(),
+        message: format!("the grid filled up by step {}; dt {} is probably too large, consider lowering it",
+                         saturated_step.unwrap_or(0), sim.dt()),
+    });
+    
+    checks
+}
+
+/// Print every check and return whether all of them passed.
+pub fn report_self_test(checks: &[SelfTestCheck]) -> bool {
+    for check in checks {
+        if check.passed {
+            println!("[PASS] {}", check.name);
+        } else {
+            println!("[FAIL] {}: {}", check.name, check.message);
+        }
+    }
+    
+    checks.iter().all(|c| c.passed)
+}
@@ -0,0 +1,1580 @@
+//! The simulation itself: grid, kernel, stepping, rendering and export.
+
+use std::collections::VecDeque;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::ops::ControlFlow;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::active::ActiveTiles;
+use crate::conv::{FftConvolver, Scratch, Separable, convolve_blocked};
+use crate::growth::table_value;
+use crate::kernel::ImageKernel;
+use crate::{Boundary, BuildError, CellError, Clamp, ConvMethod, GaussianGrowth, Grid, GrowthRule, GrowthSchedule, Kernel, KernelFn, KernelShape, Normalization, Palette, PaletteRenderer, ParametricGrowthFn, PixelFormat, Real, Renderer, SimError, SimpleLifeBuilder};
+
+/// A growth curve mapping a potential to the rate of change of a cell.
+pub type GrowthFn = Arc<dyn Fn(Real) -> Real + Send + Sync>;
+
+/// The default growth curve, `1.8 * u * (1 - u) - 0.2`.
+pub fn default_growth(u: Real) -> Real {
+    // More forgiving growth function with a wider "alive" range
+    // Original was: 2.0 * u * (1.0 - u) - 0.5
+    1.8 * u * (1.0 - u) - 0.2
+}
+
+/// Cells above this value count as alive for the population statistics.
+pub const ACTIVE_THRESHOLD: Real = 0.01;
+
+/// Mass above this fraction of the cell count means a run has exploded, see
+/// [`SimpleLife::classify_outcome`].
+pub const EXPLOSION_FRACTION: Real = 0.5;
+
+/// How a run ended, as reported by [`SimpleLife::classify_outcome`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Every cell dropped below [`ACTIVE_THRESHOLD`] at this step.
+    Extinct(usize),
+    /// The mass passed [`EXPLOSION_FRACTION`] of the cells at this step.
+    Exploded(usize),
+    /// Neither happened within the step budget.
+    Stable,
+}
+
+/// A continuous cellular automaton in the spirit of Lenia, but simpler.
+///
+/// The grid holds values in `0.0..=1.0` on a torus. Each step convolves the grid
+/// with a normalized kernel (see [`Kernel`]) to get a potential, maps the
+/// potential through a growth function and adds `dt * growth` to every cell.
+///
+/// Clones evolve identically, RNG included, until one of them is changed.
+#[derive(Clone)]
+pub struct SimpleLife {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) grid: Grid,
+    pub(crate) kernel: Vec<Real>,
+    pub(crate) kernel_radius: usize,
+    // Half-width and half-height of the kernel table, see `Kernel::extent`
+    pub(crate) kernel_extent: (usize, usize),
+    pub(crate) kernel_fn: KernelFn,
+    // Further kernels and the weights their potentials are added in with
+    pub(crate) extra_kernels: Vec<(KernelFn, Real)>,
+    // Whether all kernel weights are scaled to sum to 1
+    pub(crate) normalize_kernel_weights: bool,
+    pub(crate) normalization: Normalization,
+    // Subsamples per axis averaged into each kernel cell, 1 for point sampling
+    pub(crate) kernel_supersampling: usize,
+    // Non-zero kernel entries as (dx, dy, weight), the only ones the direct convolution visits
+    pub(crate) kernel_offsets: Vec<(isize, isize, Real)>,
+    pub(crate) dt: Real,
+    // Whether the builder was asked to accept negative dt, which `set_dt` honours too
+    pub(crate) allow_negative_dt: bool,
+    pub(crate) conv_method: ConvMethod,
+    pub(crate) boundary: Boundary,
+    pub(crate) clamp: Clamp,
+    pub(crate) fft: Option<FftConvolver>,
+    // Row and column factors when the table is separable, used by the direct path
+    pub(crate) separable: Option<Separable>,
+    // Potential and convolution buffers kept across steps so update does not allocate
+    pub(crate) potential: Vec<Real>,
+    pub(crate) scratch: Scratch,
+    // Whether update limits the direct convolution to the tiles near live cells
+    pub(crate) activity_tracking: bool,
+    pub(crate) active: ActiveTiles,
+    pub(crate) growth: GrowthRule,
+    // Parameter handed to a parametric growth rule, the same everywhere unless a map is set
+    pub(crate) parameter: Real,
+    pub(crate) parameter_map: Option<Vec<Real>>,
+    pub(crate) growth_schedule: Option<GrowthSchedule>,
+    // Completed calls to update or update_adaptive, which the schedule is keyed on
+    pub(crate) steps: u64,
+    // Sum of dt over those steps, in f64 so that long f32 runs do not drift
+    pub(crate) sim_time: f64,
+    // Wall-clock time the last call to update or update_adaptive took
+    pub(crate) last_step_duration: Duration,
+    pub(crate) rng: StdRng,
+}
+
+impl SimpleLife {
+    /// Create an empty `width` x `height` simulation with a linear falloff kernel
+    /// of the given radius and time step `dt`.
+    ///
+    /// This is a shortcut that skips validation; use [`SimpleLife::try_new`] or
+    /// [`SimpleLife::builder`] to have the parameters checked.
+    pub fn new(width: usize, height: usize, kernel_radius: usize, dt: Real) -> Self {
+        SimpleLifeBuilder::new()
+            .size(width, height)
+            .kernel_radius(kernel_radius)
+            .dt(dt)
+            .build_unchecked()
+    }
+    
+    /// Like [`SimpleLife::new`], but rejects an empty grid, a radius of 0, a
+    /// kernel that doesn't fit in half the grid and a non-finite or negative `dt`.
+    pub fn try_new(width: usize, height: usize, kernel_radius: usize, dt: Real) -> Result<Self, BuildError> {
+        SimpleLifeBuilder::new()
+            .size(width, height)
+            .kernel_radius(kernel_radius)
+            .dt(dt)
+            .build()
+    }
+    
+    /// Like [`SimpleLife::new`], but with a custom growth curve instead of the
+    /// default quadratic, e.g. a Gaussian bump for classic Lenia.
+    pub fn with_growth(
+        width: usize,
+        height: usize,
+        kernel_radius: usize,
+        dt: Real,
+        growth: impl Fn(Real) -> Real + Send + Sync + 'static,
+    ) -> Self {
+        SimpleLifeBuilder::new()
+            .size(width, height)
+            .kernel_radius(kernel_radius)
+            .dt(dt)
+            .growth(growth)
+            .build_unchecked()
+    }
+    
+    /// Start building a simulation from the default parameters.
+    pub fn builder() -> SimpleLifeBuilder {
+        SimpleLifeBuilder::new()
+    }
+    
+    // Same kernel, growth, dt, clamp and convolution on an empty grid of at least
+    // `width` x `height`, widened so the kernel still fits. The parameter map is
+    // tied to the old size and the schedule's callback to the old run, so both
+    // are left out
+    pub(crate) fn resized(&self, width: usize, height: usize) -> SimpleLife {
+        let (width, height) = (width.max(2 * self.kernel_extent.0 + 1), height.max(2 * self.kernel_extent.1 + 1));
+        let mut sim = SimpleLife {
+            width,
+            height,
+            grid: Grid::new(width, height),
+            potential: Vec::new(),
+            scratch: Scratch::default(),
+            active: ActiveTiles::default(),
+            parameter_map: None,
+            growth_schedule: None,
+            steps: 0,
+            sim_time: 0.0,
+            last_step_duration: Duration::ZERO,
+            ..self.clone()
+        };
+        // Only the FFT plan depends on the grid size
+        sim.init_fft();
+        sim
+    }
+    
+    /// Grid width in cells.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    
+    /// Grid height in cells.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    
+    /// Cell values, indexable by coordinates or as a row-major slice.
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+    
+    /// Value of the cell at `(x, y)`, or `None` outside the grid.
+    pub fn get(&self, x: usize, y: usize) -> Option<Real> {
+        self.grid.get(x, y)
+    }
+    
+    /// Set the cell at `(x, y)`, clamping the value into `0.0..=1.0`.
+    pub fn set(&mut self, x: usize, y: usize, value: Real) -> Result<(), CellError> {
+        if value.is_nan() && x < self.width && y < self.height {
+            return Err(CellError::NotANumber);
+        }
+        
+        self.grid.set(x, y, value.clamp(0.0, 1.0))
+    }
+    
+    /// Value of the cell at `(x, y)` without the `Option`, for hot loops.
+    ///
+    /// Panics outside the grid.
+    pub fn get_unchecked(&self, x: usize, y: usize) -> Real {
+        self.grid[(x, y)]
+    }
+    
+    /// Write a cell without bounds or range checks, for hot loops.
+    ///
+    /// Panics outside the grid; the value is stored as is.
+    pub fn set_unchecked(&mut self, x: usize, y: usize, value: Real) {
+        self.grid[(x, y)] = value;
+    }
+    
+    /// Iterate over the grid one row slice at a time, top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[Real]> {
+        self.grid.chunks_exact(self.width)
+    }
+    
+    /// Iterate over every cell as `(x, y, value)` in row-major order.
+    pub fn iter_cells(&self) -> impl Iterator<Item = (usize, usize, Real)> + '_ {
+        let width = self.width;
+        self.grid.iter().enumerate().map(move |(i, &v)| (i % width, i / width, v))
+    }
+    
+    /// Iterate over the cells above `threshold` as `(x, y, value)` in row-major
+    /// order, e.g. [`ACTIVE_THRESHOLD`] for the cells [`active_cells`](Self::active_cells) counts.
+    pub fn alive_cells(&self, threshold: Real) -> impl Iterator<Item = (usize, usize, Real)> + '_ {
+        self.iter_cells().filter(move |&(_, _, v)| v > threshold)
+    }
+    
+    /// Normalized kernel weights, a row-major table of [`kernel_size`](Self::kernel_size).
+    pub fn kernel(&self) -> &[Real] {
+        &self.kernel
+    }
+    
+    /// Width and height of the kernel table; `(2r+1, 2r+1)` unless the kernel
+    /// is anisotropic.
+    pub fn kernel_size(&self) -> (usize, usize) {
+        (2 * self.kernel_extent.0 + 1, 2 * self.kernel_extent.1 + 1)
+    }
+    
+    /// Render the kernel table as text, one character per weight from ` ` (zero)
+    /// to `@` (the largest weight), to eyeball its shape in a terminal.
+    pub fn dump_kernel(&self) -> String {
+        const SHADES: &[u8] = b" .:-=+*#%@";
+        
+        let (kernel_width, kernel_height) = self.kernel_size();
+        let max = self.kernel.iter().cloned().fold(0.0, Real::max);
+        let mut out = String::with_capacity(kernel_height * (2 * kernel_width + 1));
+        
+        for row in self.kernel.chunks_exact(kernel_width) {
+            for &k in row {
+                let level = if max > 0.0 { (k / max * (SHADES.len() - 1) as Real).round() as usize } else { 0 };
+                // Doubled so the square table doesn't look squashed in a terminal
+                let c = SHADES[level.min(SHADES.len() - 1)] as char;
+                out.push(c);
+                out.push(c);
+            }
+            out.push('\n');
+        }
+        
+        out
+    }
+    
+    /// Write the kernel table as an 8-bit grayscale image, scaled so the largest
+    /// weight is white. The format follows the extension (`.pgm` or `.png`), and
+    /// the image can be loaded back with [`set_kernel_from_image`](Self::set_kernel_from_image).
+    pub fn save_kernel_image(&self, path: &str) -> Result<(), SimError> {
+        let (kernel_width, kernel_height) = self.kernel_size();
+        let max = self.kernel.iter().cloned().fold(0.0, Real::max);
+        let pixels: Vec<u8> = self.kernel.iter()
+            .map(|&k| if max > 0.0 { (k / max * 255.0).round().clamp(0.0, 255.0) as u8 } else { 0 })
+            .collect();
+        
+        save_gray(path, &pixels, kernel_width, kernel_height)
+    }
+    
+    /// Weight function the kernel table was built from.
+    pub fn kernel_fn(&self) -> &dyn Kernel {
+        self.kernel_fn.as_ref()
+    }
+    
+    /// Radius of the convolution kernel.
+    pub fn kernel_radius(&self) -> usize {
+        self.kernel_radius
+    }
+    
+    /// Rebuild the kernel at a new radius with the current shape and weights,
+    /// leaving the grid as it is.
+    ///
+    /// Fails if the radius is 0 or any kernel would no longer fit in half the grid.
+    pub fn set_kernel_radius(&mut self, radius: usize) -> Result<(), SimError> {
+        if radius == 0 {
+            return Err(BuildError::ZeroRadius.into());
+        }
+        
+        let kernels = || std::iter::once(&self.kernel_fn).chain(self.extra_kernels.iter().map(|(kernel, _)| kernel));
+        for kernel in kernels() {
+            kernel.validate_radius(radius).map_err(SimError::InvalidParameter)?;
+        }
+        
+        let max = ((self.width - 1) / 2, (self.height - 1) / 2);
+        let too_large = kernels()
+            .map(|kernel| kernel.extent(radius))
+            .find(|&(rx, ry)| rx > max.0 || ry > max.1);
+        if let Some(extent) = too_large {
+            return Err(BuildError::ExtentTooLarge { extent, max }.into());
+        }
+        
+        let previous = std::mem::replace(&mut self.kernel_radius, radius);
+        self.init_kernel().inspect_err(|_| self.kernel_radius = previous)
+    }
+    
+    /// Time step applied on every update.
+    pub fn dt(&self) -> Real {
+        self.dt
+    }
+    
+    /// Change the time step; takes effect on the next update.
+    ///
+    /// Non-finite values are refused, and so are negative ones unless the
+    /// simulation was built with
+    /// [`allow_negative_dt`](crate::SimpleLifeBuilder::allow_negative_dt).
+    pub fn set_dt(&mut self, dt: Real) -> Result<(), SimError> {
+        if !dt.is_finite() {
+            return Err(SimError::InvalidParameter(format!("dt must be a finite number, got {}", dt)));
+        }
+        if dt < 0.0 && !self.allow_negative_dt {
+            return Err(SimError::InvalidParameter(format!("negative dt {} needs allow_negative_dt", dt)));
+        }
+        self.dt = dt;
+        Ok(())
+    }
+    
+    /// Rebuild the kernel table from `kernel_radius` and the kernel function,
+    /// scaled according to the [`Normalization`].
+    ///
+    /// Extra kernels are normalized on their own and added in with their
+    /// weights, which are first scaled to sum to 1 if the builder asked for
+    /// it. Convolution is linear, so the single combined table yields the
+    /// weighted sum of the individual potentials at the cost of one kernel.
+    ///
+    /// Fails, keeping the previous table, if a kernel sums or peaks at 0 or a
+    /// non-finite value and so can't be normalized.
+    pub fn init_kernel(&mut self) -> Result<(), SimError> {
+        self.try_init_kernel().map_err(SimError::InvalidParameter)
+    }
+    
+    // The builder reports the failure as a BuildError instead
+    pub(crate) fn try_init_kernel(&mut self) -> Result<(), String> {
+        let (mut table, mut extent) = self.kernel_table(self.kernel_fn.as_ref())?;
+        
+        if !self.extra_kernels.is_empty() {
+            let total = 1.0 + self.extra_kernels.iter().map(|(_, w)| w).sum::<Real>();
+            let scale = if self.normalize_kernel_weights && total != 0.0 { 1.0 / total } else { 1.0 };
+            let extras: Vec<_> = self.extra_kernels.iter()
+                .map(|(kernel, weight)| Ok((self.kernel_table(kernel.as_ref())?, weight * scale)))
+                .collect::<Result<_, String>>()?;
+            let combined_extent = extras.iter()
+                .fold(extent, |a, ((_, b), _)| (a.0.max(b.0), a.1.max(b.1)));
+            
+            let combined_width = 2 * combined_extent.0 + 1;
+            let mut combined = vec![0.0; combined_width * (2 * combined_extent.1 + 1)];
+            for ((part, (rx, ry)), weight) in std::iter::once(((table, extent), scale)).chain(extras) {
+                let part_width = 2 * rx + 1;
+                let (ox, oy) = (combined_extent.0 - rx, combined_extent.1 - ry);
+                for (i, k) in part.into_iter().enumerate() {
+                    combined[(oy + i / part_width) * combined_width + ox + i % part_width] += weight * k;
+                }
+            }
+            
+            table = combined;
+            extent = combined_extent;
+        }
+        
+        self.kernel = table;
+        self.kernel_extent = extent;
+        self.kernel_offsets = kernel_offsets(&self.kernel, extent);
+        self.separable = Separable::factor(&self.kernel, extent);
+        self.init_fft();
+        Ok(())
+    }
+    
+    // The FFT path caches the kernel's transform, so it has to follow the table
+    fn init_fft(&mut self) {
+        self.fft = match self.conv_method.resolve(self.kernel_extent, self.separable.is_some()) {
+            ConvMethod::Fft => Some(FftConvolver::new(self.width, self.height, &self.kernel, self.kernel_extent)),
+            _ => None,
+        };
+    }
+    
+    // Normalized table of one kernel at the current radius, with its extent,
+    // or why it can't be normalized
+    fn kernel_table(&self, kernel: &dyn Kernel) -> Result<(Vec<Real>, (usize, usize)), String> {
+        let (rx, ry) = kernel.extent(self.kernel_radius);
+        let (kernel_width, kernel_height) = (2 * rx + 1, 2 * ry + 1);
+        let mut table = vec![0.0; kernel_width * kernel_height];
+        let mut kernel_sum = 0.0;
+        
+        for y in 0..kernel_height {
+            for x in 0..kernel_width {
+                let dx = x as f32 - rx as f32;
+                let dy = y as f32 - ry as f32;
+                
+                let value = sample_kernel(kernel, dx, dy, self.kernel_radius as f32, self.kernel_supersampling);
+                table[y * kernel_width + x] = value as Real;
+                kernel_sum += value as Real;
+            }
+        }
+        
+        let scale = match self.normalization {
+            Normalization::SumToOne => kernel_sum,
+            Normalization::PeakToOne => table.iter().cloned().fold(0.0, Real::max),
+            Normalization::None => 1.0,
+        };
+        if scale == 0.0 || !scale.is_finite() {
+            let what = if self.normalization == Normalization::SumToOne { "sums" } else { "peaks" };
+            return Err(format!(
+                "the kernel {} to {} at radius {}, so it can't be normalized", what, scale, self.kernel_radius));
+        }
+        for k in &mut table {
+            *k /= scale;
+        }
+        
+        Ok((table, (rx, ry)))
+    }
+    
+    /// Whether the kernel table factors into a row and a column, letting the
+    /// direct convolution run as two one-dimensional passes.
+    pub fn is_separable(&self) -> bool {
+        self.separable.is_some()
+    }
+    
+    /// Number of kernels the potential is combined from, the main one included.
+    pub fn kernel_count(&self) -> usize {
+        1 + self.extra_kernels.len()
+    }
+    
+    /// How the kernel table is scaled.
+    pub fn normalization(&self) -> Normalization {
+        self.normalization
+    }
+    
+    /// Switch the kernel normalization and rebuild the table.
+    ///
+    /// Fails, keeping the previous normalization, if the kernel can't be
+    /// scaled that way.
+    pub fn set_normalization(&mut self, normalization: Normalization) -> Result<(), SimError> {
+        let previous = std::mem::replace(&mut self.normalization, normalization);
+        self.init_kernel().inspect_err(|_| self.normalization = previous)
+    }
+    
+    /// Subsamples per axis averaged into each kernel cell.
+    pub fn kernel_supersampling(&self) -> usize {
+        self.kernel_supersampling
+    }
+    
+    /// Average `n` x `n` samples of the kernel function over each table cell
+    /// instead of taking its value at the cell center, and rebuild the table.
+    ///
+    /// This smooths the jagged edge a round kernel gets on the square grid,
+    /// which otherwise shows up as slightly square growth over long runs.
+    /// 1 is point sampling, the original table.
+    pub fn set_kernel_supersampling(&mut self, n: usize) -> Result<(), SimError> {
+        if n == 0 {
+            return Err(SimError::InvalidParameter("kernel supersampling needs at least 1 sample per axis".to_string()));
+        }
+        let previous = std::mem::replace(&mut self.kernel_supersampling, n);
+        self.init_kernel().inspect_err(|_| self.kernel_supersampling = previous)
+    }
+    
+    /// Algorithm used to compute the potential.
+    pub fn conv_method(&self) -> ConvMethod {
+        self.conv_method
+    }
+    
+    /// Whether the potential currently goes through the FFT, which depends on
+    /// the method, the kernel size (for [`ConvMethod::Auto`]) and the boundary.
+    pub fn uses_fft(&self) -> bool {
+        self.fft.is_some() && self.boundary == Boundary::Toroidal
+    }
+    
+    /// Switch the convolution algorithm, precomputing whatever it needs.
+    pub fn set_conv_method(&mut self, method: ConvMethod) {
+        self.conv_method = method;
+        self.init_fft();
+    }
+    
+    /// Replace the main kernel with `shape` at the current radius and rebuild
+    /// the table; extra kernels stay as they are.
+    ///
+    /// The new shape replaces the whole kernel function, so an anisotropic
+    /// stretch set up by the builder is dropped.
+    pub fn set_kernel_shape(&mut self, shape: KernelShape) -> Result<(), SimError> {
+        shape.check().map_err(SimError::InvalidParameter)?;
+        shape.validate_radius(self.kernel_radius).map_err(SimError::InvalidParameter)?;
+        let previous = std::mem::replace(&mut self.kernel_fn, Arc::new(shape));
+        self.init_kernel().inspect_err(|_| self.kernel_fn = previous)
+    }
+    
+    /// Replace the kernel with one drawn in a grayscale image (PNG or PGM), pixel
+    /// brightness being the weight. The image must have odd dimensions so it can
+    /// be centered; the kernel radius becomes the larger half-size.
+    pub fn set_kernel_from_image(&mut self, path: &str) -> Result<(), SimError> {
+        let image = image::open(path).map_err(|e| SimError::image(path, e))?.to_luma32f();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let kernel = ImageKernel::new(width, height, image.into_raw())
+            .map_err(|e| SimError::InvalidParameter(format!("{}: {}", path, e)))?;
+        
+        // Same limit as the builder: the kernel must not wrap onto itself
+        if width / 2 > (self.width - 1) / 2 || height / 2 > (self.height - 1) / 2 {
+            return Err(SimError::InvalidParameter(format!(
+                "{}: a {}x{} kernel does not fit the {}x{} grid", path, width, height, self.width, self.height)));
+        }
+        
+        let radius = (width / 2).max(height / 2);
+        if let Some((rx, ry)) = self.extra_kernels.iter()
+            .map(|(extra, _)| extra.extent(radius))
+            .find(|&(rx, ry)| rx > (self.width - 1) / 2 || ry > (self.height - 1) / 2)
+        {
+            return Err(SimError::InvalidParameter(format!(
+                "{}: at radius {} another kernel reaches {}x{} cells, too far for the {}x{} grid",
+                path, radius, rx, ry, self.width, self.height)));
+        }
+        
+        let previous = (self.kernel_radius, self.kernel_fn.clone());
+        self.kernel_radius = radius;
+        self.kernel_fn = Arc::new(kernel);
+        self.init_kernel().inspect_err(|_| (self.kernel_radius, self.kernel_fn) = previous)
+    }
+    
+    /// How the convolution treats the edges of the grid.
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+    
+    /// Switch the edge handling; takes effect on the next potential.
+    pub fn set_boundary(&mut self, boundary: Boundary) {
+        self.boundary = boundary;
+    }
+    
+    /// How cells are kept within `0..=1` after each step.
+    pub fn clamp(&self) -> Clamp {
+        self.clamp
+    }
+    
+    /// Switch between the hard and the soft clamp; takes effect on the next step.
+    pub fn set_clamp(&mut self, clamp: Clamp) {
+        self.clamp = clamp;
+    }
+    
+    /// Map a potential `u` to the rate of change applied to a cell, using the
+    /// constant parameter for a parametric rule and an empty cell for a
+    /// state-dependent one.
+    pub fn growth_function(&self, u: Real) -> Real {
+        self.growth.value(u, self.parameter)
+    }
+    
+    /// Replace the growth curve; the next `update` already uses the new one.
+    pub fn set_growth_rule(&mut self, growth: impl Fn(Real) -> Real + Send + Sync + 'static) {
+        self.growth = GrowthRule::Function(Arc::new(growth));
+    }
+    
+    /// Replace the growth rule with a function, a sampled table or a parametric rule.
+    pub fn set_growth(&mut self, growth: GrowthRule) -> Result<(), SimError> {
+        growth.check().map_err(SimError::InvalidParameter)?;
+        self.growth = growth;
+        Ok(())
+    }
+    
+    /// Hand the growth rule over to `schedule` from the next update on; its
+    /// steps count the updates since the simulation was built.
+    pub fn set_growth_schedule(&mut self, schedule: GrowthSchedule) -> Result<(), SimError> {
+        schedule.check().map_err(SimError::InvalidParameter)?;
+        self.growth_schedule = Some(schedule);
+        Ok(())
+    }
+    
+    /// Stop following the schedule, keeping whatever rule it installed last.
+    pub fn clear_growth_schedule(&mut self) -> Option<GrowthSchedule> {
+        self.growth_schedule.take()
+    }
+    
+    /// Parameter a [`GrowthRule::Parametric`] rule sees wherever no map is set.
+    pub fn parameter(&self) -> Real {
+        self.parameter
+    }
+    
+    /// Use the same parameter for every cell and drop any parameter map.
+    pub fn set_parameter(&mut self, value: Real) {
+        self.parameter = value;
+        self.parameter_map = None;
+    }
+    
+    /// Give every cell its own parameter for a [`GrowthRule::Parametric`] rule,
+    /// row-major like the grid; other rules ignore it.
+    ///
+    /// With a map the growth pass reads one more value per cell, which is small
+    /// next to the convolution. Without a map, or with a non-parametric rule,
+    /// the update takes the same path as before.
+    pub fn set_parameter_map(&mut self, map: Vec<Real>) -> Result<(), SimError> {
+        if map.len() != self.grid.len() {
+            return Err(SimError::InvalidParameter(format!(
+                "parameter map has {} values, the {}x{} grid needs {}", map.len(), self.width, self.height, self.grid.len())));
+        }
+        if map.iter().any(|v| !v.is_finite()) {
+            return Err(SimError::InvalidParameter("parameter map values must be finite".to_string()));
+        }
+        
+        self.parameter_map = Some(map);
+        Ok(())
+    }
+    
+    /// The per-cell parameters, if a map is set.
+    pub fn parameter_map(&self) -> Option<&[Real]> {
+        self.parameter_map.as_deref()
+    }
+    
+    /// Switch to a [`GaussianGrowth`] bell centered on `mu` with width `sigma`,
+    /// e.g. to tune the parameters of a running simulation.
+    pub fn set_gaussian_growth(&mut self, mu: Real, sigma: Real) -> Result<(), SimError> {
+        let growth = GaussianGrowth::new(mu, sigma)?;
+        self.set_growth_rule(move |u| growth.value(u));
+        Ok(())
+    }
+    
+    /// Switch to the quadratic `a * u * (1 - u) - b`, e.g. to sweep the death
+    /// pressure `b` over a run; the default curve has `a = 1.8, b = 0.2`.
+    pub fn set_quadratic_growth(&mut self, a: Real, b: Real) -> Result<(), SimError> {
+        self.set_growth(GrowthRule::Quadratic { a, b })
+    }
+    
+    /// Coefficient `a` of a [`GrowthRule::Quadratic`] rule, `None` for any
+    /// other rule.
+    pub fn growth_a(&self) -> Option<Real> {
+        match self.growth {
+            GrowthRule::Quadratic { a, .. } => Some(a),
+            _ => None,
+        }
+    }
+    
+    /// Death pressure `b` of a [`GrowthRule::Quadratic`] rule, `None` for any
+    /// other rule.
+    pub fn growth_b(&self) -> Option<Real> {
+        match self.growth {
+            GrowthRule::Quadratic { b, .. } => Some(b),
+            _ => None,
+        }
+    }
+    
+    /// Change `a` of the quadratic growth, keeping `b`; any other rule is
+    /// replaced by the quadratic with the default `b = 0.2`.
+    pub fn set_growth_a(&mut self, a: Real) -> Result<(), SimError> {
+        let b = self.growth_b().unwrap_or(0.2);
+        self.set_quadratic_growth(a, b)
+    }
+    
+    /// Change the death pressure `b` of the quadratic growth, keeping `a`; any
+    /// other rule is replaced by the quadratic with the default `a = 1.8`.
+    pub fn set_growth_b(&mut self, b: Real) -> Result<(), SimError> {
+        let a = self.growth_a().unwrap_or(1.8);
+        self.set_quadratic_growth(a, b)
+    }
+    
+    /// Plot the growth rule over potentials `0..=1` as a `width` x `height`
+    /// grayscale image, `.pgm` or `.png` by extension.
+    ///
+    /// The curve is white on black with a gray line at zero growth. The vertical
+    /// range fits the curve and always includes zero.
+    pub fn plot_growth_curve(&self, path: &str, width: usize, height: usize) -> Result<(), SimError> {
+        if width < 2 || height < 2 {
+            return Err(SimError::InvalidParameter(format!("a {}x{} plot is too small", width, height)));
+        }
+        
+        let values: Vec<Real> = (0..width)
+            .map(|x| self.growth_function(x as Real / (width - 1) as Real))
+            .collect();
+        if values.iter().any(|v| !v.is_finite()) {
+            return Err(SimError::InvalidParameter("growth rule is not finite over 0..=1".to_string()));
+        }
+        
+        let top = values.iter().cloned().fold(0.0, Real::max);
+        let bottom = values.iter().cloned().fold(0.0, Real::min);
+        // Leave a little room above and below, and don't blow up a flat curve
+        let margin = ((top - bottom) * 0.05).max(1e-6);
+        let (top, bottom) = (top + margin, bottom - margin);
+        let row = |v: Real| (((top - v) / (top - bottom)) * (height - 1) as Real).round() as usize;
+        
+        let mut pixels = vec![0u8; width * height];
+        let axis = row(0.0);
+        pixels[axis * width..(axis + 1) * width].fill(96);
+        
+        // Join neighboring samples with a vertical run so steep parts stay connected
+        let mut previous = row(values[0]);
+        for (x, &v) in values.iter().enumerate() {
+            let current = row(v);
+            for y in previous.min(current)..=previous.max(current) {
+                pixels[y * width + x] = 255;
+            }
+            previous = current;
+        }
+        
+        save_gray(path, &pixels, width, height)
+    }
+    
+    /// Convolve the grid with the kernel, treating the edges as set by the [`Boundary`].
+    ///
+    /// With several kernels this is the weighted sum of their potentials, which
+    /// is what `update` feeds to the growth function.
+    ///
+    /// The FFT path only knows toroidal wrapping; with any other boundary the
+    /// direct convolution is used even when FFT was selected.
+    pub fn compute_potential(&self) -> Vec<Real> {
+        let mut potential = vec![0.0; self.grid.len()];
+        self.potential_into(&mut potential, &mut Scratch::default());
+        potential
+    }
+    
+    pub(crate) fn potential_into(&self, potential: &mut [Real], scratch: &mut Scratch) {
+        match &self.fft {
+            Some(fft) if self.boundary == Boundary::Toroidal => fft.convolve_into(&self.grid, potential, scratch),
+            _ => match &self.separable {
+                Some(separable) => separable.convolve_into(&self.grid, self.width, self.height, self.boundary, potential, scratch),
+                None => convolve_blocked(&self.grid, &self.kernel_offsets, self.kernel_extent, self.boundary, None, potential, scratch),
+            },
+        }
+    }
+    
+    // Compute the potential into the buffer kept across steps and lend it out;
+    // callers put it back into `self.potential` once they are done with it
+    fn take_potential(&mut self) -> Vec<Real> {
+        let mut potential = std::mem::take(&mut self.potential);
+        potential.resize(self.grid.len(), 0.0);
+        
+        let mut scratch = std::mem::take(&mut self.scratch);
+        if self.skips_dead_cells() {
+            self.active.update(&self.grid, self.kernel_extent, self.boundary);
+            convolve_blocked(&self.grid, &self.kernel_offsets, self.kernel_extent, self.boundary, Some(&self.active), &mut potential, &mut scratch);
+        } else {
+            self.potential_into(&mut potential, &mut scratch);
+        }
+        self.scratch = scratch;
+        potential
+    }
+    
+    // Whether this step's potential can be limited to the active tiles. Only the
+    // plain direct path knows how, and a dead cell under zero potential has to
+    // stay dead, or the skipped regions would be wrong
+    pub(crate) fn skips_dead_cells(&self) -> bool {
+        self.activity_tracking
+            && !self.uses_fft()
+            && self.separable.is_none()
+            && self.parameter_map.is_none()
+            && self.dt * self.growth.rate(0.0, 0.0, self.parameter) <= 0.0
+    }
+    
+    /// Whether [`update`](Self::update) only convolves near live cells; on by default.
+    pub fn activity_tracking(&self) -> bool {
+        self.activity_tracking
+    }
+    
+    /// Turn activity tracking on or off.
+    ///
+    /// With tracking on, every step first finds the 32x32 tiles holding a
+    /// non-zero cell, grows them by the kernel extent (wrapping around a
+    /// toroidal grid) and runs the direct convolution only there. Elsewhere
+    /// every kernel window covers only zeros, so the result is identical, just
+    /// cheaper on sparse grids. It applies to the direct, non-separable path
+    /// without a parameter map, and only while the growth rule keeps empty
+    /// cells at zero potential from growing; otherwise every cell is computed.
+    pub fn set_activity_tracking(&mut self, on: bool) {
+        self.activity_tracking = on;
+    }
+    
+    /// One unweighted potential field per kernel, the main kernel first, for
+    /// growth rules that treat the neighborhoods separately.
+    ///
+    /// Each kernel is convolved on its own, so this costs as many convolutions
+    /// as there are kernels.
+    pub fn compute_potentials(&self) -> Vec<Vec<Real>> {
+        if self.extra_kernels.is_empty() {
+            return vec![self.compute_potential()];
+        }
+        
+        std::iter::once(&self.kernel_fn)
+            .chain(self.extra_kernels.iter().map(|(kernel, _)| kernel))
+            .map(|kernel| {
+                let (table, extent) = self.kernel_table(kernel.as_ref())
+                    .expect("every kernel was normalized when the combined table was built");
+                let mut potential = vec![0.0; self.grid.len()];
+                match self.conv_method.resolve(extent, false) {
+                    ConvMethod::Fft if self.boundary == Boundary::Toroidal => FftConvolver::new(self.width, self.height, &table, extent)
+                        .convolve_into(&self.grid, &mut potential, &mut Scratch::default()),
+                    _ => convolve_blocked(&self.grid, &kernel_offsets(&table, extent), extent, self.boundary, None,
+                        &mut potential, &mut Scratch::default()),
+                }
+                potential
+            })
+            .collect()
+    }
+    
+    /// Root mean square of the cell-by-cell difference to `other`, e.g. to
+    /// watch a clone with one perturbed cell drift away.
+    ///
+    /// Panics if the grids have different sizes.
+    pub fn grid_l2_distance(&self, other: &SimpleLife) -> Real {
+        assert_eq!((self.width, self.height), (other.width, other.height), "grids have different sizes");
+        let sum: Real = self.grid.iter().zip(other.grid.iter()).map(|(a, b)| (a - b) * (a - b)).sum();
+        (sum / self.grid.len() as Real).sqrt()
+    }
+    
+    /// Set every cell to 0, leaving the RNG and step count alone, e.g. to
+    /// paint a pattern from scratch.
+    pub fn clear(&mut self) {
+        self.grid.fill(0.0);
+    }
+    
+    /// Clear the grid and seed a random disc of cells in the center.
+    ///
+    /// `radius` is a fraction of the smaller grid dimension and `density` the
+    /// probability that a cell in the disc starts out alive. A fresh seed is
+    /// drawn from the simulation's RNG and returned, so the pattern can be
+    /// replayed with [`random_init_seeded`](Self::random_init_seeded).
+    pub fn random_init(&mut self, radius: f32, density: f32) -> u64 {
+        let seed: u64 = self.rng.r#gen();
+        self.random_init_seeded(radius, density, seed);
+        seed
+    }
+    
+    /// Like [`random_init`](Self::random_init), but the same seed always yields
+    /// the identical grid.
+    pub fn random_init_seeded(&mut self, radius: f32, density: f32, seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        self.clear();
+        
+        let center_x = self.width / 2;
+        let center_y = self.height / 2;
+        let max_r = (self.width.min(self.height) as f32 * radius) as usize;
+        
+        // Create a more structured initial pattern
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let dx = x as isize - center_x as isize;
+                let dy = y as isize - center_y as isize;
+                let dist = ((dx*dx + dy*dy) as f32).sqrt();
+                
+                if dist < max_r as f32 {
+                    let r: f32 = rng.r#gen();
+                    
+                    // More cells start alive
+                    if r < density {
+                        // Higher initial values
+                        self.grid[(x, y)] = (r * 0.5 + 0.3) as Real;
+                    } else if r < density + 0.2 {
+                        // Create some medium-valued cells too
+                        self.grid[(x, y)] = (r * 0.3) as Real;
+                    }
+                }
+            }
+        }
+        
+        // Add some stable structures (like a simple "block" pattern)
+        if self.width > 50 && self.height > 50 {
+            // Add a few stable blocks in different locations
+            for i in 0..5 {
+                let bx = center_x as isize + (i as isize - 2) * 10;
+                let by = center_y as isize + (i as isize - 2) * 10;
+                
+                if bx > 2 && bx < self.width as isize - 2 && 
+                   by > 2 && by < self.height as isize - 2 {
+                    // Create a 2x2 block with high values
+                    for yi in 0..2 {
+                        for xi in 0..2 {
+                            self.grid[(bx as usize + xi, by as usize + yi)] = 0.9;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Advance the simulation by one time step.
+    ///
+    /// Returns `false` once every cell has died, so callers can react to extinction.
+    pub fn update(&mut self) -> bool {
+        let start = Instant::now();
+        self.follow_growth_schedule();
+        
+        // dt = 0 freezes the dynamics: skip the convolution and growth entirely,
+        // but still run the bookkeeping below so rendering and painting carry on
+        if self.dt != 0.0 {
+            let potential = self.take_potential();
+            self.apply_growth(&potential, self.dt);
+            self.potential = potential;
+        }
+        self.count_steps(1);
+        self.last_step_duration = start.elapsed();
+        
+        // Check if we have any active cells
+        self.is_alive()
+    }
+    
+    /// Advance by one `dt` in as many substeps as it takes to keep every cell
+    /// from changing by more than `max_delta` in a single substep.
+    ///
+    /// Whenever the largest `|step * growth|` would exceed `max_delta`, the step
+    /// is halved, down to `dt / 65536`, and the potential is recomputed after
+    /// each substep. Patterns that blow up at a large `dt` often survive this
+    /// way. Returns the number of substeps, 1 when the plain step was stable
+    /// enough and 0 when `dt` is 0, or an error unless `max_delta` is positive
+    /// and finite.
+    pub fn update_adaptive(&mut self, max_delta: Real) -> Result<usize, SimError> {
+        // Substeps are at least dt / MAX_SUBSTEPS long, so no step needs more
+        const MAX_SUBSTEPS: usize = 1 << 16;
+        if !(max_delta.is_finite() && max_delta > 0.0) {
+            return Err(SimError::InvalidParameter(format!("max_delta must be positive and finite, got {}", max_delta)));
+        }
+        let start = Instant::now();
+        self.follow_growth_schedule();
+        
+        let mut remaining = self.dt;
+        let mut substeps = 0;
+        while remaining != 0.0 {
+            let potential = self.take_potential();
+            let parameters = self.parameter_map.as_deref();
+            let max_rate = potential.iter().zip(self.grid.iter()).enumerate()
+                .map(|(i, (&p, &cell))| self.growth.rate(cell, p, parameters.map_or(self.parameter, |map| map[i])).abs())
+                .fold(0.0, Real::max);
+            
+            let mut step = self.dt;
+            while step.abs() * max_rate > max_delta && step.abs() > self.dt.abs() / MAX_SUBSTEPS as Real {
+                step /= 2.0;
+            }
+            // The substep that reaches the end takes exactly what is left, so
+            // rounding in `remaining` can't leave a sliver that never runs out
+            substeps += 1;
+            if step.abs() >= remaining.abs() || substeps == MAX_SUBSTEPS {
+                step = remaining;
+            }
+            
+            self.apply_growth(&potential, step);
+            self.potential = potential;
+            remaining -= step;
+        }
+        self.count_steps(1);
+        self.last_step_duration = start.elapsed();
+        
+        Ok(substeps)
+    }
+    
+    /// Completed calls to [`update`](Self::update) or
+    /// [`update_adaptive`](Self::update_adaptive) since the simulation was built.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+    
+    /// Simulated time since the simulation was built, the sum of `dt` over all
+    /// steps; `steps() * dt()` unless dt was changed along the way.
+    pub fn sim_time(&self) -> f64 {
+        self.sim_time
+    }
+    
+    /// Wall-clock time the last [`update`](Self::update) or
+    /// [`update_adaptive`](Self::update_adaptive) took, zero before the first.
+    pub fn last_step_duration(&self) -> Duration {
+        self.last_step_duration
+    }
+    
+    // Add `n` steps of the current dt to the step count and the simulated time;
+    // the conversion is a no-op with the f64 feature
+    #[allow(clippy::useless_conversion)]
+    pub(crate) fn count_steps(&mut self, n: u64) {
+        self.steps += n;
+        self.sim_time += n as f64 * f64::from(self.dt);
+    }
+    
+    pub(crate) fn follow_growth_schedule(&mut self) {
+        if let Some(schedule) = &mut self.growth_schedule && let Some(rule) = schedule.advance(self.steps) {
+            self.growth = rule;
+        }
+    }
+    
+    // Add dt * growth(potential) to every cell and clamp the result into 0..=1
+    pub(crate) fn apply_growth(&mut self, potential: &[Real], dt: Real) {
+        // Dispatch once per step so the table lookup gets inlined into the loop
+        let clamp = self.clamp;
+        match (&self.growth, &self.parameter_map) {
+            (GrowthRule::Function(f), _) => add_growth(&mut self.grid, potential, dt, clamp, |_, p| f(p)),
+            (&GrowthRule::Quadratic { a, b }, _) => add_growth(&mut self.grid, potential, dt, clamp, |_, p| a * p * (1.0 - p) - b),
+            (GrowthRule::Table(table), _) => add_growth(&mut self.grid, potential, dt, clamp, |_, p| table_value(table, p)),
+            (GrowthRule::Parametric(f), None) => {
+                let parameter = self.parameter;
+                add_growth(&mut self.grid, potential, dt, clamp, |_, p| f(p, parameter))
+            }
+            (GrowthRule::Parametric(f), Some(map)) => add_mapped_growth(&mut self.grid, potential, map, dt, clamp, f),
+            (GrowthRule::State(f), _) => add_growth(&mut self.grid, potential, dt, clamp, |cell, p| f(cell, p)),
+            (rule @ GrowthRule::SmoothLife { .. }, _) => add_growth(&mut self.grid, potential, dt, clamp, |cell, p| rule.rate(cell, p, 0.0)),
+        }
+    }
+    
+    /// Whether any cell is still above the activity threshold.
+    pub fn is_alive(&self) -> bool {
+        self.grid.iter().any(|&v| v > ACTIVE_THRESHOLD)
+    }
+    
+    /// Sum of all cell values.
+    pub fn total_mass(&self) -> Real {
+        self.grid.iter().sum()
+    }
+    
+    /// Number of cells above [`ACTIVE_THRESHOLD`].
+    pub fn active_cells(&self) -> usize {
+        self.grid.iter().filter(|&&v| v > ACTIVE_THRESHOLD).count()
+    }
+    
+    /// Average cell value, i.e. the mass per cell.
+    pub fn mean_value(&self) -> Real {
+        self.total_mass() / self.grid.len() as Real
+    }
+    
+    /// Mass-weighted center `(x, y)` of the grid, or `None` when there is no mass.
+    ///
+    /// The grid is a torus, so each axis is averaged as an angle (circular mean):
+    /// a blob straddling the edge gets a center near the edge instead of the middle.
+    pub fn center_of_mass(&self) -> Option<(Real, Real)> {
+        let tau = std::f64::consts::TAU as Real;
+        
+        let (mut x_sin, mut x_cos, mut y_sin, mut y_cos, mut mass) = (0.0, 0.0, 0.0, 0.0, 0.0);
+        for (x, y, v) in self.iter_cells() {
+            let ax = tau * x as Real / self.width as Real;
+            let ay = tau * y as Real / self.height as Real;
+            
+            x_sin += v * ax.sin();
+            x_cos += v * ax.cos();
+            y_sin += v * ay.sin();
+            y_cos += v * ay.cos();
+            mass += v;
+        }
+        
+        if mass <= 0.0 {
+            return None;
+        }
+        
+        // Map the mean angles back into 0..size
+        let x = x_sin.atan2(x_cos).rem_euclid(tau) / tau * self.width as Real;
+        let y = y_sin.atan2(y_cos).rem_euclid(tau) / tau * self.height as Real;
+        Some((x, y))
+    }
+    
+    /// Advance up to `n` steps, showing the state after each one to `observer`.
+    ///
+    /// The observer gets the simulation and the 1-based step number within this
+    /// call, and can return [`ControlFlow::Break`] to stop early. Returns the
+    /// number of steps that ran.
+    pub fn run_steps(
+        &mut self,
+        n: usize,
+        mut observer: impl FnMut(&SimpleLife, usize) -> ControlFlow<()>,
+    ) -> usize {
+        for step in 1..=n {
+            self.update();
+            
+            if observer(self, step).is_break() {
+                return step;
+            }
+        }
+        
+        n
+    }
+    
+    /// Run up to `max_steps` steps and report whether the pattern died out,
+    /// blew up to fill the grid, or survived. Steps are counted from 1.
+    pub fn classify_outcome(&mut self, max_steps: usize) -> Outcome {
+        let explosion_mass = EXPLOSION_FRACTION * self.grid.len() as Real;
+        let mut outcome = Outcome::Stable;
+        
+        self.run_steps(max_steps, |sim, step| {
+            if !sim.is_alive() {
+                outcome = Outcome::Extinct(step);
+            } else if sim.total_mass() > explosion_mass {
+                outcome = Outcome::Exploded(step);
+            } else {
+                return ControlFlow::Continue(());
+            }
+            ControlFlow::Break(())
+        });
+        
+        outcome
+    }
+    
+    /// Run up to `max_steps` steps looking for a state that repeats within
+    /// `max_period` steps, and return the period of the first repeat found.
+    ///
+    /// States are compared after rounding every cell to a multiple of
+    /// `quantum`, so float noise doesn't hide a repeat: `1.0 / 255.0` matches
+    /// the precision of saved images, larger values accept looser matches.
+    /// A pattern that settles down completely, or dies out, has period 1.
+    pub fn detect_period(&mut self, max_period: usize, max_steps: usize, quantum: Real) -> Option<usize> {
+        if max_period == 0 {
+            return None;
+        }
+        
+        let quantum = quantum.max(Real::EPSILON);
+        let fingerprint = |grid: &[Real]| {
+            let mut hasher = DefaultHasher::new();
+            for &v in grid {
+                ((v / quantum).round() as u64).hash(&mut hasher);
+            }
+            hasher.finish()
+        };
+        
+        // Fingerprints of the last `max_period` states, oldest first
+        let mut history = VecDeque::with_capacity(max_period + 1);
+        history.push_back(fingerprint(&self.grid));
+        
+        for _ in 0..max_steps {
+            self.update();
+            let current = fingerprint(&self.grid);
+            if let Some(age) = history.iter().rev().position(|&h| h == current) {
+                return Some(age + 1);
+            }
+            
+            history.push_back(current);
+            if history.len() > max_period {
+                history.pop_front();
+            }
+        }
+        
+        None
+    }
+    
+    /// Advance the simulation by up to `n` steps.
+    ///
+    /// Stops early once all cells have died and returns the number of steps that
+    /// actually ran, which is `n` when the pattern stays alive.
+    pub fn step_n(&mut self, n: usize) -> usize {
+        for step in 1..=n {
+            if !self.update() {
+                return step;
+            }
+        }
+        
+        n
+    }
+
+    /// Convert grid values to a blue-scale `0xRRGGBB` buffer for display.
+    pub fn create_buffer(&self) -> Vec<u32> {
+        self.create_buffer_with(Palette::Blue)
+    }
+    
+    /// Like [`create_buffer`](Self::create_buffer), colored with any [`Palette`].
+    pub fn create_buffer_with(&self, palette: Palette) -> Vec<u32> {
+        let mut buffer = vec![0; self.grid.len()];
+        self.create_buffer_into(palette, &mut buffer);
+        buffer
+    }
+    
+    /// Like [`create_buffer_with`](Self::create_buffer_with), but into a buffer of
+    /// `width * height` pixels the caller keeps from frame to frame.
+    ///
+    /// Panics if `out` has a different length.
+    pub fn create_buffer_into(&self, palette: Palette, out: &mut [u32]) {
+        assert_eq!(out.len(), self.grid.len(), "buffer does not hold {}x{} pixels", self.width, self.height);
+        for (pixel, &value) in out.iter_mut().zip(self.grid.iter()) {
+            *pixel = palette.color(value);
+        }
+    }
+    
+    /// Render the `w` x `h` cells starting at `(x0, y0)`, each as a `scale` x
+    /// `scale` block of pixels, e.g. to zoom into a creature on a large grid.
+    ///
+    /// The buffer is `w * scale` pixels wide and `h * scale` high, for the
+    /// region as [`clamp_region`](Self::clamp_region) fits it into the grid.
+    pub fn create_buffer_region(&self, x0: usize, y0: usize, w: usize, h: usize, scale: usize) -> Vec<u32> {
+        self.create_buffer_region_with(Palette::Blue, x0, y0, w, h, scale)
+    }
+    
+    /// Like [`create_buffer_region`](Self::create_buffer_region), colored with any [`Palette`].
+    pub fn create_buffer_region_with(&self, palette: Palette, x0: usize, y0: usize, w: usize, h: usize, scale: usize) -> Vec<u32> {
+        let (x0, y0, w, h) = self.clamp_region(x0, y0, w, h);
+        let row_len = w * scale;
+        let mut buffer = Vec::with_capacity(row_len * h * scale);
+        for y in y0..y0 + h {
+            let start = buffer.len();
+            for &value in &self.grid.row(y)[x0..x0 + w] {
+                buffer.extend(std::iter::repeat_n(palette.color(value), scale));
+            }
+            // The other rows of pixels for this row of cells are copies of the first
+            for _ in 1..scale {
+                buffer.extend_from_within(start..start + row_len);
+            }
+        }
+        buffer
+    }
+    
+    /// The region `(x0, y0, w, h)` moved back inside the grid where it reaches
+    /// past the edges, keeping its size unless it is larger than the grid.
+    pub fn clamp_region(&self, x0: usize, y0: usize, w: usize, h: usize) -> (usize, usize, usize, usize) {
+        let (w, h) = (w.min(self.width), h.min(self.height));
+        (x0.min(self.width - w), y0.min(self.height - h), w, h)
+    }
+    
+    /// Draw the grid with `renderer` into `out`, which has to hold
+    /// `width * height * renderer.bytes_per_pixel()` bytes.
+    pub fn render_into(&self, renderer: &(impl Renderer + ?Sized), out: &mut [u8]) {
+        renderer.render(&self.grid, self.width, self.height, out);
+    }
+    
+    /// Draw the grid with `renderer` into a new byte buffer.
+    pub fn render(&self, renderer: &(impl Renderer + ?Sized)) -> Vec<u8> {
+        let mut out = vec![0; self.grid.len() * renderer.bytes_per_pixel()];
+        self.render_into(renderer, &mut out);
+        out
+    }
+
+    /// Per-pixel variance over a `(2r+1)x(2r+1)` neighborhood, hot = turbulent, cold = smooth.
+    pub fn local_variance(&self, window_radius: usize) -> Vec<Real> {
+        let squares: Vec<Real> = self.grid.iter().map(|v| v * v).collect();
+        let mean = box_filter(&self.grid, self.width, self.height, window_radius);
+        let mean_sq = box_filter(&squares, self.width, self.height, window_radius);
+        
+        mean.iter()
+            .zip(&mean_sq)
+            .map(|(m, m2)| (m2 - m * m).max(0.0))
+            .collect()
+    }
+    
+    /// Grid-wide average of [`local_variance`](Self::local_variance).
+    pub fn mean_local_variance(&self, window_radius: usize) -> Real {
+        let variance = self.local_variance(window_radius);
+        variance.iter().sum::<Real>() / variance.len() as Real
+    }
+    
+    /// Render the local variance through a black-red-yellow-white sequential colormap.
+    pub fn create_variance_buffer(&self, window_radius: usize) -> Vec<u32> {
+        self.local_variance(window_radius)
+            .iter()
+            .map(|&v| {
+                // Values in 0..1 have a variance of at most 0.25, so the std dev is at most 0.5
+                let t = (v.sqrt() * 2.0).clamp(0.0, 1.0);
+                let red = ((t * 3.0).min(1.0) * 255.0) as u32;
+                let green = ((t * 3.0 - 1.0).clamp(0.0, 1.0) * 255.0) as u32;
+                let blue = ((t * 3.0 - 2.0).clamp(0.0, 1.0) * 255.0) as u32;
+                
+                (red << 16) | (green << 8) | blue
+            })
+            .collect()
+    }
+
+    /// Breakdown of the buffers this simulation holds.
+    ///
+    /// The FFT spectra and the active tiles are optional: they only make
+    /// stepping faster, and [`enforce_memory_budget`](Self::enforce_memory_budget)
+    /// frees them, in that order, to get within a budget. The scratch buffers
+    /// grow on the first step, so the report is most accurate after one.
+    pub fn memory_report(&self) -> MemoryBudget {
+        let real = std::mem::size_of::<Real>();
+        let mut budget = MemoryBudget::default();
+        
+        budget.register("grid", self.grid.capacity() * real);
+        budget.register("kernel", self.kernel.capacity() * real);
+        budget.register("kernel offsets", self.kernel_offsets.capacity() * std::mem::size_of::<(isize, isize, Real)>());
+        if let Some(separable) = &self.separable {
+            budget.register("separable factors", separable.memory_bytes());
+        }
+        if !self.extra_kernels.is_empty() {
+            budget.register("extra kernels", self.extra_kernels.capacity() * std::mem::size_of::<(KernelFn, Real)>());
+        }
+        budget.register("potential", self.potential.capacity() * real);
+        budget.register("convolution scratch", self.scratch.memory_bytes());
+        if let GrowthRule::Table(table) = &self.growth {
+            budget.register("growth table", table.capacity() * real);
+        }
+        if let Some(map) = &self.parameter_map {
+            budget.register("parameter map", map.capacity() * real);
+        }
+        if let Some(fft) = &self.fft {
+            budget.register_optional(FFT_SPECTRA, fft.memory_bytes() + self.scratch.fft_memory_bytes(), 0);
+        }
+        if self.activity_tracking {
+            budget.register_optional(ACTIVE_TILES, self.active.memory_bytes(), 1);
+        }
+        
+        budget
+    }
+    
+    /// Free optional buffers in [`MemoryBudget::shrink_plan`] order until the
+    /// [`memory_report`](Self::memory_report) fits in `limit` bytes, returning
+    /// what was freed so the caller can log it.
+    ///
+    /// Freeing the FFT spectra switches to [`ConvMethod::Direct`] and freeing
+    /// the active tiles turns off [activity tracking](Self::set_activity_tracking);
+    /// the results stay the same up to rounding, only slower. If freeing
+    /// everything optional is not enough, the simulation stays over the limit.
+    pub fn enforce_memory_budget(&mut self, limit: usize) -> Vec<MemoryEntry> {
+        let freed = self.memory_report().shrink_plan(limit);
+        for entry in &freed {
+            match entry.name {
+                FFT_SPECTRA => {
+                    self.set_conv_method(ConvMethod::Direct);
+                    self.scratch.release_fft();
+                }
+                ACTIVE_TILES => {
+                    self.set_activity_tracking(false);
+                    self.active = ActiveTiles::default();
+                }
+                _ => {}
+            }
+        }
+        freed
+    }
+
+    /// Write the grid as an RGB8 PNG using the same coloring as [`create_buffer`](Self::create_buffer),
+    /// so saved frames match what the window shows.
+    pub fn save_png(&self, filename: &str) -> Result<(), SimError> {
+        let rgb = self.render(&PaletteRenderer::new(Palette::Blue, PixelFormat::Rgb8));
+        
+        image::save_buffer_with_format(
+            filename,
+            &rgb,
+            self.width as u32,
+            self.height as u32,
+            image::ExtendedColorType::Rgb8,
+            image::ImageFormat::Png,
+        ).map_err(|e| SimError::image(filename, e))
+    }
+    
+    /// Write the grid as a binary grayscale PGM (P5) image.
+    pub fn save_image(&self, filename: &str) -> Result<(), SimError> {
+        let non_zero_pixels = self.write_pgm(filename).map_err(|e| SimError::io(filename, e))?;
+        
+        println!("Saved image with {} non-zero pixels out of {}", 
+                non_zero_pixels, self.width * self.height);
+        
+        Ok(())
+    }
+    
+    /// Replace the grid with a grayscale image (PNG or PGM), mapping pixel
+    /// intensity 0..255 to cell values 0.0..1.0.
+    ///
+    /// The image is centered on the grid without scaling: a larger image is
+    /// cropped and a smaller one is surrounded by empty cells.
+    pub fn load_from_image(&mut self, path: &str) -> Result<(), SimError> {
+        let image = image::open(path).map_err(|e| SimError::image(path, e))?.to_luma8();
+        let (image_width, image_height) = (image.width() as isize, image.height() as isize);
+        let offset_x = (image_width - self.width as isize) / 2;
+        let offset_y = (image_height - self.height as isize) / 2;
+        
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (sx, sy) = (x as isize + offset_x, y as isize + offset_y);
+                let inside = (0..image_width).contains(&sx) && (0..image_height).contains(&sy);
+                self.grid[(x, y)] = if inside {
+                    image.get_pixel(sx as u32, sy as u32).0[0] as Real / 255.0
+                } else {
+                    0.0
+                };
+            }
+        }
+        
+        Ok(())
+    }
+    
+    // Returns the number of non-zero pixels written
+    fn write_pgm(&self, filename: &str) -> std::io::Result<usize> {
+        let mut file = File::create(filename)?;
+        
+        // Write PGM header with proper line endings
+        writeln!(file, "P5")?;
+        writeln!(file, "{} {}", self.width, self.height)?;
+        writeln!(file, "255")?;
+        
+        // Count non-zero pixels for debugging
+        let mut non_zero_pixels = 0;
+        
+        // Write pixel data
+        for value in &self.grid {
+            let pixel = (*value * 255.0) as u8;
+            file.write_all(&[pixel])?;
+            
+            if pixel > 0 {
+                non_zero_pixels += 1;
+            }
+        }
+        
+        Ok(non_zero_pixels)
+    }
+}
+
+// Names of the optional buffers in the memory report
+const FFT_SPECTRA: &str = "fft spectra";
+const ACTIVE_TILES: &str = "active tiles";
+
+/// Bookkeeping of how many bytes each subsystem holds.
+///
+/// This is plain accounting filled in by the owners of the buffers, not an
+/// allocator hook. Displaying it prints the breakdown and the total.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBudget {
+    entries: Vec<MemoryEntry>,
+}
+
+/// One line of a [`MemoryBudget`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryEntry {
+    pub name: &'static str,
+    pub bytes: usize,
+    /// For buffers their owner can do without, the order they are freed in to
+    /// get within a budget, lowest first; `None` for the rest.
+    pub shrink_priority: Option<u32>,
+}
+
+impl MemoryBudget {
+    /// Record that `name` holds `bytes` bytes it can't do without.
+    pub fn register(&mut self, name: &'static str, bytes: usize) {
+        self.entries.push(MemoryEntry { name, bytes, shrink_priority: None });
+    }
+    
+    /// Record that `name` holds `bytes` bytes that can be freed, lower
+    /// priorities first, when over budget.
+    pub fn register_optional(&mut self, name: &'static str, bytes: usize, priority: u32) {
+        self.entries.push(MemoryEntry { name, bytes, shrink_priority: Some(priority) });
+    }
+    
+    /// Everything registered, in order.
+    pub fn entries(&self) -> &[MemoryEntry] {
+        &self.entries
+    }
+    
+    /// Sum of everything registered so far.
+    pub fn total(&self) -> usize {
+        self.entries.iter().map(|entry| entry.bytes).sum()
+    }
+    
+    /// The optional entries to free to get the total within `limit` bytes, in
+    /// the order to free them: by priority, and in registration order within
+    /// one. Empty if the total already fits; every optional entry if even
+    /// that is not enough.
+    pub fn shrink_plan(&self, limit: usize) -> Vec<MemoryEntry> {
+        let mut optional: Vec<MemoryEntry> = self.entries.iter()
+            .filter(|entry| entry.shrink_priority.is_some())
+            .copied()
+            .collect();
+        optional.sort_by_key(|entry| entry.shrink_priority);
+        
+        let mut total = self.total();
+        optional.into_iter()
+            .take_while(|entry| {
+                let over = total > limit;
+                total -= entry.bytes;
+                over
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for MemoryBudget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Memory usage:")?;
+        for entry in &self.entries {
+            let optional = if entry.shrink_priority.is_some() { " (optional)" } else { "" };
+            writeln!(f, "  {:<28} {:>10.2} KiB{}", entry.name, entry.bytes as f64 / 1024.0, optional)?;
+        }
+        writeln!(f, "  {:<28} {:>10.2} KiB", "total", self.total() as f64 / 1024.0)
+    }
+}
+
+// Like `add_growth`, with each cell's parameter taken from the map
+fn add_mapped_growth(grid: &mut [Real], potential: &[Real], map: &[Real], dt: Real, clamp: Clamp, growth: &ParametricGrowthFn) {
+    let apply = |(cell, (&p, &m)): (&mut Real, (&Real, &Real))| {
+        *cell = clamp.apply(*cell, dt * growth(p, m));
+    };
+    
+    #[cfg(feature = "parallel")]
+    grid.par_iter_mut().zip(potential.par_iter().zip(map.par_iter())).for_each(apply);
+    
+    #[cfg(not(feature = "parallel"))]
+    grid.iter_mut().zip(potential.iter().zip(map.iter())).for_each(apply);
+}
+
+// Write 8-bit grayscale pixels as PGM or PNG, following the extension
+fn save_gray(path: &str, pixels: &[u8], width: usize, height: usize) -> Result<(), SimError> {
+    let (w, h) = (width as u32, height as u32);
+    
+    // The image crate writes PAM for any PNM extension unless told otherwise
+    let is_pgm = std::path::Path::new(path).extension().is_some_and(|e| e.eq_ignore_ascii_case("pgm"));
+    let result = if is_pgm {
+        use image::ImageEncoder;
+        use image::codecs::pnm::{PnmEncoder, PnmSubtype, SampleEncoding};
+        
+        let file = File::create(path).map_err(|e| SimError::io(path, e))?;
+        PnmEncoder::new(std::io::BufWriter::new(file))
+            .with_subtype(PnmSubtype::Graymap(SampleEncoding::Binary))
+            .write_image(pixels, w, h, image::ExtendedColorType::L8)
+    } else {
+        image::save_buffer(path, pixels, w, h, image::ExtendedColorType::L8)
+    };
+    
+    result.map_err(|e| SimError::image(path, e))
+}
+
+// `growth` gets the current cell value and the potential
+fn add_growth(grid: &mut [Real], potential: &[Real], dt: Real, clamp: Clamp, growth: impl Fn(Real, Real) -> Real + Sync) {
+    let apply = |(cell, &p): (&mut Real, &Real)| {
+        *cell = clamp.apply(*cell, dt * growth(*cell, p));
+    };
+    
+    #[cfg(feature = "parallel")]
+    grid.par_iter_mut().zip(potential.par_iter()).for_each(apply);
+    
+    #[cfg(not(feature = "parallel"))]
+    grid.iter_mut().zip(potential.iter()).for_each(apply);
+}
+
+// Weight of the table cell centered on (dx, dy), averaged over n x n evenly
+// spaced points inside the cell
+fn sample_kernel(kernel: &dyn Kernel, dx: f32, dy: f32, radius: f32, n: usize) -> f32 {
+    if n == 1 {
+        return kernel.weight(dx, dy, radius);
+    }
+    
+    let offset = |i: usize| (i as f32 + 0.5) / n as f32 - 0.5;
+    let mut sum = 0.0;
+    for sy in 0..n {
+        for sx in 0..n {
+            sum += kernel.weight(dx + offset(sx), dy + offset(sy), radius);
+        }
+    }
+    sum / (n * n) as f32
+}
+
+// Non-zero entries of a kernel table as (dx, dy, weight); the corners outside
+// the falloff are skipped entirely
+fn kernel_offsets(table: &[Real], (rx, ry): (usize, usize)) -> Vec<(isize, isize, Real)> {
+    let kernel_width = 2 * rx + 1;
+    table.iter()
+        .enumerate()
+        .filter(|&(_, &k)| k != 0.0)
+        .map(|(i, &k)| ((i % kernel_width) as isize - rx as isize, (i / kernel_width) as isize - ry as isize, k))
+        .collect()
+}
+
+// Toroidal box filter (mean over a (2r+1)x(2r+1) window) using running sums,
+// so the cost is O(N) no matter how large the window is
+fn box_filter(data: &[Real], width: usize, height: usize, radius: usize) -> Vec<Real> {
+    let window = 2 * radius + 1;
+    let mut rows = vec![0.0; width * height];
+    let mut out = vec![0.0; width * height];
+    
+    // Horizontal pass
+    for y in 0..height {
+        let row = &data[y * width..(y + 1) * width];
+        let mut sum: Real = (0..window).map(|i| row[(i + width * window - radius) % width]).sum();
+        
+        for x in 0..width {
+            rows[y * width + x] = sum;
+            sum += row[(x + radius + 1) % width] - row[(x + width * window - radius) % width];
+        }
+    }
+    
+    // Vertical pass
+    for x in 0..width {
+        let mut sum: Real = (0..window).map(|i| rows[((i + height * window - radius) % height) * width + x]).sum();
+        
+        for y in 0..height {
+            out[y * width + x] = sum / (window * window) as Real;
+            sum += rows[((y + radius + 1) % height) * width + x]
+                - rows[((y + height * window - radius) % height) * width + x];
+        }
+    }
+    
+    out
+}
+
@@ -0,0 +1,113 @@
+//! Snapshots of a running simulation that can be resumed later.
+//!
+//! The format is a small versioned little-endian binary:
+//!
+//! ```text
+//! magic    b"SLSTATE\0"
+//! version  u32
+//! width    u64
+//! height   u64
+//! radius   u64
+//! dt       f64
+//! grid     width * height f64 values, row-major
+//! ```
+//!
+//! Values are stored as f64 whatever [`Real`] is, so f32 and f64 builds can
+//! read each other's files. The kernel is re-derived from the radius, so
+//! custom kernels and growth curves have to be set again after loading.
+
+use std::fs;
+use std::io;
+
+use crate::{Real, SimError, SimpleLife};
+
+const MAGIC: &[u8; 8] = b"SLSTATE\0";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 8 + 4 + 3 * 8 + 8;
+
+impl SimpleLife {
+    /// Write the grid and the parameters needed to rebuild the simulation.
+    // The casts are no-ops with the f64 feature
+    #[allow(clippy::unnecessary_cast)]
+    pub fn save_state(&self, path: &str) -> Result<(), SimError> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.grid.len() * 8);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.width as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.height as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.kernel_radius as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.dt as f64).to_le_bytes());
+        for &v in &self.grid {
+            bytes.extend_from_slice(&(v as f64).to_le_bytes());
+        }
+
+        fs::write(path, bytes).map_err(|e| SimError::io(path, e))
+    }
+
+    /// Rebuild a simulation from a file written by [`save_state`](Self::save_state).
+    ///
+    /// The result has the dimensions stored in the file, whatever simulations
+    /// are running at the time. Truncated or foreign files are reported as
+    /// invalid data instead of panicking.
+    pub fn load_state(path: &str) -> Result<SimpleLife, SimError> {
+        let bytes = fs::read(path).map_err(|e| SimError::io(path, e))?;
+        let invalid = |msg: String| SimError::io(path, io::Error::new(io::ErrorKind::InvalidData, msg));
+
+        let mut reader = Reader { bytes: &bytes, pos: 0 };
+        if reader.take(8) != Some(MAGIC.as_slice()) {
+            return Err(invalid("not a SimpleLife state file".to_string()));
+        }
+        let header = (|| Some((reader.u32()?, reader.u64()?, reader.u64()?, reader.u64()?, reader.f64()?)))();
+        let Some((version, width, height, radius, dt)) = header else {
+            return Err(invalid("state file header is truncated".to_string()));
+        };
+        if version != VERSION {
+            return Err(invalid(format!("unsupported state file version {}", version)));
+        }
+
+        // Check the size against the file before allocating anything
+        let expected = width.checked_mul(height).and_then(|cells| cells.checked_mul(8));
+        if expected != Some((bytes.len() - HEADER_LEN) as u64) {
+            return Err(invalid(format!("grid data does not match a {}x{} grid", width, height)));
+        }
+
+        let mut sim = SimpleLife::builder()
+            .size(width as usize, height as usize)
+            .kernel_radius(radius as usize)
+            .dt(dt as Real)
+            // A negative dt could only have been saved if it was allowed
+            .allow_negative_dt(dt < 0.0)
+            .build()?;
+        for cell in &mut sim.grid {
+            *cell = reader.f64().unwrap_or_default() as Real;
+        }
+
+        Ok(sim)
+    }
+}
+
+// Sequential little-endian reader over a byte slice
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let slice = self.bytes.get(self.pos..self.pos + n)?;
+        self.pos += n;
+        Some(slice)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn f64(&mut self) -> Option<f64> {
+        Some(f64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+}
@@ -0,0 +1,33 @@
+use std::sync::{Mutex, PoisonError};
+
+// Hands the latest value from one thread to another without allocating and
+// without either side waiting for more than a swap. The writer and the reader
+// each own a value and trade it for the one in the middle.
+pub struct TripleBuffer<T> {
+    // The middle value and whether it was published since the reader last took it
+    middle: Mutex<(T, bool)>,
+}
+
+impl<T> TripleBuffer<T> {
+    pub fn new(middle: T) -> Self {
+        TripleBuffer { middle: Mutex::new((middle, false)) }
+    }
+
+    // Make `back` the latest value; `back` comes back holding an older one to refill
+    pub fn publish(&self, back: &mut T) {
+        let mut middle = self.middle.lock().unwrap_or_else(PoisonError::into_inner);
+        std::mem::swap(&mut middle.0, back);
+        middle.1 = true;
+    }
+
+    // Swap the latest value into `front` if one was published since the last call
+    pub fn take_latest(&self, front: &mut T) -> bool {
+        let mut middle = self.middle.lock().unwrap_or_else(PoisonError::into_inner);
+        if !middle.1 {
+            return false;
+        }
+        std::mem::swap(&mut middle.0, front);
+        middle.1 = false;
+        true
+    }
+}
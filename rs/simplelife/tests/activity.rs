@@ -0,0 +1,59 @@
+//! Updates limited to the active tiles against updating every cell.
+
+use simplelife::{Boundary, ConvMethod, GrowthPreset, Real, SimpleLife};
+
+// A grid that is not a whole number of tiles, with a blob across the corner so
+// that on the torus it wraps around both axes
+fn blob(radius: usize, boundary: Boundary, growth: GrowthPreset, dt: Real, tracking: bool) -> SimpleLife {
+    let mut sim = SimpleLife::builder()
+        .size(100, 70)
+        .kernel_radius(radius)
+        .conv_method(ConvMethod::Direct)
+        .boundary(boundary)
+        .growth_preset(growth)
+        .dt(dt)
+        .allow_negative_dt(true)
+        .build()
+        .unwrap();
+    sim.set_activity_tracking(tracking);
+
+    let mut state: u32 = 99;
+    for dy in -9..=9_isize {
+        for dx in -9..=9_isize {
+            if dx * dx + dy * dy <= 81 {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let (x, y) = ((dx + 3).rem_euclid(100) as usize, (dy + 2).rem_euclid(70) as usize);
+                sim.set(x, y, 0.3 + 0.7 * (state >> 8) as Real / (1 << 24) as Real).unwrap();
+            }
+        }
+    }
+    sim
+}
+
+fn assert_same_runs(radius: usize, boundary: Boundary, growth: GrowthPreset, dt: Real) {
+    let mut tracked = blob(radius, boundary, growth, dt, true);
+    let mut full = blob(radius, boundary, growth, dt, false);
+
+    for step in 1..=10 {
+        tracked.update();
+        full.update();
+        assert!(tracked.grid().iter().map(|v| v.to_bits()).eq(full.grid().iter().map(|v| v.to_bits())),
+            "{:?}, radius {}, {}, dt {}: grids differ after step {}", boundary, radius, growth, dt, step);
+    }
+}
+
+#[test]
+fn tracked_updates_match_full_updates() {
+    for boundary in [Boundary::Toroidal, Boundary::Fixed, Boundary::Reflective] {
+        for radius in [3, 12] {
+            assert_same_runs(radius, boundary, GrowthPreset::QuadraticWide, 0.1);
+        }
+    }
+}
+
+#[test]
+fn growth_out_of_nothing_falls_back_to_every_cell() {
+    // Both make empty cells under zero potential grow, so nothing may be skipped
+    assert_same_runs(5, Boundary::Toroidal, "quadratic:1.8,-0.05".parse().unwrap(), 0.1);
+    assert_same_runs(5, Boundary::Toroidal, GrowthPreset::QuadraticWide, -0.05);
+}
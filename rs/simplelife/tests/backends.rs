@@ -0,0 +1,23 @@
+//! Every convolution backend against the brute-force reference step.
+
+use simplelife::backends::{Backend, BackendMatrix, Comparison, MatrixCase};
+
+#[test]
+fn backend_matrix() {
+    let matrix = BackendMatrix::run(&MatrixCase::suite());
+    println!("{}", matrix);
+    let failures: Vec<String> = matrix.failures().map(|entry| format!("{} on {}", entry.backend, entry.case)).collect();
+    assert!(failures.is_empty(), "{} failed:\n{}", failures.len(), failures.join("\n"));
+
+    // Every backend has an entry for every case, and only the FFT off the torus
+    // and a missing GPU may be skipped
+    for backend in Backend::ALL {
+        let entries: Vec<_> = matrix.entries.iter().filter(|entry| entry.backend == backend).collect();
+        assert_eq!(entries.len(), MatrixCase::suite().len());
+        for entry in entries {
+            if let Comparison::Skipped(reason) = &entry.comparison {
+                assert!(matches!(backend, Backend::Fft | Backend::Gpu), "{} skipped on {}: {}", backend, entry.case, reason);
+            }
+        }
+    }
+}
@@ -0,0 +1,43 @@
+//! Both binaries step the same library simulation: the headless runner's
+//! output has to match what the viewer's plain `update()` loop computes.
+
+use std::process::Command;
+
+use simplelife::{ConvMethod, Real, SimpleLife};
+
+const STEPS: usize = 30;
+
+#[test]
+fn headless_runner_matches_the_viewer_update_loop() {
+    let dir = std::env::temp_dir().join(format!("simplelife_binaries_{}", std::process::id()));
+    let output = Command::new(env!("CARGO_BIN_EXE_simplelife_static"))
+        .args(["--width", "64", "--height", "48", "--radius", "6", "--dt", "0.1", "--conv", "direct", "--seed", "21"])
+        .args(["--steps", &STEPS.to_string(), "--save-every", "1000", "--skip-self-test"])
+        .arg("--output-dir")
+        .arg(&dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let csv = std::fs::read_to_string(dir.join("simplelife_stats.csv")).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // Built the way the viewer builds it from the same flags, then stepped
+    // the way its simulation thread does
+    let mut sim = SimpleLife::builder()
+        .size(64, 48)
+        .kernel_radius(6)
+        .dt(0.1)
+        .conv_method(ConvMethod::Direct)
+        .build()
+        .unwrap();
+    sim.random_init_seeded(0.3, 0.3, 21);
+
+    let masses: Vec<Real> = csv.lines().skip(1).map(|line| line.split(',').nth(1).unwrap().parse().unwrap()).collect();
+    assert_eq!(masses.len(), STEPS + 1);
+    assert_eq!(masses[0], sim.total_mass());
+    for (step, &mass) in masses.iter().enumerate().skip(1) {
+        sim.update();
+        assert_eq!(mass, sim.total_mass(), "step {}", step);
+    }
+    assert!(sim.is_alive(), "the pattern died out, so there was nothing to compare");
+}
@@ -0,0 +1,60 @@
+//! Invalid builder combinations and the BuildError each one is refused with.
+
+use simplelife::{BuildError, GrowthPreset, KernelShape, Real, SimpleLife, SimpleLifeBuilder};
+
+fn valid() -> SimpleLifeBuilder {
+    SimpleLife::builder().size(32, 24).kernel_radius(5)
+}
+
+#[test]
+fn a_valid_combination_builds() {
+    let sim = valid().build().unwrap();
+    assert_eq!((sim.width(), sim.height()), (32, 24));
+}
+
+#[test]
+fn grid_and_radius_errors() {
+    assert_eq!(valid().size(0, 24).build().err(), Some(BuildError::ZeroSize { width: 0, height: 24 }));
+    assert_eq!(valid().size(32, 0).build().err(), Some(BuildError::ZeroSize { width: 32, height: 0 }));
+    assert_eq!(valid().kernel_radius(0).build().err(), Some(BuildError::ZeroRadius));
+    // The smaller dimension limits the radius
+    assert_eq!(valid().kernel_radius(12).build().err(), Some(BuildError::RadiusTooLarge { radius: 12, max: 11 }));
+    assert!(valid().kernel_radius(11).build().is_ok());
+    // Stretched along x, the table no longer fits even though the radius does
+    assert_eq!(valid().size(24, 64).kernel_radius(10).anisotropy(2.0, 0.0).build().err(),
+        Some(BuildError::ExtentTooLarge { extent: (20, 10), max: (11, 31) }));
+}
+
+#[test]
+fn kernel_errors() {
+    let invalid = |builder: SimpleLifeBuilder| matches!(builder.build(), Err(BuildError::InvalidKernel(_)));
+    assert!(invalid(valid().kernel_shape(KernelShape::Gaussian { mu: 0.5, sigma: 0.0 })));
+    assert!(invalid(valid().kernel_shape(KernelShape::Annulus { inner: 4.0, outer: 2.0, antialias: false })));
+    assert!(invalid(valid().kernel_supersampling(0)));
+    assert!(invalid(valid().add_kernel(KernelShape::Linear, Real::NAN)));
+    // Weights that cancel can't be scaled to sum to 1, but only need to if asked
+    assert!(invalid(valid().add_kernel(KernelShape::Linear, -1.0).normalize_kernel_weights(true)));
+    assert!(valid().add_kernel(KernelShape::Linear, -1.0).build().is_ok());
+}
+
+#[test]
+fn growth_and_dt_errors() {
+    let invalid = |preset| matches!(valid().growth_preset(preset).build(), Err(BuildError::InvalidGrowth(_)));
+    assert!(invalid(GrowthPreset::GaussianBell { mu: 0.15, sigma: 0.0 }));
+    assert!(invalid(GrowthPreset::StepBand { low: 0.4, high: 0.2 }));
+    assert!(invalid(GrowthPreset::Quadratic { a: Real::INFINITY, b: 0.2 }));
+    assert!(invalid(GrowthPreset::Interval { b1: 0.3, b2: 0.2, d1: 0.2, d2: 0.4, width: 0.0 }));
+
+    assert!(matches!(valid().dt(Real::NAN).build(), Err(BuildError::NonFiniteDt(dt)) if dt.is_nan()));
+    assert_eq!(valid().dt(Real::INFINITY).build().err(), Some(BuildError::NonFiniteDt(Real::INFINITY)));
+    assert_eq!(valid().dt(-0.1).build().err(), Some(BuildError::NegativeDt(-0.1)));
+    assert!(valid().dt(-0.1).allow_negative_dt(true).build().is_ok());
+}
+
+#[test]
+fn errors_describe_the_problem() {
+    let message = valid().kernel_radius(12).build().err().unwrap().to_string();
+    assert!(message.contains("12") && message.contains("11"), "{}", message);
+    let message = valid().size(0, 24).build().err().unwrap().to_string();
+    assert!(message.contains("0x24"), "{}", message);
+}
@@ -0,0 +1,18 @@
+//! Reading cells back out of the grid by coordinates.
+
+use simplelife::{ACTIVE_THRESHOLD, SimpleLife};
+
+#[test]
+fn alive_cells_yields_coordinates_above_the_threshold() {
+    let mut sim = SimpleLife::new(8, 6, 2, 0.1);
+    sim.set(1, 0, 0.5).unwrap();
+    sim.set(7, 2, 0.25).unwrap();
+    sim.set(3, 5, 1.0).unwrap();
+    sim.set(4, 4, ACTIVE_THRESHOLD).unwrap();
+
+    let alive: Vec<_> = sim.alive_cells(ACTIVE_THRESHOLD).collect();
+    assert_eq!(alive, [(1, 0, 0.5), (7, 2, 0.25), (3, 5, 1.0)]);
+    assert_eq!(alive.len(), sim.active_cells());
+    assert_eq!(sim.alive_cells(0.3).map(|(x, y, _)| (x, y)).collect::<Vec<_>>(), [(1, 0), (3, 5)]);
+    assert_eq!(sim.alive_cells(1.0).count(), 0);
+}
@@ -0,0 +1,73 @@
+//! The soft clamp against the hard one on grids under constant growth.
+
+use simplelife::{Clamp, Real, SOFT_CLAMP_FLOOR, SimpleLife};
+
+// Every cell at `start`, growing at a constant `rate` whatever the potential
+fn uniform(clamp: Clamp, start: Real, rate: Real) -> SimpleLife {
+    let mut sim = SimpleLife::builder()
+        .size(16, 16)
+        .kernel_radius(3)
+        .dt(0.1)
+        .clamp(clamp)
+        .build()
+        .unwrap();
+    sim.set_growth_rule(move |_| rate);
+    for y in 0..16 {
+        for x in 0..16 {
+            sim.set(x, y, start).unwrap();
+        }
+    }
+    sim
+}
+
+#[test]
+fn hard_is_the_default() {
+    assert_eq!(SimpleLife::builder().size(16, 16).kernel_radius(3).build().unwrap().clamp(), Clamp::Hard);
+    assert_eq!("soft".parse::<Clamp>().unwrap().to_string(), "soft");
+    assert!("smooth".parse::<Clamp>().is_err());
+}
+
+#[test]
+fn soft_clamp_approaches_one_asymptotically() {
+    let mut hard = uniform(Clamp::Hard, 0.5, 1.0);
+    let mut soft = uniform(Clamp::Soft, 0.5, 1.0);
+
+    // Each step closes a tenth of the remaining gap to 1
+    let mut gap: Real = 0.5;
+    for _ in 0..20 {
+        hard.update();
+        soft.update();
+        gap *= 0.9;
+        let cell = soft.get(3, 4).unwrap();
+        assert!(cell < 1.0 && (1.0 - cell - gap).abs() < 1e-5, "cell {} with {} left to 1", cell, gap);
+    }
+    assert_eq!(hard.get(3, 4), Some(1.0));
+}
+
+#[test]
+fn soft_clamp_decays_to_exactly_zero() {
+    let mut soft = uniform(Clamp::Soft, 0.5, -1.0);
+    soft.update();
+    assert!((soft.get(0, 0).unwrap() - 0.45).abs() < 1e-6);
+
+    // 0.5 * 0.9^n drops below the floor after about 125 steps
+    let steps = (1..=200_usize).find(|_| {
+        soft.update();
+        soft.get(0, 0) == Some(0.0)
+    }).unwrap();
+    assert!(soft.grid().iter().all(|&v| v == 0.0));
+    let expected = ((SOFT_CLAMP_FLOOR / 0.45).ln() / (0.9 as Real).ln()).ceil() as usize;
+    assert!(steps.abs_diff(expected) <= 1, "zero after {} more steps, expected about {}", steps, expected);
+}
+
+#[test]
+fn set_clamp_takes_effect_on_the_next_step() {
+    let mut sim = uniform(Clamp::Hard, 0.95, 1.0);
+    sim.set_clamp(Clamp::Soft);
+    sim.update();
+    assert!((sim.get(0, 0).unwrap() - 0.955).abs() < 1e-6);
+
+    sim.set_clamp(Clamp::Hard);
+    sim.update();
+    assert_eq!(sim.get(0, 0), Some(1.0));
+}
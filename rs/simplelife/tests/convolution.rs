@@ -0,0 +1,222 @@
+//! The potential at the edges and across convolution methods.
+
+use simplelife::{AUTO_FFT_RADIUS, Boundary, ConvMethod, Kernel, KernelShape, Real, SimpleLife};
+
+const WIDTH: usize = 24;
+const HEIGHT: usize = 18;
+const RADIUS: usize = 4;
+
+fn corner_cell(method: ConvMethod, boundary: Boundary) -> SimpleLife {
+    let mut sim = SimpleLife::builder()
+        .size(WIDTH, HEIGHT)
+        .kernel_radius(RADIUS)
+        .conv_method(method)
+        .boundary(boundary)
+        .build()
+        .unwrap();
+    sim.set(0, 0, 1.0).unwrap();
+    sim
+}
+
+// Kernel weight at (dx, dy) from the center, 0 outside the table
+fn weight(sim: &SimpleLife, dx: isize, dy: isize) -> Real {
+    let r = RADIUS as isize;
+    if dx.abs() > r || dy.abs() > r {
+        return 0.0;
+    }
+    sim.kernel()[((dy + r) * (2 * r + 1) + dx + r) as usize]
+}
+
+// Shortest signed offset from `from` to `to` around a ring of `size`
+fn wrapped_offset(from: usize, to: usize, size: usize) -> isize {
+    let d = (to as isize - from as isize).rem_euclid(size as isize);
+    if d > size as isize / 2 { d - size as isize } else { d }
+}
+
+#[test]
+fn single_cell_leaks_to_the_opposite_corner() {
+    for method in [ConvMethod::Direct, ConvMethod::Fft] {
+        let sim = corner_cell(method, Boundary::Toroidal);
+        let potential = sim.compute_potential();
+        let at = |x: usize, y: usize| potential[y * WIDTH + x];
+
+        // (0, 0) is one step right and down of the far corner once the grid wraps
+        let expected = weight(&sim, 1, 1);
+        assert!(expected > 0.0);
+        assert!((at(WIDTH - 1, HEIGHT - 1) - expected).abs() < 1e-6,
+            "{:?}: far corner got {}, expected {}", method, at(WIDTH - 1, HEIGHT - 1), expected);
+        assert!((at(WIDTH - 1, 0) - weight(&sim, 1, 0)).abs() < 1e-6, "{:?}: right edge", method);
+        assert!((at(0, HEIGHT - 1) - weight(&sim, 0, 1)).abs() < 1e-6, "{:?}: bottom edge", method);
+    }
+}
+
+#[test]
+fn single_cell_spreads_as_the_wrapped_kernel() {
+    for method in [ConvMethod::Direct, ConvMethod::Fft] {
+        let sim = corner_cell(method, Boundary::Toroidal);
+        let potential = sim.compute_potential();
+
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let expected = weight(&sim, wrapped_offset(x, 0, WIDTH), wrapped_offset(y, 0, HEIGHT));
+                let got = potential[y * WIDTH + x];
+                assert!((got - expected).abs() < 1e-6, "{:?}: ({}, {}) got {}, expected {}", method, x, y, got, expected);
+            }
+        }
+
+        let total: Real = potential.iter().sum();
+        assert!((total - 1.0).abs() < 1e-5, "{:?}: mass {} leaked", method, total);
+    }
+}
+
+#[test]
+fn fixed_boundary_does_not_wrap() {
+    let sim = corner_cell(ConvMethod::Direct, Boundary::Fixed);
+    let potential = sim.compute_potential();
+
+    assert_eq!(potential[HEIGHT * WIDTH - 1], 0.0);
+    assert_eq!(potential[WIDTH - 1], 0.0);
+    assert!((potential[0] - weight(&sim, 0, 0)).abs() < 1e-6);
+}
+
+#[test]
+fn fft_matches_direct_on_random_grids() {
+    for (radius, shape) in [(3, KernelShape::Linear), (13, KernelShape::Linear), (25, KernelShape::Ring { peaks: vec![0.5, 1.0] })] {
+        let build = |method| {
+            let mut sim = SimpleLife::builder()
+                .size(96, 80)
+                .kernel_radius(radius)
+                .kernel_shape(shape.clone())
+                .conv_method(method)
+                .build()
+                .unwrap();
+            sim.random_init_seeded(0.3, 0.3, 11);
+            sim
+        };
+        let direct = build(ConvMethod::Direct).compute_potential();
+        let fft = build(ConvMethod::Fft).compute_potential();
+
+        let worst = direct.iter().zip(&fft).map(|(a, b)| (a - b).abs()).fold(0.0, Real::max);
+        assert!(worst < 1e-4, "radius {}: FFT differs from direct by {}", radius, worst);
+    }
+}
+
+#[test]
+fn auto_switches_to_fft_at_the_threshold() {
+    for radius in [1, 2, AUTO_FFT_RADIUS, 8] {
+        let sim = SimpleLife::builder().size(WIDTH, HEIGHT).kernel_radius(radius).build().unwrap();
+        assert_eq!(sim.conv_method(), ConvMethod::Auto);
+        assert_eq!(sim.uses_fft(), radius >= AUTO_FFT_RADIUS, "radius {}", radius);
+    }
+
+    let fixed = corner_cell(ConvMethod::Auto, Boundary::Fixed);
+    assert!(!fixed.uses_fft());
+}
+
+// Fill every cell, edges included, so the boundary handling is exercised
+fn fill_pseudo_random(sim: &mut SimpleLife) {
+    let mut state: u32 = 12345;
+    for y in 0..sim.height() {
+        for x in 0..sim.width() {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            sim.set(x, y, (state >> 8) as Real / (1 << 24) as Real).unwrap();
+        }
+    }
+}
+
+// The direct convolution as a plain per-cell loop over the kernel table,
+// either over its non-zero entries or over the whole dense square
+fn scalar_potential(sim: &SimpleLife, skip_zeros: bool) -> Vec<Real> {
+    let (kernel_width, kernel_height) = sim.kernel_size();
+    let (rx, ry) = (kernel_width as isize / 2, kernel_height as isize / 2);
+    let mut potential = Vec::with_capacity(sim.width() * sim.height());
+    for y in 0..sim.height() as isize {
+        for x in 0..sim.width() as isize {
+            let mut sum = 0.0;
+            for (i, &k) in sim.kernel().iter().enumerate().filter(|&(_, &k)| !skip_zeros || k != 0.0) {
+                let (dx, dy) = ((i % kernel_width) as isize - rx, (i / kernel_width) as isize - ry);
+                if let Some(value) = sim.grid().get_bounded(x + dx, y + dy, sim.boundary()) {
+                    sum += value * k;
+                }
+            }
+            potential.push(sum);
+        }
+    }
+    potential
+}
+
+#[test]
+fn direct_matches_the_scalar_loop_bit_for_bit() {
+    for boundary in [Boundary::Toroidal, Boundary::Fixed, Boundary::Reflective] {
+        for radius in [1, 4, 9] {
+            let mut sim = SimpleLife::builder()
+                .size(37, 29)
+                .kernel_radius(radius)
+                .conv_method(ConvMethod::Direct)
+                .boundary(boundary)
+                .build()
+                .unwrap();
+            fill_pseudo_random(&mut sim);
+
+            let potential = sim.compute_potential();
+            let expected = scalar_potential(&sim, true);
+            assert!(potential.iter().map(|v| v.to_bits()).eq(expected.iter().map(|v| v.to_bits())),
+                "{:?} at radius {} differs from the scalar loop", boundary, radius);
+        }
+    }
+}
+
+#[test]
+fn sparse_offsets_match_the_dense_table() {
+    // Most of a thin ring's square is zeros, which the direct path never visits
+    let mut sim = SimpleLife::builder()
+        .size(48, 40)
+        .kernel_radius(12)
+        .kernel_shape(KernelShape::Ring { peaks: vec![0.0, 1.0, 0.0] })
+        .conv_method(ConvMethod::Direct)
+        .build()
+        .unwrap();
+    let zeros = sim.kernel().iter().filter(|&&k| k == 0.0).count();
+    assert!(zeros > sim.kernel().len() / 2, "only {} of {} entries are zero", zeros, sim.kernel().len());
+
+    fill_pseudo_random(&mut sim);
+    let potential = sim.compute_potential();
+    let dense = scalar_potential(&sim, false);
+    assert!(potential.iter().map(|v| v.to_bits()).eq(dense.iter().map(|v| v.to_bits())));
+}
+
+// Tent along x times a narrower tent along y: an exact outer product
+struct TentProduct;
+
+impl Kernel for TentProduct {
+    fn weight(&self, dx: f32, dy: f32, radius: f32) -> f32 {
+        (1.0 - dx.abs() / (radius + 1.0)) * (1.0 - dy.abs() / (radius + 1.0)).powi(2)
+    }
+}
+
+#[test]
+fn separable_passes_match_the_direct_sum() {
+    for boundary in [Boundary::Toroidal, Boundary::Fixed, Boundary::Reflective] {
+        for radius in [1, 6, 13] {
+            let mut sim = SimpleLife::builder()
+                .size(41, 33)
+                .kernel_radius(radius)
+                .kernel(TentProduct)
+                .conv_method(ConvMethod::Direct)
+                .boundary(boundary)
+                .build()
+                .unwrap();
+            assert!(sim.is_separable(), "radius {}", radius);
+            fill_pseudo_random(&mut sim);
+
+            let worst = sim.compute_potential().iter()
+                .zip(&scalar_potential(&sim, false))
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0, Real::max);
+            assert!(worst <= 1e-5, "{:?} at radius {}: separable differs from direct by {}", boundary, radius, worst);
+        }
+    }
+
+    // The cone is round, not a product of a row and a column
+    assert!(!SimpleLife::builder().size(41, 33).kernel_radius(6).build().unwrap().is_separable());
+}
@@ -0,0 +1,64 @@
+//! Cloned simulations and the distance between their grids.
+
+use simplelife::{Real, SimpleLife};
+
+fn seeded() -> SimpleLife {
+    let mut sim = SimpleLife::builder()
+        .size(48, 40)
+        .kernel_radius(5)
+        .seed(17)
+        .build()
+        .unwrap();
+    sim.random_init_seeded(0.3, 0.4, 17);
+    sim
+}
+
+#[test]
+fn clones_evolve_identically() {
+    let mut sim = seeded();
+    let mut clone = sim.clone();
+    assert_eq!(sim.grid_l2_distance(&clone), 0.0);
+
+    for _ in 0..10 {
+        sim.update();
+        clone.update();
+    }
+    assert_eq!(sim.grid(), clone.grid());
+
+    // The RNG state comes along too
+    let seed = sim.random_init(0.3, 0.4);
+    assert_eq!(clone.random_init(0.3, 0.4), seed);
+    assert_eq!(sim.grid(), clone.grid());
+
+    // And the returned seed replays the pattern
+    clone.clear();
+    clone.random_init_seeded(0.3, 0.4, seed);
+    assert_eq!(sim.grid(), clone.grid());
+}
+
+#[test]
+fn one_cell_perturbation_is_measured() {
+    let mut sim = seeded();
+    let mut perturbed = sim.clone();
+    let (x, y) = (24, 20);
+    perturbed.set(x, y, 1.0 - sim.get(x, y).unwrap()).unwrap();
+
+    let delta = (sim.get(x, y).unwrap() - perturbed.get(x, y).unwrap()).abs();
+    let expected = delta / ((48 * 40) as Real).sqrt();
+    assert!((sim.grid_l2_distance(&perturbed) - expected).abs() < 1e-6);
+    assert_eq!(sim.grid_l2_distance(&perturbed), perturbed.grid_l2_distance(&sim));
+
+    for _ in 0..10 {
+        sim.update();
+        perturbed.update();
+    }
+    let distance = sim.grid_l2_distance(&perturbed);
+    assert!(distance.is_finite() && distance > 0.0, "distance {} after 10 steps", distance);
+}
+
+#[test]
+#[should_panic(expected = "different sizes")]
+fn distance_needs_equal_sizes() {
+    let other = SimpleLife::builder().size(40, 40).kernel_radius(5).build().unwrap();
+    seeded().grid_l2_distance(&other);
+}
@@ -0,0 +1,60 @@
+//! The compute shader against the CPU step; skipped where no GPU is found.
+#![cfg(feature = "gpu")]
+
+use simplelife::{Boundary, Clamp, GpuLife, Real, SimError, SimpleLife};
+
+fn seeded(boundary: Boundary, clamp: Clamp) -> SimpleLife {
+    let mut sim = SimpleLife::builder()
+        .size(96, 64)
+        .kernel_radius(7)
+        .boundary(boundary)
+        .clamp(clamp)
+        .seed(5)
+        .build()
+        .unwrap();
+    sim.random_init_seeded(1.0, 0.5, 5);
+    sim
+}
+
+fn on_gpu(sim: SimpleLife) -> Option<GpuLife> {
+    match GpuLife::new(sim) {
+        Ok(gpu) => Some(gpu),
+        Err(SimError::Gpu(e)) => {
+            eprintln!("skipping, no GPU: {}", e);
+            None
+        }
+        Err(e) => panic!("{}", e),
+    }
+}
+
+#[test]
+fn gpu_steps_follow_the_cpu() {
+    for boundary in [Boundary::Toroidal, Boundary::Fixed, Boundary::Reflective] {
+        for clamp in [Clamp::Hard, Clamp::Soft] {
+            let mut cpu = seeded(boundary, clamp);
+            let Some(mut gpu) = on_gpu(cpu.clone()) else { return };
+
+            gpu.step_n(20).unwrap();
+            cpu.step_n(20);
+            let gpu = gpu.into_sim().unwrap();
+            assert_eq!(gpu.steps(), cpu.steps());
+            assert!((gpu.sim_time() - cpu.sim_time()).abs() < 1e-9);
+            let worst = gpu.grid().iter().zip(cpu.grid().iter()).map(|(a, b)| (a - b).abs()).fold(0.0, Real::max);
+            assert!(worst < 1e-3, "{:?}, {} clamp: cells differ by up to {}", boundary, clamp, worst);
+        }
+    }
+}
+
+#[test]
+fn edits_are_uploaded_before_the_next_step() {
+    let Some(mut gpu) = on_gpu(seeded(Boundary::Toroidal, Clamp::Hard)) else { return };
+    gpu.update().unwrap();
+    gpu.sim_mut().unwrap().clear();
+    gpu.update().unwrap();
+    assert_eq!(gpu.sim().unwrap().total_mass(), 0.0);
+
+    gpu.sim_mut().unwrap().set_growth_rule(|u| u);
+    assert!(gpu.update().is_ok());
+    gpu.sim_mut().unwrap().set_parameter_map(vec![0.0; 96 * 64]).unwrap();
+    assert!(matches!(gpu.update(), Err(SimError::InvalidParameter(_))));
+}
@@ -0,0 +1,208 @@
+//! Growth presets and rules, and how the simulation applies them.
+
+use std::sync::Arc;
+
+use simplelife::{GrowthPreset, GrowthRule, Real, SimError, SimpleLife, default_growth};
+
+const HAT: &str = "mexican-hat:0.3,0.05,1.5,0.3,0.15,1";
+
+// Potential with the extreme value of `preset` over `range`, sampled every 1e-4
+fn extremum(preset: &GrowthPreset, range: (Real, Real), better: impl Fn(Real, Real) -> bool) -> Real {
+    let steps = ((range.1 - range.0) * 10_000.0).round() as usize;
+    let mut best = range.0;
+    for i in 0..=steps {
+        let u = range.0 + i as Real / 10_000.0;
+        if better(preset.value(u), preset.value(best)) {
+            best = u;
+        }
+    }
+    best
+}
+
+#[test]
+fn mexican_hat_extrema() {
+    let hat: GrowthPreset = HAT.parse().unwrap();
+
+    // Same center, so the peak sits on it and the lobes sit symmetrically at
+    // mu +- d with d^2 = 2 ln(amp1 sigma2^2 / (amp2 sigma1^2)) / (1/sigma1^2 - 1/sigma2^2)
+    let (sigma1, sigma2): (Real, Real) = (0.05, 0.15);
+    let d = (2.0 * (1.5 * sigma2 * sigma2 / (sigma1 * sigma1)).ln() / (1.0 / (sigma1 * sigma1) - 1.0 / (sigma2 * sigma2))).sqrt();
+    let peak = extremum(&hat, (0.0, 1.0), |a, b| a > b);
+    let sparse = extremum(&hat, (0.0, 0.3), |a, b| a < b);
+    let crowded = extremum(&hat, (0.3, 1.0), |a, b| a < b);
+
+    assert!((peak - 0.3).abs() < 1e-3, "peak at {}", peak);
+    assert!((sparse - (0.3 - d)).abs() < 1e-3, "sparse lobe at {}, expected {}", sparse, 0.3 - d);
+    assert!((crowded - (0.3 + d)).abs() < 1e-3, "crowded lobe at {}, expected {}", crowded, 0.3 + d);
+
+    assert!((hat.value(peak) - 0.5).abs() < 1e-4);
+    assert!(hat.value(sparse) < 0.0 && hat.value(crowded) < 0.0);
+    // The lobes dip below the far tails, which is what sets it apart from a plain bell
+    assert!(hat.value(sparse) < hat.value(0.0) && hat.value(crowded) < hat.value(1.0));
+}
+
+#[test]
+fn mexican_hat_spec_round_trips() {
+    let hat: GrowthPreset = HAT.parse().unwrap();
+    assert_eq!(hat.to_string().parse::<GrowthPreset>().unwrap(), hat);
+
+    assert!("mexican-hat:0.3,0,1.5,0.3,0.15,1".parse::<GrowthPreset>().is_err());
+    assert!("mexican-hat:0.3,0.05,-1,0.3,0.15,1".parse::<GrowthPreset>().is_err());
+    assert!("mexican-hat:0.3,0.05,1.5".parse::<GrowthPreset>().is_err());
+}
+
+#[test]
+fn quadratic_defaults_match_the_default_curve() {
+    let quadratic: GrowthPreset = "quadratic:1.8,0.2".parse().unwrap();
+    for i in 0..=100 {
+        let u = i as Real / 100.0;
+        assert_eq!(quadratic.value(u), default_growth(u), "u = {}", u);
+        assert_eq!(quadratic.value(u), GrowthPreset::QuadraticWide.value(u), "u = {}", u);
+    }
+    assert_eq!(quadratic.to_string().parse::<GrowthPreset>().unwrap(), quadratic);
+    assert!("quadratic:1.8".parse::<GrowthPreset>().is_err());
+}
+
+#[test]
+fn quadratic_coefficients_can_be_tuned() {
+    let mut sim = SimpleLife::new(16, 16, 3, 0.1);
+    assert_eq!((sim.growth_a(), sim.growth_b()), (Some(1.8), Some(0.2)));
+    for i in 0..=100 {
+        let u = i as Real / 100.0;
+        assert_eq!(sim.growth_function(u), default_growth(u), "u = {}", u);
+    }
+
+    sim.set_growth_b(0.3).unwrap();
+    sim.set_growth_a(2.0).unwrap();
+    assert_eq!((sim.growth_a(), sim.growth_b()), (Some(2.0), Some(0.3)));
+    assert_eq!(sim.growth_function(0.5), 2.0 * 0.25 - 0.3);
+    assert!(matches!(sim.set_growth_b(Real::NAN), Err(SimError::InvalidParameter(_))));
+    assert_eq!(sim.growth_b(), Some(0.3));
+
+    // Another rule has no coefficients until one is set
+    sim.set_growth_rule(|u| u);
+    assert_eq!((sim.growth_a(), sim.growth_b()), (None, None));
+    sim.set_growth_b(0.1).unwrap();
+    assert_eq!((sim.growth_a(), sim.growth_b()), (Some(1.8), Some(0.1)));
+}
+
+#[test]
+fn a_growth_rule_swapped_mid_run_takes_effect_on_the_next_step() {
+    let mut sim = SimpleLife::new(48, 48, 5, 0.1);
+    sim.random_init_seeded(0.3, 0.3, 13);
+    sim.step_n(5);
+    let mut unchanged = sim.clone();
+
+    // Every cell grows by exactly dt * 0.5 under the new rule, whatever its potential
+    sim.set_growth_rule(|_| 0.5);
+    let before: Vec<Real> = sim.grid().iter().copied().collect();
+    sim.update();
+    unchanged.update();
+    for (i, (&old, &new)) in before.iter().zip(sim.grid().iter()).enumerate() {
+        let expected = (old + 0.05).min(1.0);
+        assert!((new - expected).abs() <= 1e-6, "cell {}: {} -> {}, expected {}", i, old, new, expected);
+    }
+    assert_ne!(sim.grid(), unchanged.grid());
+    assert_eq!(sim.steps(), unchanged.steps());
+}
+
+#[test]
+fn every_preset_is_pinned_at_sample_inputs() {
+    let pinned: [(&str, &[(Real, Real)]); 9] = [
+        ("quadratic-wide", &[(0.0, -0.2), (0.15, 0.0295), (0.3, 0.178), (0.5, 0.25), (1.0, -0.2)]),
+        ("quadratic-original", &[(0.0, -0.5), (0.15, -0.245), (0.3, -0.08), (0.5, 0.0), (1.0, -0.5)]),
+        ("quadratic:3,0.4", &[(0.0, -0.4), (0.15, -0.0175), (0.3, 0.23), (0.5, 0.35), (1.0, -0.4)]),
+        ("gaussian:0.15,0.015", &[(0.0, -1.0), (0.12, -0.7293294), (0.15, 1.0), (0.165, 0.2130613), (0.5, -1.0)]),
+        ("gaussian:0.3,0.1", &[(0.0, -0.977782), (0.15, -0.3506951), (0.3, 1.0), (0.5, -0.7293294), (1.0, -1.0)]),
+        ("band:0.2,0.4", &[(0.1, -1.0), (0.2, 1.0), (0.3, 1.0), (0.4, 1.0), (0.5, -1.0)]),
+        ("interval:0.25,0.35,0.2,0.5", &[(0.1, -1.0), (0.22, 0.0), (0.3, 1.0), (0.45, 0.0), (0.6, -1.0)]),
+        ("interval:0.25,0.35,0.2,0.5,0.05", &[(0.0, -0.9999999), (0.15, -0.9813491), (0.3, 0.9643391), (0.5, -0.4999908), (1.0, -1.0)]),
+        (HAT, &[(0.0, -0.1353353), (0.15, -0.5898672), (0.3, 0.5), (0.5, -0.4106091), (1.0, -1.87e-5)]),
+    ];
+    for (spec, samples) in pinned {
+        let preset: GrowthPreset = spec.parse().unwrap();
+        for &(u, expected) in samples {
+            let value = preset.value(u);
+            assert!((value - expected).abs() <= 1e-5, "{} at {}: {}, pinned at {}", spec, u, value, expected);
+        }
+    }
+}
+
+#[test]
+fn hard_interval_edges() {
+    let interval = GrowthPreset::Interval { b1: 0.25, b2: 0.35, d1: 0.2, d2: 0.5, width: 0.0 };
+    // Birth inside b1..=b2, edges included, wins over the hold band it sits in
+    for u in [0.25, 0.3, 0.35] {
+        assert_eq!(interval.value(u), 1.0, "u = {}", u);
+    }
+    // Hold in the rest of d1..=d2, edges included
+    for u in [0.2, 0.22, 0.2499, 0.3501, 0.45, 0.5] {
+        assert_eq!(interval.value(u), 0.0, "u = {}", u);
+    }
+    // Death outside both
+    for u in [0.0, 0.1999, 0.5001, 0.8, 1.0] {
+        assert_eq!(interval.value(u), -1.0, "u = {}", u);
+    }
+
+    // The simulation runs the preset as given
+    let sim = SimpleLife::builder().size(16, 16).kernel_radius(3).growth_preset(interval).build().unwrap();
+    for u in [0.1, 0.2, 0.3, 0.5, 0.6] {
+        assert_eq!(sim.growth_function(u), interval.value(u), "u = {}", u);
+    }
+}
+
+#[test]
+fn smoothed_interval_edges() {
+    let (b1, b2, d1, d2, width) = (0.25, 0.35, 0.2, 0.5, 0.02);
+    let interval = GrowthPreset::Interval { b1, b2, d1, d2, width };
+    // Well inside and outside the edges it settles on the hard values
+    for (u, expected) in [(0.3, 1.0), (0.43, 0.0), (0.0, -1.0), (0.7, -1.0)] {
+        assert!((interval.value(u) - expected).abs() < 1e-3, "u = {}: {}", u, interval.value(u));
+    }
+    // On each edge it is halfway between the two sides
+    for (u, expected) in [(b1, 0.5), (b2, 0.5), (d1, -0.5), (d2, -0.5)] {
+        assert!((interval.value(u) - expected).abs() < 1e-3, "edge {}: {}", u, interval.value(u));
+    }
+    // And changes monotonically across it
+    let rising = [b1 - width, b1, b1 + width].map(|u| interval.value(u));
+    assert!(rising[0] < rising[1] && rising[1] < rising[2], "{:?}", rising);
+    let falling = [d2 - width, d2, d2 + width].map(|u| interval.value(u));
+    assert!(falling[0] > falling[1] && falling[1] > falling[2], "{:?}", falling);
+}
+
+#[test]
+fn interval_thresholds_are_validated() {
+    assert!("interval:0.25,0.35,0.2,0.5,0.02".parse::<GrowthPreset>().is_ok());
+    assert!("interval:0.35,0.25,0.2,0.5".parse::<GrowthPreset>().is_err());
+    assert!("interval:0.25,0.35,0.5,0.2".parse::<GrowthPreset>().is_err());
+    assert!("interval:0.25,0.35,0.2,0.5,-0.1".parse::<GrowthPreset>().is_err());
+    let reversed = GrowthPreset::Interval { b1: 0.35, b2: 0.25, d1: 0.2, d2: 0.5, width: 0.0 };
+    assert!(SimpleLife::builder().size(16, 16).kernel_radius(3).growth_preset(reversed).build().is_err());
+}
+
+#[test]
+fn single_argument_rules_step_exactly_as_before() {
+    let build = || {
+        let mut sim = SimpleLife::new(40, 40, 5, 0.1);
+        sim.random_init_seeded(0.3, 0.3, 31);
+        sim
+    };
+    let mut function = build();
+    function.set_growth_rule(default_growth);
+    let mut adapted = build();
+    adapted.set_growth(GrowthRule::State(Arc::new(|_, u| default_growth(u)))).unwrap();
+
+    for step in 1..=20 {
+        // The update as it was: every cell moves by dt * growth(potential), clamped
+        let potential = function.compute_potential();
+        let expected: Vec<Real> = function.grid().iter().zip(&potential)
+            .map(|(&cell, &u)| (cell + 0.1 * default_growth(u)).clamp(0.0, 1.0))
+            .collect();
+
+        function.update();
+        adapted.update();
+        assert!(function.grid().iter().map(|v| v.to_bits()).eq(expected.iter().map(|v| v.to_bits())), "step {}", step);
+        assert!(adapted.grid().iter().map(|v| v.to_bits()).eq(expected.iter().map(|v| v.to_bits())), "step {}", step);
+    }
+    assert!(function.is_alive());
+}
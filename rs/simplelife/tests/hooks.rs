@@ -0,0 +1,89 @@
+//! Hook ordering, removal and failure handling in the HookRegistry.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use simplelife::SimpleLife;
+use simplelife::hooks::{HookError, HookFn, HookRegistry, HookStage, StepReport};
+
+// A hook that appends `label` to the shared log whenever it runs
+fn recording(log: &Rc<RefCell<Vec<&'static str>>>, label: &'static str) -> HookFn {
+    let log = Rc::clone(log);
+    Box::new(move |_, _| {
+        log.borrow_mut().push(label);
+        Ok(())
+    })
+}
+
+fn run_stage(hooks: &mut HookRegistry, stage: HookStage) -> usize {
+    let mut sim = SimpleLife::new(16, 16, 2, 0.1);
+    let report = StepReport::new(&sim, 7);
+    hooks.run(stage, &mut sim, &report).len()
+}
+
+#[test]
+fn hooks_run_by_priority_then_insertion_order() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut hooks = HookRegistry::default();
+    hooks.add_hook("late", HookStage::PostStep, 10, recording(&log, "late"));
+    hooks.add_hook("first tie", HookStage::PostStep, 0, recording(&log, "first tie"));
+    hooks.add_hook("early", HookStage::PostStep, -5, recording(&log, "early"));
+    hooks.add_hook("second tie", HookStage::PostStep, 0, recording(&log, "second tie"));
+    hooks.add_hook("third tie", HookStage::PostStep, 0, recording(&log, "third tie"));
+    hooks.add_hook("other stage", HookStage::PreStep, -100, recording(&log, "other stage"));
+
+    assert_eq!(run_stage(&mut hooks, HookStage::PostStep), 0);
+    assert_eq!(*log.borrow(), ["early", "first tie", "second tie", "third tie", "late"]);
+
+    log.borrow_mut().clear();
+    run_stage(&mut hooks, HookStage::PreStep);
+    assert_eq!(*log.borrow(), ["other stage"]);
+}
+
+#[test]
+fn removing_a_hook_drops_every_hook_of_that_name() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut hooks = HookRegistry::default();
+    hooks.add_hook("save", HookStage::PostStep, 0, recording(&log, "save a"));
+    hooks.add_hook("stats", HookStage::PostStep, 1, recording(&log, "stats"));
+    hooks.add_hook("save", HookStage::PostRender, 2, recording(&log, "save b"));
+
+    assert!(hooks.remove_hook("save"));
+    assert!(!hooks.remove_hook("save"));
+    assert!(!hooks.remove_hook("missing"));
+    assert!(hooks.has_hooks(HookStage::PostStep));
+    assert!(!hooks.has_hooks(HookStage::PostRender));
+    assert!(!hooks.has_hooks(HookStage::PreStep));
+
+    run_stage(&mut hooks, HookStage::PostStep);
+    run_stage(&mut hooks, HookStage::PostRender);
+    assert_eq!(*log.borrow(), ["stats"]);
+}
+
+#[test]
+fn a_failing_hook_is_reported_once_and_disabled() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut hooks = HookRegistry::default();
+    hooks.add_hook("broken", HookStage::PostStep, 0, Box::new(|_, _| Err(HookError("disk full".to_string()))));
+    hooks.add_hook("after", HookStage::PostStep, 1, recording(&log, "after"));
+
+    let mut sim = SimpleLife::new(16, 16, 2, 0.1);
+    let report = StepReport::new(&sim, 7);
+    let failures = hooks.run(HookStage::PostStep, &mut sim, &report);
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].to_string(), "hook 'broken' failed at step 7: disk full");
+
+    // The rest of the stage still ran, and the broken hook stays off
+    assert_eq!(run_stage(&mut hooks, HookStage::PostStep), 0);
+    assert_eq!(*log.borrow(), ["after", "after"]);
+}
+
+#[test]
+fn a_stage_whose_hooks_are_all_disabled_has_none() {
+    let mut hooks = HookRegistry::default();
+    hooks.add_hook("broken", HookStage::PreStep, 0, Box::new(|_, _| Err(HookError("disk full".to_string()))));
+    assert!(hooks.has_hooks(HookStage::PreStep));
+
+    assert_eq!(run_stage(&mut hooks, HookStage::PreStep), 1);
+    assert!(!hooks.has_hooks(HookStage::PreStep));
+}
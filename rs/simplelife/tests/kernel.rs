@@ -0,0 +1,261 @@
+//! Guardrails for the kernel table every convolution is built on.
+
+use simplelife::{BuildError, Kernel, KernelShape, Normalization, Real, SimpleLife};
+
+const RADII: [usize; 5] = [1, 2, 5, 13, 25];
+
+fn sim(radius: usize, shape: KernelShape) -> SimpleLife {
+    SimpleLife::builder()
+        .size(64, 64)
+        .kernel_radius(radius)
+        .kernel_shape(shape)
+        .seed(1)
+        .build()
+        .unwrap()
+}
+
+// Table entry at (dx, dy) from the center
+fn weight(sim: &SimpleLife, dx: isize, dy: isize) -> Real {
+    let (width, height) = sim.kernel_size();
+    let (cx, cy) = (width as isize / 2, height as isize / 2);
+    sim.kernel()[((cy + dy) * width as isize + cx + dx) as usize]
+}
+
+#[test]
+fn kernel_sums_to_one() {
+    for radius in RADII {
+        for shape in [KernelShape::Linear, KernelShape::GaussianBlob { sigma: 0.5 }] {
+            let sim = sim(radius, shape.clone());
+            let sum: Real = sim.kernel().iter().sum();
+            // Rounding grows with the number of entries added up
+            let tolerance = Real::EPSILON * sim.kernel().len() as Real;
+            assert!((sum - 1.0).abs() <= tolerance, "{:?} at radius {} sums to {}", shape, radius, sum);
+        }
+    }
+}
+
+#[test]
+fn kernel_is_radially_symmetric() {
+    for radius in RADII {
+        let sim = sim(radius, KernelShape::Linear);
+        assert_eq!(sim.kernel_size(), (2 * radius + 1, 2 * radius + 1));
+
+        let r = radius as isize;
+        for dy in -r..=r {
+            for dx in -r..=r {
+                let w = weight(&sim, dx, dy);
+                assert_eq!(w, weight(&sim, -dx, dy), "radius {}: ({}, {}) vs ({}, {})", radius, dx, dy, -dx, dy);
+                assert_eq!(w, weight(&sim, dx, -dy), "radius {}: ({}, {}) vs ({}, {})", radius, dx, dy, dx, -dy);
+                assert_eq!(w, weight(&sim, dy, dx), "radius {}: ({}, {}) vs ({}, {})", radius, dx, dy, dy, dx);
+            }
+        }
+    }
+}
+
+#[test]
+fn center_weight_is_the_maximum() {
+    for radius in RADII {
+        let sim = sim(radius, KernelShape::Linear);
+        let center = weight(&sim, 0, 0);
+        let max = sim.kernel().iter().cloned().fold(0.0, Real::max);
+        assert_eq!(center, max, "radius {}", radius);
+        assert!(sim.kernel().iter().all(|&w| w >= 0.0), "radius {} has negative weights", radius);
+    }
+}
+
+#[test]
+fn weights_do_not_grow_outward() {
+    for radius in RADII {
+        let sim = sim(radius, KernelShape::Linear);
+        let r = radius as isize;
+        for dx in 0..r {
+            assert!(weight(&sim, dx + 1, 0) <= weight(&sim, dx, 0), "radius {} rises at dx = {}", radius, dx + 1);
+        }
+    }
+}
+
+#[test]
+fn radius_one_kernel_is_the_identity() {
+    // The neighbors lie at the edge of the linear falloff, so only the center counts
+    let mut sim = sim(1, KernelShape::Linear);
+    assert_eq!(sim.kernel_size(), (3, 3));
+    assert_eq!(weight(&sim, 0, 0), 1.0);
+
+    sim.random_init_seeded(0.3, 0.3, 5);
+    let potential = sim.compute_potential();
+    assert!(potential.iter().eq(sim.grid().iter()));
+}
+
+#[test]
+fn each_normalization_scales_the_table_as_named() {
+    for radius in RADII {
+        for shape in [KernelShape::Linear, KernelShape::Gaussian { mu: 0.5, sigma: 0.15 }] {
+            let mut sim = sim(radius, shape.clone());
+            let tolerance = Real::EPSILON * sim.kernel().len() as Real;
+            let sum = |sim: &SimpleLife| sim.kernel().iter().sum::<Real>();
+            let peak = |sim: &SimpleLife| sim.kernel().iter().cloned().fold(0.0, Real::max);
+            assert!((sum(&sim) - 1.0).abs() <= tolerance, "{:?} at radius {} sums to {}", shape, radius, sum(&sim));
+
+            sim.set_normalization(Normalization::PeakToOne).unwrap();
+            assert_eq!(peak(&sim), 1.0, "{:?} at radius {}", shape, radius);
+
+            // Unscaled, the table holds the kernel function's own values
+            let scaled = sim.kernel().to_vec();
+            sim.set_normalization(Normalization::None).unwrap();
+            let raw_peak = peak(&sim);
+            assert!(raw_peak > 0.0 && raw_peak <= 1.0, "{:?} at radius {} peaks at {}", shape, radius, raw_peak);
+            assert!(sim.kernel().iter().zip(&scaled).all(|(raw, scaled)| (raw / raw_peak - scaled).abs() <= Real::EPSILON));
+        }
+    }
+}
+
+#[test]
+fn a_kernel_that_cannot_be_normalized_is_refused() {
+    // A ring far outside the radius leaves every entry at 0
+    let empty = KernelShape::Gaussian { mu: 5.0, sigma: 0.01 };
+    for normalization in [Normalization::SumToOne, Normalization::PeakToOne] {
+        let result = SimpleLife::builder()
+            .size(32, 32)
+            .kernel_radius(5)
+            .kernel_shape(empty.clone())
+            .normalization(normalization)
+            .build();
+        assert!(matches!(result, Err(BuildError::InvalidKernel(_))), "{:?}", normalization);
+    }
+
+    // Unscaled it is only useless, not invalid
+    let unscaled = SimpleLife::builder()
+        .size(32, 32)
+        .kernel_radius(5)
+        .kernel_shape(empty.clone())
+        .normalization(Normalization::None)
+        .build();
+    assert!(unscaled.unwrap().kernel().iter().all(|&w| w == 0.0));
+
+    // Setters refuse it too and keep the table they had
+    let mut sim = sim(5, KernelShape::Linear);
+    let before = sim.kernel().to_vec();
+    assert!(sim.set_kernel_shape(empty).is_err());
+    assert_eq!(sim.kernel(), before);
+    assert!(sim.kernel().iter().all(|w| w.is_finite()));
+}
+
+// A plus sign of arm length `radius`, heavier toward the center
+struct Cross;
+
+impl Kernel for Cross {
+    fn weight(&self, dx: f32, dy: f32, radius: f32) -> f32 {
+        if dx != 0.0 && dy != 0.0 {
+            return 0.0;
+        }
+        radius + 1.0 - dx.abs().max(dy.abs())
+    }
+}
+
+#[test]
+fn custom_kernel_is_normalized_to_sum_to_one() {
+    for radius in RADII {
+        let sim = SimpleLife::builder().size(64, 64).kernel_radius(radius).kernel(Cross).build().unwrap();
+        let sum: Real = sim.kernel().iter().sum();
+        let tolerance = Real::EPSILON * sim.kernel().len() as Real;
+        assert!((sum - 1.0).abs() <= tolerance, "radius {} sums to {}", radius, sum);
+
+        // Scaled, not reshaped: off the cross stays 0 and the arm keeps its ratios
+        assert_eq!(weight(&sim, 1, 1), 0.0);
+        let r = radius as isize;
+        let center = weight(&sim, 0, 0);
+        assert!((weight(&sim, r, 0) * (r + 1) as Real - center).abs() <= Real::EPSILON, "radius {}", radius);
+        assert_eq!(weight(&sim, 0, -r), weight(&sim, r, 0));
+    }
+}
+
+#[test]
+fn stretched_kernel_rotated_a_quarter_turn_is_transposed() {
+    let stretched = |rotation: f32| {
+        SimpleLife::builder()
+            .size(64, 64)
+            .kernel_radius(6)
+            .kernel_shape(KernelShape::Gaussian { mu: 0.5, sigma: 0.15 })
+            .anisotropy(2.0, rotation)
+            .build()
+            .unwrap()
+    };
+    let wide = stretched(0.0);
+    let tall = stretched(90.0);
+    assert_eq!(wide.kernel_size(), (25, 13));
+    assert_eq!(tall.kernel_size(), (13, 25));
+
+    // cos 90° is not exactly 0, so allow for rounding
+    for dy in -6..=6 {
+        for dx in -12..=12 {
+            let (w, t) = (weight(&wide, dx, dy), weight(&tall, dy, dx));
+            assert!((w - t).abs() <= 1e-6, "({}, {}) is {} but {} transposed", dx, dy, w, t);
+        }
+    }
+    assert!(weight(&wide, 6, 0) > weight(&wide, 0, 6));
+}
+
+#[test]
+fn annulus_covers_the_ring_area() {
+    use std::f64::consts::PI;
+    for (inner, outer, radius) in [(4.0, 10.0, 12), (2.0, 5.0, 5), (8.0, 20.0, 20)] {
+        let area = PI * (outer * outer - inner * inner);
+        let build = |antialias, normalization| {
+            SimpleLife::builder()
+                .size(64, 64)
+                .kernel_radius(radius)
+                .kernel_shape(KernelShape::Annulus { inner: inner as f32, outer: outer as f32, antialias })
+                .normalization(normalization)
+                .build()
+                .unwrap()
+        };
+
+        // Hard edges: every cell within the ring gets the same share
+        let hard = build(false, Normalization::SumToOne);
+        let nonzero: Vec<Real> = hard.kernel().iter().copied().filter(|&w| w != 0.0).collect();
+        let count = nonzero.len() as f64;
+        // Cells exactly on either edge count, which weighs more on small rings
+        assert!((count - area).abs() <= 0.1 * area, "ring {}..{}: {} cells, area {:.1}", inner, outer, count, area);
+        assert!(nonzero.iter().all(|&w| w == nonzero[0]));
+        assert_eq!(weight(&hard, 0, 0), 0.0);
+
+        // Antialiased, the partly covered cells make up the area between them,
+        // less the outer half cell where the ring reaches the kernel radius
+        let smooth = build(true, Normalization::None);
+        let covered: Real = smooth.kernel().iter().sum();
+        assert!((covered as f64 - area).abs() <= 0.1 * area, "ring {}..{}: covers {}, area {:.1}", inner, outer, covered, area);
+        assert!(smooth.kernel().iter().any(|&w| w > 0.0 && w < 1.0));
+    }
+}
+
+#[test]
+fn supersampled_kernel_stays_normalized_and_symmetric() {
+    for radius in RADII {
+        for shape in [KernelShape::Linear, KernelShape::Gaussian { mu: 0.5, sigma: 0.15 }] {
+            let build = |n| SimpleLife::builder()
+                .size(64, 64)
+                .kernel_radius(radius)
+                .kernel_shape(shape.clone())
+                .kernel_supersampling(n)
+                .build()
+                .unwrap();
+            let point = build(1);
+            let smooth = build(4);
+            assert_eq!(point.kernel(), sim(radius, shape.clone()).kernel(), "1 sample per axis is the original table");
+            assert_ne!(smooth.kernel(), point.kernel(), "{:?} at radius {}", shape, radius);
+
+            let sum: Real = smooth.kernel().iter().sum();
+            let tolerance = Real::EPSILON * smooth.kernel().len() as Real;
+            assert!((sum - 1.0).abs() <= tolerance, "{:?} at radius {} sums to {}", shape, radius, sum);
+
+            // A quarter turn maps (dx, dy) to (-dy, dx)
+            let r = radius as isize;
+            for dy in -r..=r {
+                for dx in -r..=r {
+                    let (w, turned) = (weight(&smooth, dx, dy), weight(&smooth, -dy, dx));
+                    assert!((w - turned).abs() <= 1e-6, "{:?} at radius {}: ({}, {}) is {} but {} turned", shape, radius, dx, dy, w, turned);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! Kernel tables written out as images and read back in as kernels.
+
+use simplelife::{KernelShape, Real, SimpleLife};
+
+fn temp_path(name: &str) -> String {
+    std::env::temp_dir().join(format!("simplelife_{}_{}", std::process::id(), name)).to_string_lossy().into_owned()
+}
+
+fn sim(radius: usize, shape: KernelShape) -> SimpleLife {
+    let mut sim = SimpleLife::builder().size(48, 48).kernel_radius(radius).kernel_shape(shape).build().unwrap();
+    sim.random_init_seeded(0.3, 0.3, 9);
+    sim
+}
+
+#[test]
+fn a_saved_kernel_loads_back_with_the_same_potential() {
+    for (name, shape) in [("cone.png", KernelShape::Linear), ("ring.pgm", KernelShape::Gaussian { mu: 0.5, sigma: 0.15 })] {
+        let original = sim(7, shape);
+        let path = temp_path(name);
+        original.save_kernel_image(&path).unwrap();
+
+        // A different radius first, which the image has to replace
+        let mut loaded = sim(3, KernelShape::Linear);
+        loaded.set_kernel_from_image(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.kernel_radius(), 7);
+        assert_eq!(loaded.kernel_size(), original.kernel_size());
+
+        // Weights went through 8 bits, so the potentials agree to about 1/255
+        let difference = original.compute_potential().iter()
+            .zip(loaded.compute_potential().iter())
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, Real::max);
+        assert!(difference < 5e-3, "{}: potentials differ by {}", name, difference);
+    }
+}
+
+#[test]
+fn an_even_sized_image_is_refused() {
+    let path = temp_path("even.pgm");
+    image::GrayImage::from_pixel(4, 5, image::Luma([200])).save(&path).unwrap();
+    let mut sim = sim(3, KernelShape::Linear);
+    let result = sim.set_kernel_from_image(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert!(result.is_err());
+    assert_eq!(sim.kernel_radius(), 3);
+}
+
+#[test]
+fn the_default_cone_is_brightest_at_the_center() {
+    for (radius, name) in [(1, "cone1.pgm"), (5, "cone5.png"), (12, "cone12.pgm")] {
+        let path = temp_path(name);
+        sim(radius, KernelShape::Linear).save_kernel_image(&path).unwrap();
+        let image = image::open(&path).unwrap().to_luma8();
+        std::fs::remove_file(&path).unwrap();
+
+        let size = 2 * radius as u32 + 1;
+        assert_eq!(image.dimensions(), (size, size), "{}", name);
+        let center = image.get_pixel(radius as u32, radius as u32)[0];
+        assert_eq!(center, 255, "{}", name);
+        // Nothing else is as bright
+        assert_eq!(image.pixels().filter(|p| p[0] == 255).count(), 1, "{}", name);
+    }
+}
+
+#[test]
+fn stretched_kernels_are_saved_at_their_table_size() {
+    let path = temp_path("stretched.png");
+    let sim = SimpleLife::builder().size(64, 64).kernel_radius(6).anisotropy(2.0, 90.0).build().unwrap();
+    sim.save_kernel_image(&path).unwrap();
+    let image = image::open(&path).unwrap().to_luma8();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(image.dimensions(), (13, 25));
+}
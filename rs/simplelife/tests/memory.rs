@@ -0,0 +1,102 @@
+//! Memory accounting: report totals and the order optional buffers are freed in.
+
+use simplelife::{ConvMethod, GrowthRule, MemoryBudget, MemoryEntry, Real, SimpleLife};
+
+#[test]
+fn totals_match_the_registered_sizes() {
+    let mut budget = MemoryBudget::default();
+    budget.register("grid", 4000);
+    budget.register_optional("cache", 1500, 0);
+    budget.register("kernel", 12);
+    assert_eq!(budget.total(), 5512);
+    assert_eq!(budget.entries(), [
+        MemoryEntry { name: "grid", bytes: 4000, shrink_priority: None },
+        MemoryEntry { name: "cache", bytes: 1500, shrink_priority: Some(0) },
+        MemoryEntry { name: "kernel", bytes: 12, shrink_priority: None },
+    ]);
+
+    let text = budget.to_string();
+    assert!(text.contains("cache") && text.contains("(optional)"), "{}", text);
+    assert!(text.lines().last().unwrap().contains("5.38 KiB"), "{}", text);
+}
+
+#[test]
+fn the_simulation_report_adds_up() {
+    let mut sim = SimpleLife::builder()
+        .size(64, 48)
+        .kernel_radius(8)
+        .conv_method(ConvMethod::Fft)
+        .build()
+        .unwrap();
+    sim.set_growth(GrowthRule::from_samples(|u| 1.8 * u * (1.0 - u) - 0.2, 256)).unwrap();
+    sim.set_parameter_map(vec![0.5; 64 * 48]).unwrap();
+    sim.random_init_seeded(0.3, 0.3, 4);
+    sim.update();
+
+    let report = sim.memory_report();
+    let sum: usize = report.entries().iter().map(|entry| entry.bytes).sum();
+    assert_eq!(report.total(), sum);
+
+    let bytes = |name: &str| report.entries().iter().find(|entry| entry.name == name).map(|entry| entry.bytes);
+    let real = std::mem::size_of::<Real>();
+    assert_eq!(bytes("grid"), Some(64 * 48 * real));
+    assert_eq!(bytes("parameter map"), Some(64 * 48 * real));
+    assert_eq!(bytes("growth table"), Some(256 * real));
+    assert!(bytes("fft spectra").is_some_and(|b| b > 0));
+    // Only owned buffers are counted, not what a front end renders into
+    assert_eq!(bytes("display buffer (per frame)"), None);
+}
+
+#[test]
+fn optional_buffers_are_freed_by_priority_until_within_budget() {
+    let mut budget = MemoryBudget::default();
+    budget.register("grid", 1000);
+    budget.register_optional("history", 300, 2);
+    budget.register_optional("cache", 200, 0);
+    budget.register_optional("rewind", 100, 2);
+    budget.register_optional("tiles", 50, 1);
+    let names = |plan: Vec<MemoryEntry>| plan.into_iter().map(|entry| entry.name).collect::<Vec<_>>();
+
+    assert!(budget.shrink_plan(1650).is_empty());
+    assert_eq!(names(budget.shrink_plan(1649)), ["cache"]);
+    assert_eq!(names(budget.shrink_plan(1450)), ["cache"]);
+    assert_eq!(names(budget.shrink_plan(1400)), ["cache", "tiles"]);
+    // Same priority: in the order they were registered
+    assert_eq!(names(budget.shrink_plan(1399)), ["cache", "tiles", "history"]);
+    // Not enough to free: everything optional goes
+    assert_eq!(names(budget.shrink_plan(10)), ["cache", "tiles", "history", "rewind"]);
+}
+
+#[test]
+fn enforcing_drops_the_fft_before_activity_tracking() {
+    let mut sim = SimpleLife::builder()
+        .size(96, 96)
+        .kernel_radius(13)
+        .conv_method(ConvMethod::Fft)
+        .build()
+        .unwrap();
+    sim.random_init_seeded(0.3, 0.3, 8);
+    let mut reference = sim.clone();
+    sim.update();
+    reference.update();
+    assert!(sim.uses_fft() && sim.activity_tracking());
+
+    let total = sim.memory_report().total();
+    assert!(sim.enforce_memory_budget(total).is_empty());
+
+    let freed = sim.enforce_memory_budget(total - 1);
+    assert_eq!(freed.iter().map(|entry| entry.name).collect::<Vec<_>>(), ["fft spectra"]);
+    assert!(!sim.uses_fft() && sim.activity_tracking());
+    assert_eq!(sim.memory_report().total(), total - freed[0].bytes);
+
+    let freed = sim.enforce_memory_budget(0);
+    assert_eq!(freed.iter().map(|entry| entry.name).collect::<Vec<_>>(), ["active tiles"]);
+    assert!(!sim.activity_tracking());
+    assert!(sim.enforce_memory_budget(0).is_empty());
+
+    // Only slower: the dynamics carry on as before
+    sim.step_n(5);
+    reference.step_n(5);
+    let drift = sim.grid().iter().zip(reference.grid().iter()).map(|(a, b)| (a - b).abs()).fold(0.0, Real::max);
+    assert!(drift < 1e-4, "drift {}", drift);
+}
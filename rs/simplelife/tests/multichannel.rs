@@ -0,0 +1,66 @@
+//! Multi-channel stepping against the single-channel update it generalizes.
+
+use simplelife::{GrowthRule, GrowthSchedule, MultiLife, Real, SimpleLife};
+
+// Two differently seeded channels with different radii
+fn channels() -> Vec<SimpleLife> {
+    [(4, 1), (7, 2)].into_iter()
+        .map(|(radius, seed)| {
+            let mut sim = SimpleLife::new(48, 48, radius, 0.1);
+            sim.random_init_seeded(0.3, 0.3, seed);
+            sim
+        })
+        .collect()
+}
+
+#[test]
+fn identity_interaction_matches_independent_updates() {
+    let mut independent = channels();
+    let mut multi = MultiLife::from_channels(channels()).unwrap();
+
+    for _ in 0..10 {
+        multi.update();
+        for sim in &mut independent {
+            sim.update();
+        }
+    }
+
+    for (c, sim) in independent.iter().enumerate() {
+        let channel = multi.channel(c);
+        assert_eq!(channel.steps(), sim.steps(), "channel {}", c);
+        assert_eq!(channel.sim_time(), sim.sim_time(), "channel {}", c);
+        let drift = channel.grid().iter().zip(sim.grid().iter()).map(|(a, b)| (a - b).abs()).fold(0.0, Real::max);
+        assert!(drift <= 1e-6, "channel {} drifted by {}", c, drift);
+    }
+}
+
+#[test]
+fn off_diagonal_weights_couple_the_channels() {
+    let mut coupled = MultiLife::from_channels(channels()).unwrap();
+    coupled.set_interaction(vec![vec![1.0, 0.5], vec![0.0, 1.0]]).unwrap();
+    let mut identity = MultiLife::from_channels(channels()).unwrap();
+    coupled.update();
+    identity.update();
+
+    // Channel 0 took in part of channel 1's potential, channel 1 nothing
+    assert_ne!(coupled.channel(0).grid().iter().collect::<Vec<_>>(), identity.channel(0).grid().iter().collect::<Vec<_>>());
+    assert!(coupled.channel(1).grid().iter().eq(identity.channel(1).grid().iter()));
+}
+
+#[test]
+fn channels_follow_their_growth_schedules() {
+    let mut multi = MultiLife::from_channels(channels()).unwrap();
+    // From step 2 on, channel 1 dies out everywhere
+    let schedule = GrowthSchedule::new().at(2, GrowthRule::Quadratic { a: 0.0, b: 1.0 });
+    multi.channel_mut(1).set_growth_schedule(schedule).unwrap();
+
+    multi.update();
+    multi.update();
+    assert!(multi.channel(1).total_mass() > 0.0);
+    for _ in 0..10 {
+        multi.update();
+    }
+    assert_eq!(multi.channel(1).total_mass(), 0.0);
+    assert!(multi.channel(0).total_mass() > 0.0);
+    assert_eq!(multi.channel(1).steps(), 12);
+}
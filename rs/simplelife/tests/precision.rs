@@ -0,0 +1,101 @@
+//! How far f32 and f64 grids drift apart over a long run.
+//!
+//! One build only has one `Real`, so the run is repeated here by a plain
+//! reference stepper in both precisions from the library's kernel table and
+//! starting grid. The library's own run is checked against the reference in
+//! its precision.
+
+use std::ops::{Add, Mul, Sub};
+
+use simplelife::{ConvMethod, Real, SimpleLife};
+
+const SIZE: usize = 32;
+const RADIUS: usize = 4;
+const DT: f64 = 0.05;
+const STEPS: usize = 1000;
+
+trait Float: Copy + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + PartialOrd {
+    fn of(x: f64) -> Self;
+    fn to_f64(self) -> f64;
+}
+
+impl Float for f32 {
+    fn of(x: f64) -> Self {
+        x as f32
+    }
+    fn to_f64(self) -> f64 {
+        f64::from(self)
+    }
+}
+
+impl Float for f64 {
+    fn of(x: f64) -> Self {
+        x
+    }
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+// Direct toroidal convolution and the default quadratic growth with a hard clamp
+fn reference<T: Float>(kernel: &[f64], grid: &[f64], steps: usize) -> Vec<f64> {
+    let kernel: Vec<T> = kernel.iter().map(|&k| T::of(k)).collect();
+    let mut grid: Vec<T> = grid.iter().map(|&v| T::of(v)).collect();
+    let (n, r, width) = (SIZE as isize, RADIUS as isize, 2 * RADIUS + 1);
+    let (zero, one) = (T::of(0.0), T::of(1.0));
+    for _ in 0..steps {
+        let mut next = grid.clone();
+        for y in 0..n {
+            for x in 0..n {
+                let mut u = zero;
+                for dy in -r..=r {
+                    for dx in -r..=r {
+                        let k = kernel[((dy + r) as usize) * width + (dx + r) as usize];
+                        u = u + k * grid[((y + dy).rem_euclid(n) * n + (x + dx).rem_euclid(n)) as usize];
+                    }
+                }
+                let growth = T::of(1.8) * u * (one - u) - T::of(0.2);
+                let cell = grid[(y * n + x) as usize] + T::of(DT) * growth;
+                next[(y * n + x) as usize] = if cell < zero { zero } else if cell > one { one } else { cell };
+            }
+        }
+        grid = next;
+    }
+    grid.into_iter().map(T::to_f64).collect()
+}
+
+fn max_difference(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y).abs()).fold(0.0, f64::max)
+}
+
+#[test]
+fn f32_and_f64_drift_over_a_thousand_steps() {
+    let mut sim = SimpleLife::builder()
+        .size(SIZE, SIZE)
+        .kernel_radius(RADIUS)
+        .conv_method(ConvMethod::Direct)
+        .dt(DT as Real)
+        .build()
+        .unwrap();
+    sim.random_init_seeded(0.3, 0.3, 3);
+    let to_f64 = |values: &[Real]| values.iter().map(|&v| v.to_f64()).collect::<Vec<f64>>();
+    let kernel = to_f64(sim.kernel());
+    let start = to_f64(&sim.grid().iter().copied().collect::<Vec<_>>());
+
+    let single = reference::<f32>(&kernel, &start, STEPS);
+    let double = reference::<f64>(&kernel, &start, STEPS);
+    sim.step_n(STEPS);
+    let library = to_f64(&sim.grid().iter().copied().collect::<Vec<_>>());
+
+    // About 1e-6 per cell here; the f64 feature is for runs far longer than this
+    let drift = max_difference(&single, &double);
+    let mass_drift = (single.iter().sum::<f64>() - double.iter().sum::<f64>()).abs();
+    println!("after {} steps f32 is off f64 by up to {:e} per cell, {:e} in total mass", STEPS, drift, mass_drift);
+    assert!(drift > 0.0 && drift < 1e-4, "drift {:e}", drift);
+    assert!(mass_drift < 1e-3, "mass drift {:e}", mass_drift);
+
+    // The library runs in its own precision, whichever that is
+    let own = if std::mem::size_of::<Real>() == 8 { &double } else { &single };
+    let error = max_difference(&library, own);
+    assert!(error < 1e-5, "the library is off its precision's reference by {:e}", error);
+}
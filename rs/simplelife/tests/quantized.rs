@@ -0,0 +1,81 @@
+//! The fixed-point stepper against the floating-point one.
+
+use simplelife::{Boundary, Clamp, QuantizedLife, Real, SimError, SimpleLife, dequantize, quantize};
+
+fn seeded(boundary: Boundary, clamp: Clamp) -> SimpleLife {
+    let mut sim = SimpleLife::builder()
+        .size(96, 64)
+        .kernel_radius(7)
+        .boundary(boundary)
+        .clamp(clamp)
+        .seed(5)
+        .build()
+        .unwrap();
+    sim.random_init_seeded(1.0, 0.5, 5);
+    sim
+}
+
+// Largest difference between two grids, in 8-bit grey levels
+fn grey_levels(a: &SimpleLife, b: &SimpleLife) -> Real {
+    a.grid().iter().zip(b.grid().iter()).map(|(x, y)| (x - y).abs()).fold(0.0, Real::max) * 255.0
+}
+
+#[test]
+fn levels_round_trip() {
+    for level in [0, 1, 12345, u16::MAX] {
+        assert_eq!(quantize(dequantize(level)), level);
+    }
+    for value in [0.0, 0.3, 0.5, 0.999, 1.0] {
+        assert!((dequantize(quantize(value)) - value).abs() <= 0.5 / 65535.0 + 1e-7);
+    }
+    assert_eq!(quantize(-0.5), 0);
+    assert_eq!(quantize(2.0), u16::MAX);
+}
+
+#[test]
+fn hundred_steps_stay_within_a_few_grey_levels() {
+    for boundary in [Boundary::Toroidal, Boundary::Fixed, Boundary::Reflective] {
+        for clamp in [Clamp::Hard, Clamp::Soft] {
+            let mut float = seeded(boundary, clamp);
+            let mut fixed = QuantizedLife::new(float.clone());
+
+            float.step_n(100);
+            fixed.step_n(100).unwrap();
+            let fixed = fixed.into_sim();
+            assert_eq!(fixed.steps(), 100);
+            assert!(float.total_mass() > 1.0, "{:?}, {} clamp: the pattern died out", boundary, clamp);
+            let diff = grey_levels(&fixed, &float);
+            assert!(diff <= 3.0, "{:?}, {} clamp: cells differ by {} grey levels", boundary, clamp, diff);
+        }
+    }
+}
+
+#[test]
+fn runs_can_switch_representations() {
+    let mut float = seeded(Boundary::Toroidal, Clamp::Hard);
+    let mut fixed = QuantizedLife::new(float.clone());
+    float.step_n(10);
+    fixed.step_n(10).unwrap();
+
+    // Back to floating point for a while, then fixed point again
+    let mut switched = fixed.into_sim();
+    switched.step_n(10);
+    let mut fixed = QuantizedLife::new(switched);
+    fixed.step_n(10).unwrap();
+    float.step_n(20);
+    assert_eq!(fixed.sim().steps(), 30);
+    assert!(grey_levels(fixed.sim(), &float) <= 3.0);
+
+    // Edits through sim_mut are quantized before the next step
+    fixed.sim_mut().clear();
+    fixed.update().unwrap();
+    assert!(fixed.levels().unwrap().iter().all(|&level| level == 0));
+}
+
+#[test]
+fn unsupported_settings_are_rejected() {
+    let mut fixed = QuantizedLife::new(seeded(Boundary::Toroidal, Clamp::Hard));
+    fixed.sim_mut().set_parameter_map(vec![0.0; 96 * 64]).unwrap();
+    assert!(matches!(fixed.update(), Err(SimError::InvalidParameter(_))));
+    assert!(SimpleLife::new_quantized(64, 64, 5, 0.1).is_ok());
+}
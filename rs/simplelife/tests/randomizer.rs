@@ -0,0 +1,132 @@
+//! The F3 randomizer: bounded jumps through parameter space and the history
+//! F4 walks back through.
+
+use simplelife::randomizer::{Bound, Bounds, History, Param, ParamSet, ParamsLog, Randomizer};
+use simplelife::{GrowthPreset, KernelShape, SimpleLife};
+
+fn lenia() -> ParamSet {
+    ParamSet {
+        growth: GrowthPreset::GaussianBell { mu: 0.15, sigma: 0.015 },
+        kernel: KernelShape::Gaussian { mu: 0.5, sigma: 0.15 },
+        dt: 0.1,
+    }
+}
+
+#[test]
+fn bounds_parse_and_clamp() {
+    let bound: Bound = "growth.sigma=0.01..0.05".parse().unwrap();
+    assert_eq!(bound, Bound { name: "growth.sigma".to_string(), min: 0.01, max: 0.05 });
+    assert!("dt=0.5..0.1".parse::<Bound>().is_err());
+    assert!("dt".parse::<Bound>().is_err());
+
+    let later: Bound = "growth.sigma=0.02..0.03".parse().unwrap();
+    let bounds = Bounds { spread: 0.25, ranges: vec![bound, "dt=-0.1..0.1".parse().unwrap(), later] };
+    // The later bound on a name wins
+    assert_eq!(bounds.clamp("growth.sigma", 0.001), 0.02);
+    assert_eq!(bounds.clamp("growth.sigma", 0.04), 0.03);
+    assert_eq!(bounds.clamp("dt", -0.5), -0.1);
+    assert_eq!(bounds.clamp("dt", 0.05), 0.05);
+    // Parameters without a bound only stay non-negative
+    assert_eq!(bounds.clamp("kernel.mu", -0.3), 0.0);
+    assert_eq!(bounds.clamp("kernel.mu", 7.0), 7.0);
+}
+
+#[test]
+fn randomized_parameters_stay_within_bounds() {
+    let bounds = Bounds {
+        spread: 2.0,
+        ranges: ["dt=0.05..0.12", "growth.mu=0.1..0.2", "kernel.sigma=0.1..0.2"].iter().map(|b| b.parse().unwrap()).collect(),
+    };
+    let mut randomizer = Randomizer::new(3, bounds, &Param::ALL);
+    let mut set = lenia();
+    for _ in 0..200 {
+        set = randomizer.randomize(&set);
+        assert!((0.05..=0.12).contains(&set.dt), "dt {}", set.dt);
+        let GrowthPreset::GaussianBell { mu, sigma } = set.growth else {
+            panic!("the growth left its family: {}", set.growth);
+        };
+        assert!((0.1..=0.2).contains(&mu), "growth mu {}", mu);
+        assert!(sigma > 0.0, "growth sigma {}", sigma);
+        let KernelShape::Gaussian { mu, sigma } = set.kernel else {
+            panic!("the kernel left its family: {}", set.kernel);
+        };
+        assert!(mu >= 0.0, "kernel mu {}", mu);
+        assert!((0.1..=0.2).contains(&sigma), "kernel sigma {}", sigma);
+    }
+}
+
+#[test]
+fn only_the_chosen_parts_change() {
+    let mut randomizer = Randomizer::new(5, Bounds::default(), &[Param::Dt]);
+    let next = randomizer.randomize(&lenia());
+    assert_ne!(next.dt, lenia().dt);
+    assert!((next.dt - 0.1).abs() <= 0.025 + 1e-6);
+    assert_eq!((&next.growth, &next.kernel), (&lenia().growth, &lenia().kernel));
+
+    // The same seed draws the same jumps
+    let again = Randomizer::new(5, Bounds::default(), &[Param::Dt]).randomize(&lenia());
+    assert_eq!(again, next);
+}
+
+#[test]
+fn fixed_quadratics_are_varied_by_their_coefficients() {
+    let set = ParamSet { growth: GrowthPreset::QuadraticWide, kernel: KernelShape::Linear, dt: 0.05 };
+    let next = Randomizer::new(1, Bounds::default(), &[Param::Growth]).randomize(&set);
+    let GrowthPreset::Quadratic { a, b } = next.growth else {
+        panic!("expected quadratic coefficients, got {}", next.growth);
+    };
+    assert!((1.35..=2.25).contains(&a) && (0.15..=0.25).contains(&b), "a={} b={}", a, b);
+}
+
+#[test]
+fn history_steps_back_most_recent_first() {
+    let mut randomizer = Randomizer::new(9, Bounds::default(), &Param::ALL);
+    let mut history = History::default();
+    assert!(history.is_empty());
+    assert_eq!(history.pop(), None);
+
+    // Three jumps leave the first three sets behind, in order
+    let mut visited = vec![lenia()];
+    for _ in 0..3 {
+        let next = randomizer.randomize(visited.last().unwrap());
+        history.push(visited.last().unwrap().clone());
+        visited.push(next);
+    }
+    assert_eq!(history.len(), 3);
+    assert_eq!(history.pop().as_ref(), Some(&visited[2]));
+    assert_eq!(history.pop().as_ref(), Some(&visited[1]));
+
+    // A jump from there goes on top of what is left
+    history.push(visited[1].clone());
+    assert_eq!(history.pop().as_ref(), Some(&visited[1]));
+    assert_eq!(history.pop().as_ref(), Some(&visited[0]));
+    assert_eq!(history.pop(), None);
+}
+
+#[test]
+fn applied_sets_are_logged_as_json_lines() {
+    let mut sim = SimpleLife::new(48, 48, 8, 0.05);
+    let set = lenia();
+    set.apply(&mut sim).unwrap();
+    assert_eq!(sim.dt(), 0.1);
+
+    let path = std::env::temp_dir().join(format!("simplelife_params_{}.jsonl", std::process::id()));
+    let mut log = ParamsLog::create(&path).unwrap();
+    log.log(&set, 0, "params_000.png").unwrap();
+    log.log(&ParamSet { dt: 0.2, ..set }, 120, "a \"quoted\" name.png").unwrap();
+    let text = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines, [
+        r#"{"step":0,"growth":"gaussian:0.15,0.015","kernel":"gaussian:0.5,0.15","dt":0.1,"thumbnail":"params_000.png"}"#,
+        r#"{"step":120,"growth":"gaussian:0.15,0.015","kernel":"gaussian:0.5,0.15","dt":0.2,"thumbnail":"a \"quoted\" name.png"}"#,
+    ]);
+}
+
+#[test]
+fn a_set_that_does_not_fit_is_refused() {
+    let mut sim = SimpleLife::new(48, 48, 8, 0.05);
+    let set = ParamSet { kernel: KernelShape::Annulus { inner: 2.0, outer: 12.0, antialias: false }, ..lenia() };
+    assert!(set.apply(&mut sim).is_err());
+}
@@ -0,0 +1,40 @@
+//! Zoomed display buffers of a region of the grid.
+
+use simplelife::{Palette, SimpleLife};
+
+fn seeded() -> SimpleLife {
+    let mut sim = SimpleLife::new(24, 16, 3, 0.1);
+    sim.random_init_seeded(0.5, 0.5, 7);
+    sim
+}
+
+#[test]
+fn whole_grid_at_scale_one_is_the_display_buffer() {
+    let sim = seeded();
+    assert_eq!(sim.create_buffer_region(0, 0, 24, 16, 1), sim.create_buffer());
+}
+
+#[test]
+fn each_cell_becomes_a_block_of_pixels() {
+    let sim = seeded();
+    let (x0, y0, w, h, scale) = (5, 3, 6, 4, 3);
+    let buffer = sim.create_buffer_region_with(Palette::Heat, x0, y0, w, h, scale);
+    assert_eq!(buffer.len(), w * scale * h * scale);
+
+    let full = sim.create_buffer_with(Palette::Heat);
+    for py in 0..h * scale {
+        for px in 0..w * scale {
+            let (x, y) = (x0 + px / scale, y0 + py / scale);
+            assert_eq!(buffer[py * w * scale + px], full[y * 24 + x], "pixel ({}, {})", px, py);
+        }
+    }
+}
+
+#[test]
+fn regions_past_the_edges_are_moved_inside() {
+    let sim = seeded();
+    assert_eq!(sim.clamp_region(20, 14, 8, 4), (16, 12, 8, 4));
+    assert_eq!(sim.clamp_region(3, 2, 100, 100), (0, 0, 24, 16));
+    assert_eq!(sim.create_buffer_region(20, 14, 8, 4, 2), sim.create_buffer_region(16, 12, 8, 4, 2));
+    assert_eq!(sim.create_buffer_region(0, 0, 50, 50, 1), sim.create_buffer());
+}
@@ -0,0 +1,83 @@
+//! Comparison reports across finished headless runs.
+
+use std::path::Path;
+use std::process::Command;
+
+use simplelife::report::{RunRecord, StatsRow, write_comparison};
+
+const PNG: &str = "data:image/png;base64,iVBORw0KGgo";
+
+fn run(dir: &Path, seed: u64, steps: usize) {
+    let output = Command::new(env!("CARGO_BIN_EXE_simplelife_static"))
+        .args(["--width", "48", "--height", "32", "--radius", "5", "--dt", "0.1", "--skip-self-test"])
+        .args(["--seed", &seed.to_string(), "--steps", &steps.to_string(), "--save-every", "10"])
+        .arg("--output-dir")
+        .arg(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn two_seeded_runs_compare_in_one_report() {
+    let root = std::env::temp_dir().join(format!("simplelife_report_{}", std::process::id()));
+    let (short, long) = (root.join("short"), root.join("long"));
+    run(&short, 3, 20);
+    run(&long, 4, 35);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_simplelife_static"))
+        .arg("--compare")
+        .arg(format!("{},{}", short.display(), long.display()))
+        .arg("--output-dir")
+        .arg(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let report = std::fs::read_to_string(root.join("simplelife_report.md")).unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+
+    let sections: Vec<&str> = report.lines().filter(|line| line.starts_with('#')).collect();
+    assert_eq!(sections, ["# SimpleLife run comparison", "## Mass", "## Outcomes", "## Final frames", "### short", "### long"]);
+    // The mass plot and both final frames, each a complete PNG
+    assert_eq!(report.matches("data:image/png;base64,").count(), 3);
+    assert_eq!(report.matches(PNG).count(), 3);
+
+    // The runs are compared where the shorter one ended
+    assert!(report.contains("| Mass at step 20 |"));
+    let rows: Vec<&str> = report.lines().filter(|line| line.starts_with("| short ") || line.starts_with("| long ")).collect();
+    assert_eq!(rows.len(), 2);
+    assert!(rows[0].contains(" | 20 | "), "{}", rows[0]);
+    assert!(rows[1].contains(" | 35 | "), "{}", rows[1]);
+}
+
+#[test]
+fn missing_statistics_and_frames_are_noted() {
+    let row = |step, mass, active_cells| StatsRow { step, mass, active_cells };
+    let runs = [
+        RunRecord { name: "dies".to_string(), rows: vec![row(0, 10.0, 40), row(5, 2.0, 8), row(8, 0.0, 0)], final_frame: None },
+        RunRecord { name: "longer".to_string(), rows: vec![row(0, 10.0, 40), row(6, 12.0, 45), row(12, 13.0, 50)], final_frame: None },
+        RunRecord { name: "empty".to_string(), rows: Vec::new(), final_frame: None },
+    ];
+    assert_eq!(runs[0].extinction_step(), Some(8));
+    assert_eq!(runs[1].at(8), Some(&row(6, 12.0, 45)));
+
+    let path = std::env::temp_dir().join(format!("simplelife_report_missing_{}.md", std::process::id()));
+    let comparison = write_comparison(&runs, &path).unwrap();
+    let report = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // Only the plot, aligned at the end of the shorter run with statistics
+    assert_eq!(comparison.common_step, Some(8));
+    assert_eq!(comparison.images, 1);
+    assert_eq!(report.matches(PNG).count(), 1);
+    assert!(report.contains("| dies | `#4e79a7` | 8 | 0.000 | 0 | 0.000 | extinct at step 8 |"));
+    assert!(report.contains("| longer | `#f28e2b` | 12 | 13.000 | 50 | 12.000 | alive |"));
+    assert!(report.contains("| empty | `#e15759` | - | - | - | - | no statistics |"));
+    assert_eq!(report.matches("_No frame saved._").count(), 3);
+}
+
+#[test]
+fn a_run_without_statistics_is_an_error() {
+    let dir = std::env::temp_dir().join(format!("simplelife_report_nothing_{}", std::process::id()));
+    assert!(RunRecord::load(&dir).is_err());
+}
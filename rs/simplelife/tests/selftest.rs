@@ -0,0 +1,83 @@
+//! The startup self-test against configurations that are known to be broken.
+
+use simplelife::selftest::{SelfTestCheck, self_test};
+use simplelife::{KernelShape, Normalization, Real, SimpleLife};
+
+fn sim() -> SimpleLife {
+    SimpleLife::builder().size(64, 64).kernel_radius(8).build().unwrap()
+}
+
+fn failed(checks: &[SelfTestCheck]) -> Vec<&'static str> {
+    checks.iter().filter(|check| !check.passed).map(|check| check.name).collect()
+}
+
+#[test]
+fn runs_on_a_clone() {
+    let sim = sim();
+    self_test(&sim, 10);
+    assert_eq!(sim.steps(), 0);
+    assert!(sim.grid().iter().all(|&v| v == 0.0));
+}
+
+#[test]
+fn is_deterministic() {
+    let a: Vec<_> = self_test(&sim(), 20).into_iter().map(|check| (check.passed, check.message)).collect();
+    let b: Vec<_> = self_test(&sim(), 20).into_iter().map(|check| (check.passed, check.message)).collect();
+    assert_eq!(a, b);
+}
+
+#[test]
+fn runs_at_a_fixed_small_size() {
+    let large = SimpleLife::builder().size(256, 192).kernel_radius(8).build().unwrap();
+    let a: Vec<_> = self_test(&large, 20).into_iter().map(|check| (check.passed, check.message)).collect();
+    let b: Vec<_> = self_test(&sim(), 20).into_iter().map(|check| (check.passed, check.message)).collect();
+    assert_eq!(a, b);
+}
+
+#[test]
+fn fits_a_kernel_wider_than_the_test_grid() {
+    let sim = SimpleLife::builder().size(128, 128).kernel_radius(40).build().unwrap();
+    assert!(!failed(&self_test(&sim, 1)).contains(&"grid stays finite"));
+}
+
+#[test]
+fn accepts_every_normalization() {
+    for normalization in [Normalization::PeakToOne, Normalization::None] {
+        let sim = SimpleLife::builder().size(64, 64).kernel_radius(8).normalization(normalization).build().unwrap();
+        let checks = self_test(&sim, 1);
+        assert!(!checks.iter().any(|check| check.name == "kernel normalized"), "{:?}", normalization);
+        assert!(!failed(&checks).contains(&"kernel weights finite"), "{:?}", normalization);
+    }
+}
+
+#[test]
+fn accepts_unnormalized_kernel_weights() {
+    let sim = SimpleLife::builder().size(64, 64).kernel_radius(8).add_kernel(KernelShape::Linear, 0.5).build().unwrap();
+    assert!(!failed(&self_test(&sim, 1)).contains(&"kernel normalized"));
+}
+
+#[test]
+fn diagnoses_extinction() {
+    let mut sim = sim();
+    sim.set_growth_rule(|_| -1.0);
+    let checks = self_test(&sim, 20);
+    assert_eq!(failed(&checks), ["pattern survives"]);
+    assert!(checks.iter().any(|check| check.message.contains("negative for every potential")));
+}
+
+#[test]
+fn diagnoses_saturation() {
+    let mut sim = sim();
+    sim.set_growth_rule(|_| 1.0);
+    sim.set_dt(0.5).unwrap();
+    let checks = self_test(&sim, 10);
+    assert_eq!(failed(&checks), ["pattern does not saturate"]);
+    assert!(checks.iter().any(|check| check.message.contains("dt 0.5")));
+}
+
+#[test]
+fn diagnoses_nan() {
+    let mut sim = sim();
+    sim.set_growth_rule(|_| Real::NAN);
+    assert!(failed(&self_test(&sim, 10)).contains(&"grid stays finite"));
+}
@@ -0,0 +1,20 @@
+//! The statistics CSV written by the headless runner.
+
+use simplelife::SimpleLife;
+use simplelife::recorder::StatsLogger;
+
+#[test]
+fn time_is_the_last_column() {
+    let path = std::env::temp_dir().join(format!("simplelife_stats_{}.csv", std::process::id()));
+    let mut sim = SimpleLife::new(32, 32, 4, 0.25);
+    let mut stats = StatsLogger::create(&path).unwrap();
+    stats.log(&sim, 0).unwrap();
+    sim.update();
+    sim.update();
+    stats.log(&sim, 2).unwrap();
+    let text = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines, ["step,mass,active_cells,com_x,com_y,time", "0,0,0,,,0", "2,0,0,,,0.5"]);
+}
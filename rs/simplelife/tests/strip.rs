@@ -0,0 +1,71 @@
+//! Frame strips: several steps of a run side by side in one labelled PNG.
+
+use simplelife::recorder::{StripOptions, export_strip};
+use simplelife::{Palette, SimError, SimpleLife};
+
+fn seeded() -> SimpleLife {
+    let mut sim = SimpleLife::new(48, 32, 5, 0.1);
+    sim.random_init_seeded(0.4, 0.5, 11);
+    sim
+}
+
+#[test]
+fn three_steps_side_by_side() {
+    let path = std::env::temp_dir().join(format!("simplelife_strip_{}.png", std::process::id()));
+    let options = StripOptions { thumbnail_height: 64, palette: Palette::Heat, margin: 8 };
+    let mut sim = seeded();
+    // Out of order, with a duplicate and a step past the end of the run
+    let strip = export_strip(&mut sim, &[20, 0, 10, 20, 500], 100, &path, options).unwrap();
+
+    // 48x32 grows to 96x64 thumbnails, each wider than its "step N" label
+    assert_eq!(strip.frames.iter().map(|frame| frame.step).collect::<Vec<_>>(), [0, 10, 20]);
+    assert_eq!(strip.skipped, [500]);
+    assert_eq!((strip.width, strip.height), (8 + 3 * (96 + 8), 8 + 64 + 8 + 7 + 8));
+    assert_eq!(sim.steps(), 20);
+
+    let image = image::open(&path).unwrap().to_rgb8();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(image.dimensions(), (strip.width as u32, strip.height as u32));
+
+    let mut reference = seeded();
+    for (i, frame) in strip.frames.iter().enumerate() {
+        assert_eq!((frame.x, frame.y), (8 + i * 104, 8));
+        // "step 0" is 35 pixels wide and "step 10" 41, centered under the thumbnail
+        let label_width = if frame.step == 0 { 35 } else { 41 };
+        assert_eq!((frame.label_x, frame.label_y), (frame.x + (96 - label_width) / 2, 80));
+        // The top of the "t" in "step" sits one glyph in
+        assert_eq!(image.get_pixel((frame.label_x + 7) as u32, frame.label_y as u32).0, [255, 255, 255]);
+        assert_eq!(image.get_pixel(frame.label_x as u32, frame.label_y as u32).0, [0, 0, 0]);
+
+        // Every cell is a 2x2 block of its color
+        reference.step_n((frame.step - reference.steps()) as usize);
+        let buffer = reference.create_buffer_with(Palette::Heat);
+        let (gx, gy) = (17, 9);
+        let color = buffer[gy * 48 + gx];
+        let pixel = image.get_pixel((frame.x + 2 * gx + 1) as u32, (frame.y + 2 * gy + 1) as u32).0;
+        assert_eq!(pixel, [(color >> 16) as u8, (color >> 8) as u8, color as u8], "step {}", frame.step);
+    }
+}
+
+#[test]
+fn nothing_to_draw_is_an_error() {
+    let path = std::env::temp_dir().join("simplelife_strip_never_written.png");
+    let mut sim = seeded();
+    sim.step_n(5);
+    let result = export_strip(&mut sim, &[0, 200], 100, &path, StripOptions::default());
+    assert!(matches!(result, Err(SimError::InvalidParameter(_))));
+    assert!(!path.exists());
+}
+
+#[test]
+fn keeps_stepping_after_extinction() {
+    let path = std::env::temp_dir().join(format!("simplelife_strip_extinct_{}.png", std::process::id()));
+    let mut sim = seeded();
+    sim.set_growth_rule(|_| -1.0);
+    let strip = export_strip(&mut sim, &[5, 40], 100, &path, StripOptions::default()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // The pattern is gone within a few steps, but the last frame is still step 40
+    assert_eq!(strip.frames.iter().map(|frame| frame.step).collect::<Vec<_>>(), [5, 40]);
+    assert_eq!(sim.steps(), 40);
+}
@@ -0,0 +1,164 @@
+//! Steps through the reused buffers against doing every step from scratch.
+
+use std::time::Duration;
+
+use simplelife::{BuildError, ConvMethod, Palette, Real, SimError, SimpleLife};
+
+const STEPS: usize = 30;
+
+fn seeded(method: ConvMethod) -> SimpleLife {
+    let mut sim = SimpleLife::builder()
+        .size(64, 48)
+        .kernel_radius(8)
+        .conv_method(method)
+        .seed(3)
+        .build()
+        .unwrap();
+    sim.random_init_seeded(0.4, 0.5, 3);
+    sim
+}
+
+// One step with a freshly allocated potential, the way update used to work
+fn allocating_step(sim: &mut SimpleLife) {
+    let potential = sim.compute_potential();
+    for (i, &u) in potential.iter().enumerate() {
+        let (x, y) = (i % sim.width(), i / sim.width());
+        let cell = sim.get_unchecked(x, y) + sim.dt() * sim.growth_function(u);
+        sim.set_unchecked(x, y, cell.clamp(0.0, 1.0));
+    }
+}
+
+#[test]
+fn reused_buffers_match_allocating_steps() {
+    for method in [ConvMethod::Direct, ConvMethod::Fft] {
+        let mut reused = seeded(method);
+        let mut allocating = seeded(method);
+
+        for step in 0..STEPS {
+            reused.update();
+            allocating_step(&mut allocating);
+            assert!(reused.grid().iter().map(|v| v.to_bits()).eq(allocating.grid().iter().map(|v| v.to_bits())),
+                "{:?}: grids differ after step {}", method, step + 1);
+        }
+    }
+}
+
+#[test]
+fn display_buffer_can_be_reused() {
+    let mut sim = seeded(ConvMethod::Auto);
+    let mut buffer = vec![0; sim.width() * sim.height()];
+
+    for _ in 0..3 {
+        sim.update();
+        for palette in [Palette::Blue, Palette::Grayscale] {
+            sim.create_buffer_into(palette, &mut buffer);
+            assert_eq!(buffer, sim.create_buffer_with(palette));
+        }
+    }
+}
+
+#[test]
+fn steps_and_sim_time_follow_updates() {
+    let mut sim = seeded(ConvMethod::Direct);
+    assert_eq!((sim.steps(), sim.sim_time()), (0, 0.0));
+
+    sim.step_n(3);
+    assert_eq!(sim.steps(), 3);
+    assert!((sim.sim_time() - 3.0 * 0.05).abs() < 1e-6);
+
+    // An adaptive step still covers one dt, and a new dt only counts from then on
+    sim.update_adaptive(0.01).unwrap();
+    sim.set_dt(0.1).unwrap();
+    sim.update();
+    assert_eq!(sim.steps(), 5);
+    assert!((sim.sim_time() - (4.0 * 0.05 + 0.1)).abs() < 1e-6, "t = {}", sim.sim_time());
+}
+
+#[test]
+fn negative_dt_needs_the_opt_in() {
+    let mut sim = seeded(ConvMethod::Direct);
+    assert!(matches!(sim.set_dt(-0.05), Err(SimError::InvalidParameter(_))));
+    assert!(sim.set_dt(Real::NAN).is_err());
+    sim.set_dt(0.0).unwrap();
+    assert_eq!(sim.dt(), 0.0);
+
+    let mut sim = SimpleLife::builder().size(32, 32).allow_negative_dt(true).build().unwrap();
+    sim.set_dt(-0.05).unwrap();
+    assert_eq!(sim.dt(), -0.05);
+
+    // The builder asks for the same opt-in
+    assert!(matches!(SimpleLife::builder().size(32, 32).dt(-0.05).build(), Err(BuildError::NegativeDt(dt)) if dt == -0.05));
+    let sim = SimpleLife::builder().size(32, 32).dt(-0.05).allow_negative_dt(true).build().unwrap();
+    assert_eq!(sim.dt(), -0.05);
+}
+
+#[test]
+fn zero_dt_skips_the_step() {
+    let mut sim = seeded(ConvMethod::Direct);
+    sim.set_dt(0.0).unwrap();
+    let before = sim.grid().clone();
+    assert!(sim.update());
+    assert_eq!(sim.grid(), &before);
+    assert_eq!((sim.steps(), sim.sim_time()), (1, 0.0));
+}
+
+// Every cell at 0.5 growing at a constant `rate` whatever the potential
+fn constant_growth(rate: f32, dt: f32) -> SimpleLife {
+    let mut sim = SimpleLife::builder().size(16, 16).kernel_radius(3).dt(dt as _).build().unwrap();
+    sim.set_growth_rule(move |_| rate as _);
+    for y in 0..16 {
+        for x in 0..16 {
+            sim.set(x, y, 0.5).unwrap();
+        }
+    }
+    sim
+}
+
+#[test]
+fn adaptive_step_is_a_single_substep_when_stable() {
+    let mut sim = constant_growth(1.0, 0.01);
+    assert_eq!(sim.update_adaptive(0.05).unwrap(), 1);
+    assert!((sim.get(3, 3).unwrap() - 0.51).abs() < 1e-6);
+}
+
+#[test]
+fn adaptive_substeps_cover_the_same_time() {
+    let mut sim = constant_growth(1.0, 0.1);
+    // 0.1 halves to 0.00625 before a substep changes cells by at most 0.01
+    assert_eq!(sim.update_adaptive(0.01).unwrap(), 16);
+    assert!((sim.get(3, 3).unwrap() - 0.6).abs() < 1e-5);
+    assert_eq!(sim.steps(), 1);
+    assert!((sim.sim_time() - 0.1).abs() < 1e-6);
+
+    // Substeps that don't divide dt evenly still add up to it
+    let mut sim = constant_growth(-1.0, 0.1);
+    sim.set_growth_rule(|u| if u > 0.45 { -3.0 } else { -1.0 });
+    sim.update_adaptive(0.04).unwrap();
+    assert!(sim.grid().iter().all(|&v| v < 0.5 && v > 0.0));
+}
+
+#[test]
+fn adaptive_step_with_zero_dt_only_counts() {
+    let mut sim = constant_growth(1.0, 0.0);
+    assert_eq!(sim.update_adaptive(0.01).unwrap(), 0);
+    assert_eq!(sim.steps(), 1);
+    assert!(sim.grid().iter().all(|&v| v == 0.5));
+}
+
+#[test]
+fn adaptive_step_rejects_a_bad_max_delta() {
+    let mut sim = constant_growth(1.0, 0.1);
+    for max_delta in [0.0, -0.01, f32::NAN, f32::INFINITY] {
+        assert!(matches!(sim.update_adaptive(max_delta as _), Err(SimError::InvalidParameter(_))), "{}", max_delta);
+    }
+    assert_eq!(sim.steps(), 0);
+}
+
+#[test]
+fn last_step_duration_follows_updates() {
+    let mut sim = seeded(ConvMethod::Direct);
+    assert_eq!(sim.last_step_duration(), Duration::ZERO);
+
+    sim.update();
+    assert!(sim.last_step_duration() > Duration::ZERO);
+}
@@ -0,0 +1,55 @@
+//! Local variance ("temperature") against a brute-force computation.
+
+use simplelife::hooks::StepReport;
+use simplelife::{Real, SimpleLife};
+
+// Variance of the (2r+1)x(2r+1) window around every cell, wrapping around the edges
+fn brute_force(sim: &SimpleLife, radius: usize) -> Vec<Real> {
+    let (width, height) = (sim.width(), sim.height());
+    let r = radius as isize;
+    let mut variance = Vec::with_capacity(width * height);
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            let mut values = Vec::new();
+            for dy in -r..=r {
+                for dx in -r..=r {
+                    let (wx, wy) = ((x + dx).rem_euclid(width as isize), (y + dy).rem_euclid(height as isize));
+                    values.push(sim.get_unchecked(wx as usize, wy as usize));
+                }
+            }
+            let n = values.len() as Real;
+            let mean = values.iter().sum::<Real>() / n;
+            variance.push(values.iter().map(|v| (v - mean) * (v - mean)).sum::<Real>() / n);
+        }
+    }
+    variance
+}
+
+#[test]
+fn local_variance_matches_brute_force() {
+    let mut sim = SimpleLife::new(23, 17, 3, 0.1);
+    sim.random_init_seeded(0.6, 0.8, 19);
+    // Windows smaller than, close to and wider than the grid
+    for radius in [0, 1, 2, 5, 12] {
+        let fast = sim.local_variance(radius);
+        let slow = brute_force(&sim, radius);
+        for (i, (&f, &s)) in fast.iter().zip(&slow).enumerate() {
+            assert!((f - s).abs() < 1e-5, "radius {} cell {}: {} vs {}", radius, i, f, s);
+        }
+
+        let mean = slow.iter().sum::<Real>() / slow.len() as Real;
+        assert!((sim.mean_local_variance(radius) - mean).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn step_reports_carry_the_variance_on_request() {
+    let mut sim = SimpleLife::new(32, 32, 4, 0.1);
+    sim.random_init_seeded(0.5, 0.5, 3);
+    let report = StepReport::new(&sim, 0);
+    assert_eq!(report.mean_local_variance, None);
+
+    let report = report.with_local_variance(&sim, 2);
+    assert_eq!(report.mean_local_variance, Some(sim.mean_local_variance(2)));
+    assert!(report.mean_local_variance.is_some_and(|v: Real| v > 0.0));
+}
@@ -0,0 +1,37 @@
+//! Fitting a buffer into a target of another size, as the viewer's presenter does.
+
+use simplelife::{ScaleMode, Viewport};
+
+fn letterbox(src: (usize, usize), dst: (usize, usize)) -> Viewport {
+    Viewport::fit(src.0, src.1, dst.0, dst.1, ScaleMode::Letterbox)
+}
+
+#[test]
+fn stretch_fills_the_target() {
+    assert_eq!(Viewport::fit(640, 360, 300, 300, ScaleMode::Stretch), Viewport { x: 0, y: 0, width: 300, height: 300 });
+    assert_eq!(Viewport::fit(10, 10, 7, 900, ScaleMode::Stretch), Viewport { x: 0, y: 0, width: 7, height: 900 });
+}
+
+#[test]
+fn same_aspect_scales_to_the_whole_target() {
+    assert_eq!(letterbox((400, 400), (400, 400)), Viewport { x: 0, y: 0, width: 400, height: 400 });
+    assert_eq!(letterbox((320, 180), (1280, 720)), Viewport { x: 0, y: 0, width: 1280, height: 720 });
+    assert_eq!(letterbox((1280, 720), (640, 360)), Viewport { x: 0, y: 0, width: 640, height: 360 });
+}
+
+#[test]
+fn wider_buffers_get_bars_above_and_below() {
+    // 16:9 into a square window: full width, centered vertically
+    assert_eq!(letterbox((640, 360), (400, 400)), Viewport { x: 0, y: 87, width: 400, height: 225 });
+}
+
+#[test]
+fn taller_buffers_get_bars_left_and_right() {
+    assert_eq!(letterbox((200, 400), (800, 600)), Viewport { x: 250, y: 0, width: 300, height: 600 });
+}
+
+#[test]
+fn extreme_aspects_keep_at_least_a_pixel() {
+    assert_eq!(letterbox((1000, 1), (100, 100)), Viewport { x: 0, y: 49, width: 100, height: 1 });
+    assert_eq!(letterbox((1, 1000), (100, 100)), Viewport { x: 49, y: 0, width: 1, height: 100 });
+}